@@ -1,16 +1,110 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use argon2::{
     password_hash::{rand_core::OsRng as argonOsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2}; //Argon2 hashing algorithm for hashing and verification
 use lazy_static::lazy_static;
 use regex::Regex; // validating user inputs like usernames and passwords
-use rpassword::read_password; // hidden password entry for CLI
-use std::{sync::{Arc, Mutex}, io::{self, Write}}; // reading inputs and printing prompts
+use std::{sync::{Arc, Mutex}, io::{self, BufRead, Read, Write}}; // reading inputs and printing prompts
 use zeroize::{Zeroize, Zeroizing}; // used for sensitive data are wiped from the memory after use
 use rusqlite::{params, Connection, OptionalExtension}; // handle for executing SQL queries
 
 use crate::db;
 use crate::logger;
+use crate::function::prompt_input;
+
+
+/*------------------------ Hidden secret entry ---------------------*/
+
+// Longest password, PIN or recovery code accepted at any prompt
+pub const MAX_SECRET_LEN: usize = 1024;
+
+/* Reads one hidden line from the terminal, newline removed. Entry stops at
+   MAX_SECRET_LEN bytes: anything longer is an error, the rest of the line is
+   thrown away without being stored, and what was read is wiped. Elsewhere
+   rpassword reads the whole line and the same limit is checked afterwards. */
+pub fn read_secret() -> Result<Zeroizing<String>> {
+    #[cfg(unix)]
+    {
+        let tty = std::fs::File::open("/dev/tty").context("No terminal for hidden input")?;
+        let _echo_off = hidden_tty::EchoOff::new(&tty)?;
+        read_secret_from(&mut io::BufReader::new(&tty), MAX_SECRET_LEN)
+    }
+    #[cfg(not(unix))]
+    {
+        let raw = Zeroizing::new(rpassword::read_password()?);
+        read_secret_from(&mut raw.as_bytes(), MAX_SECRET_LEN)
+    }
+}
+
+// The capped line reader behind read_secret; never holds more than max_len + 1 bytes
+pub fn read_secret_from(reader: &mut impl BufRead, max_len: usize) -> Result<Zeroizing<String>> {
+    // Sized up front so the buffer is never reallocated, leaving no stray copies
+    let mut buf = Zeroizing::new(Vec::with_capacity(max_len + 2));
+    reader.by_ref().take(max_len as u64 + 1).read_until(b'\n', &mut buf)?;
+
+    if buf.len() > max_len && buf.last() != Some(&b'\n') {
+        // Skip the rest of the line in place
+        loop {
+            let chunk = reader.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+            match chunk.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    reader.consume(i + 1);
+                    break;
+                }
+                None => {
+                    let n = chunk.len();
+                    reader.consume(n);
+                }
+            }
+        }
+        bail!("Input too long (max {} bytes).", max_len);
+    }
+
+    while matches!(buf.last(), Some(b'\n' | b'\r')) {
+        buf.pop();
+    }
+    let text = std::str::from_utf8(&buf).context("Input is not valid UTF-8")?;
+    Ok(Zeroizing::new(text.to_string()))
+}
+
+#[cfg(unix)]
+mod hidden_tty {
+    use std::fs::File;
+    use std::io;
+    use std::process::{Command, Stdio};
+
+    // Terminal echo stays off until this is dropped; Enter is still echoed
+    pub struct EchoOff {
+        tty: File,
+        saved: String,
+    }
+
+    // Runs stty against the terminal itself, returning what it printed
+    fn stty(tty: &File, args: &[&str]) -> io::Result<String> {
+        let out = Command::new("stty").args(args).stdin(Stdio::from(tty.try_clone()?)).output()?;
+        if !out.status.success() {
+            return Err(io::Error::other(format!("stty {} failed", args.join(" "))));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+
+    impl EchoOff {
+        pub fn new(tty: &File) -> io::Result<Self> {
+            let saved = stty(tty, &["-g"])?;
+            stty(tty, &["-echo", "echonl"])?;
+            Ok(Self { tty: tty.try_clone()?, saved })
+        }
+    }
+
+    impl Drop for EchoOff {
+        fn drop(&mut self) {
+            let _ = stty(&self.tty, &[self.saved.as_str()]);
+        }
+    }
+}
 
 
 /*------------------------ Registration---------------------*/
@@ -61,12 +155,11 @@ pub fn register_user(conn: &mut Connection, acting_user: Option<(&str, &str)>) -
     //Get username for the new account
     print!("Enter new username (3–32 chars, letters/digits/_ only): ");
     io::stdout().flush().ok();
-    let mut username = String::new();
-    if io::stdin().read_line(&mut username).is_err() {
-        println!("Failed to read username input.");
+    let Some(username) = prompt_input() else {
+        println!("Input closed. Registration cancelled.");
         return Ok(());
-    }
-    let username = username.trim();
+    };
+    let username = username.as_str();
 
     if !username_is_valid(username) {
         println!("Invalid username format.");
@@ -78,36 +171,26 @@ pub fn register_user(conn: &mut Connection, acting_user: Option<(&str, &str)>) -
     }
 
     // Determine the new user’s role based on who is creating it
-    let new_role = match acting_role.as_str() {
-        "admin" => {
-            // Admins can create any valid role type
-            print!("Enter role [homeowner | technician]: ");
-            io::stdout().flush().ok();
-            let mut role_input = String::new();
-            if io::stdin().read_line(&mut role_input).is_err() {
-                println!("Failed to read role input.");
-                return Ok(());
-            }
-            let r = role_input.trim().to_lowercase();
-           match r.as_str() {
-            "homeowner" | "technician" => r, // allowed roles
-            _ => {
-                println!("Invalid role. Admins can only create homeowners or technicians.");
-                return Ok(()); // stop registration here
-                }
-            }
-        }
-        "homeowner" | "technician" => {
+    let requested_role = if acting_role == "admin" {
+        // Admins can create any valid role type
+        print!("Enter role [homeowner | technician]: ");
+        io::stdout().flush().ok();
+        let Some(role_input) = prompt_input() else {
+            println!("Input closed. Registration cancelled.");
+            return Ok(());
+        };
+        Some(role_input.trim().to_lowercase())
+    } else {
         println!("{acting_role}s may only create guest accounts.");
-        "guest".to_string()
-    }
-        _ => unreachable!(), // already validated above
+        None
     };
-
-    if new_role == "admin" {
-        println!("Creation of admin accounts is disabled");
-        return Ok(());
+    let new_role = match role_for_new_account(&acting_role, requested_role.as_deref()) {
+        Ok(r) => r,
+        Err(e) => {
+            println!("{e}");
+            return Ok(()); // stop registration here
         }
+    };
 
     // Validate role choice
     if !role_is_valid(&new_role) {
@@ -115,24 +198,32 @@ pub fn register_user(conn: &mut Connection, acting_user: Option<(&str, &str)>) -
         return Ok(());
     }
 
+    // Resolve the guest's homeowner before asking for a PIN, so a lapsed grant stops here
+    let homeowner_id_opt = if new_role == "guest" {
+        match guest_homeowner_id(conn, acting_username, &acting_role) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                println!("{e}");
+                return Ok(());
+            }
+        }
+    } else {
+        None
+    };
+
     // Prompt for credential (password or PIN)
     let credential_label = if new_role == "guest" { "PIN" } else { "Password" };
 
     print!("Enter {credential_label}: ");
     io::stdout().flush().ok();
-    let password = {
-        let raw = read_password()?;
-        Zeroizing::new(raw.trim_end_matches(['\r', '\n']).to_string())
+    let password = match read_secret() {
+        Ok(p) => p,
+        Err(e) => {
+            println!("{credential_label}: {e}");
+            return Ok(());
+        }
     };
 
-
-    // Hard cap to prevent resource abuse (e.g., extremely long inputs)
-    const MAX_SECRET_LEN: usize = 1024;
-    if password.len() > MAX_SECRET_LEN {
-        println!("{} too long (max {}).", credential_label, MAX_SECRET_LEN);
-        return Ok(());
-    }
-
         // If registering a guest, enforce PIN policy
     if new_role == "guest" {
     // numeric-only, min 6 digits. Adjust MIN_PIN_LEN to taste.
@@ -173,10 +264,7 @@ pub fn register_user(conn: &mut Connection, acting_user: Option<(&str, &str)>) -
     // Confirm password/PIN
     print!("Confirm {credential_label}: ");
     io::stdout().flush().ok();
-    let confirm = {
-        let raw = read_password()?;
-        Zeroizing::new(raw.trim_end_matches(['\r', '\n']).to_string())
-    };
+    let confirm = read_secret()?;
 
     if confirm.as_str() != password.as_str() {
         println!("{credential_label}s do not match.");
@@ -184,28 +272,82 @@ pub fn register_user(conn: &mut Connection, acting_user: Option<(&str, &str)>) -
         return Ok(());
     }
 
-    // If a homeowner is creating a guest, link them via homeowner_id 
-    let homeowner_id_opt = if new_role == "guest" {
-    match acting_role.as_str() {
-        // homeowners link guests to themselves
-        "homeowner" => {
-            match db::get_user_id_and_role(conn, acting_username)? {
-                Some((id, status)) if status == "homeowner" => Some(id),
-                _ => {
-                    println!("Acting user is not a valid homeowner.");
-                    return Ok(());
+    // Hash and insert; nothing is written until the hash succeeded
+    let hashed = match hash_password(&password) {
+        Ok(h) => h,
+        Err(e) => {
+            eprintln!("Failed to hash {credential_label}: {e:#}");
+            println!("No account was created for '{username}'. Please try again.");
+            return Ok(());
+        }
+    };
+    let mut pw_clear = password;
+    pw_clear.zeroize();
+
+    // Last check before writing: only admins create full accounts, and every guest has a homeowner
+    if (new_role != "guest" && acting_role != "admin") || (new_role == "guest") != homeowner_id_opt.is_some() {
+        println!("Registration refused: role and homeowner link do not match the acting account.");
+        return Ok(());
+    }
+
+    match db::insert_user(conn, &username, &acting_username, &hashed, &new_role, homeowner_id_opt) {
+        Ok(_) => {
+            println!("Registered '{username}' as {new_role}");
+            // Guests are managed by their homeowner, so only full accounts get self-service codes
+            if new_role != "guest" {
+                match db::generate_recovery_codes(conn, username) {
+                    Ok(codes) => show_recovery_codes(username, &codes),
+                    Err(e) => eprintln!("Failed to generate recovery codes: {e}"),
                 }
             }
         }
-        // Technicians can register guests *only if they have permission* under a homeowner
+        Err(e) => {
+            let msg = e.to_string();
+            if msg.to_lowercase().contains("unique") {
+                println!("Username already exists.");
+            } else {
+                println!("Registration failed: {msg}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+/* Role for an account created by `acting_role`. `requested` is what an admin typed;
+   homeowners and technicians only ever create guests, and asking for anything
+   else is refused rather than quietly turned into a guest. */
+pub fn role_for_new_account(acting_role: &str, requested: Option<&str>) -> Result<String> {
+    match acting_role {
+        "admin" => match requested {
+            Some(r @ ("homeowner" | "technician")) => Ok(r.to_string()),
+            Some("admin") => bail!("Creation of admin accounts is disabled"),
+            _ => bail!("Invalid role. Admins can only create homeowners or technicians."),
+        },
+        "homeowner" | "technician" => match requested {
+            None | Some("guest") => Ok("guest".to_string()),
+            Some(r) => bail!("{acting_role}s may only create guest accounts, not '{r}'."),
+        },
+        "guest" => bail!("Guests cannot register new users."),
+        other => bail!("Invalid acting role '{other}'."),
+    }
+}
+
+/* The homeowner a new guest belongs to. Homeowners link guests to themselves;
+   a technician links them to the homeowner of a grant that is still running,
+   checked again through tech_has_perm. */
+pub fn guest_homeowner_id(conn: &Connection, acting_username: &str, acting_role: &str) -> Result<i64> {
+    let homeowner_username = match acting_role {
+        "homeowner" => acting_username.to_string(),
         "technician" => {
-            // Find which homeowner this technician has permission for
-            let homeowner_username_opt: Option<String> = conn
+            let _ = db::sweep_expire_grants(conn);
+            let granted: Option<String> = conn
                 .query_row(
                     r#"
                     SELECT homeowner_username FROM technician_jobs
                      WHERE technician_username = ?1 COLLATE NOCASE AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-                       AND datetime(updated_at, printf('+%d minutes', access_minutes)) > datetime('now')
+                       AND grant_expires > datetime('now')
                      ORDER BY updated_at DESC
                      LIMIT 1
                     "#,
@@ -213,64 +355,83 @@ pub fn register_user(conn: &mut Connection, acting_user: Option<(&str, &str)>) -
                     |r| r.get(0),
                 )
                 .optional()?;
-
-            let Some(homeowner_username) = homeowner_username_opt else {
-                println!("Technician '{acting_username}' has no active homeowner access grants.");
-                return Ok(());   
+            let Some(homeowner_username) = granted else {
+                bail!("Technician '{acting_username}' has no active homeowner access grants.");
             };
-
             if !db::tech_has_perm(conn, acting_username, &homeowner_username)? {
-                println!("Technician '{acting_username}' does not currently have permission under homeowner '{homeowner_username}'.");
-                return Ok(());
-            }
-            // Retrieve homeowner ID for linkage
-            match db::get_user_id_and_role(conn, &homeowner_username)? {
-                Some((id, status)) if status == "homeowner" => Some(id),
-                _ => {
-                    println!("Failed to resolve homeowner ID for '{}'.", homeowner_username);
-                    return Ok(());
-                }
+                bail!("Technician '{acting_username}' does not currently have permission under homeowner '{homeowner_username}'.");
             }
+            homeowner_username
         }
-
-            _ => None,
-            }
-        } else {
-    None
+        other => bail!("{other}s cannot link guests to a homeowner."),
     };
-    
 
-    // Hash and insert
-    let hashed = match hash_password(&password) {
-        Ok(h) => h,
-        Err(_) => {
-            eprintln!("Failed to hash {credential_label}");
-            return Ok(());
-        }
-    };
-    let mut pw_clear = password;
-    pw_clear.zeroize();
+    match db::get_user_id_and_role(conn, &homeowner_username)? {
+        Some((id, status)) if status == "homeowner" => Ok(id),
+        _ => bail!("Failed to resolve homeowner ID for '{homeowner_username}'."),
+    }
+}
 
-    match db::insert_user(conn, &username, &acting_username, &hashed, &new_role, homeowner_id_opt) {
-        Ok(_) => {
-            println!("Registered '{username}' as {new_role}");
-        }
-        Err(e) => {
-            let msg = e.to_string();
-            if msg.to_lowercase().contains("unique") {
-                println!("Username already exists.");
-            } else {
-                println!("Registration failed: {msg}");
+fn show_recovery_codes(username: &str, codes: &[String]) {
+    println!();
+    println!("Recovery codes for '{username}' (each works once if the account gets locked):");
+    for code in codes {
+        println!("    {code}");
+    }
+    println!("Store these somewhere safe. They will not be shown again.");
+}
+
+/* Front-page self-service: a locked-out user enters one unused recovery code
+   to clear their lockout. Attempts are capped per user in db::redeem_recovery_code. */
+pub fn recover_locked_account(conn: &Connection) -> Result<()> {
+    print!("Username: ");
+    io::stdout().flush().ok();
+    let Some(username_input) = prompt_input() else { return Ok(()); };
+    let username = username_input.trim().to_string();
+    if username.is_empty() {
+        println!("Username cannot be empty.");
+        return Ok(());
+    }
+
+    for attempt in 1..=db::MAX_RECOVERY_ATTEMPTS {
+        print!("Recovery code: ");
+        io::stdout().flush().ok();
+        let code = read_secret()?;
+        match db::redeem_recovery_code(conn, &username, code.trim()) {
+            Ok(true) => {
+                let left = db::remaining_recovery_codes(conn, &username)?;
+                println!("✅ Lockout cleared. You can log in again ({left} recovery code(s) left).");
+                return Ok(());
+            }
+            Ok(false) => {
+                logger::fake_verification_delay();
+                println!("Invalid or already used recovery code ({attempt}/{}).", db::MAX_RECOVERY_ATTEMPTS);
+            }
+            Err(e) => {
+                println!("{e}");
+                return Ok(());
             }
         }
     }
-
+    println!("Too many failed attempts. Please contact an admin.");
     Ok(())
 }
 
 
-// Validates a username format (no special characters)
+// Validates a username for a new or renamed account: the format, and not in the anonymized namespace
 pub fn username_is_valid(username: &str) -> bool {
+    if !username_format_is_valid(username) {
+        return false;
+    }
+    if db::is_reserved_username(username) {
+        eprintln!("Usernames starting with '{}' are reserved for anonymized accounts.", db::ANONYMIZED_USERNAME_PREFIX);
+        return false;
+    }
+    true
+}
+
+// Validates a username format (no special characters)
+pub fn username_format_is_valid(username: &str) -> bool {
     // Ensure no whitespace or control characters
     if username.chars().any(|c| c.is_whitespace() || c.is_control()) {
         eprintln!("Username contains spaces or control characters");
@@ -344,21 +505,22 @@ pub fn password_is_strong(password: &str, username: &str) -> bool {
 // Build a secure Argon2id hasher with reasonable parameters
 // Argon2id is chosen for its hybrid resistance (safe against both GPU and side-channel attacks).
 // We use a memory-hard setup that balances performance and security for modern CPUs.
-fn argon2_hasher() -> Argon2<'static> {
+// Bad parameters come back as an error instead of panicking mid-registration.
+pub fn argon2_hasher() -> Result<Argon2<'static>> {
     /* Create Argon2 hashing parameters:
        - memory_cost: 65_536 KiB (≈64 MiB) → resists GPU cracking
        - iterations: 3 passes over memory
        - parallelism: 1 thread (sufficient for most single-user systems)
        - output_length: None → use default (32 bytes) */
-    let params = argon2::Params::new(65_536, 3, 1, None).expect("Invalid Argon2 params");
-    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+    let params = argon2::Params::new(65_536, 3, 1, None).context("Invalid Argon2 params")?;
+    Ok(Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params))
 }
 
 // Hash a plaintext password securely using Argon2id securely
 // Returns a Password Hashing Competition) formatted string
 pub fn hash_password(password: &str) -> Result<String> {
     let salt = SaltString::generate(&mut argonOsRng); //generate unique random salt
-    let hasher = argon2_hasher(); //create argon2id hasher instance
+    let hasher = argon2_hasher()?; //create argon2id hasher instance
     let phc = hasher
         .hash_password(password.as_bytes(), &salt) // convert pwd to raw bytes and salt adds entropy and uniqueness
         .context("Failed to hash password")?;
@@ -402,8 +564,7 @@ pub fn login_user(conn: &Connection) -> Result<Option<(String, String)>> {
     // Prompt username
     print!("Username: ");
     io::stdout().flush().ok();
-    let mut username_input = String::new();
-    io::stdin().read_line(&mut username_input)?;
+    let Some(username_input) = prompt_input() else { return Ok(None); };
     let username = username_input.trim().to_string();
     if username.is_empty() {
         println!("Username cannot be empty.");
@@ -424,7 +585,7 @@ pub fn login_user(conn: &Connection) -> Result<Option<(String, String)>> {
     // Prompt password (hidden input)
     print!("Password: ");
     io::stdout().flush().ok();
-    let pw_in = Zeroizing::new(read_password()?);
+    let pw_in = read_secret()?;
     let password = pw_in.trim_end_matches(['\r', '\n']); // &str view; buffer wiped on drop
 
     // Fetch stored hash + role + active flag
@@ -479,6 +640,28 @@ pub fn login_user(conn: &Connection) -> Result<Option<(String, String)>> {
         return Ok(None);
     }
 
+    // Admin-issued temporary password must be replaced before the session starts
+    if db::password_change_required(conn, &username)? {
+        println!("Your password was reset by an administrator. Please choose a new password.");
+        print!("New password: ");
+        io::stdout().flush().ok();
+        let new_pw = read_secret()?;
+        print!("Confirm new password: ");
+        io::stdout().flush().ok();
+        let confirm_pw = read_secret()?;
+
+        let new_pw = new_pw.trim_end_matches(['\r', '\n']);
+        if new_pw != confirm_pw.trim_end_matches(['\r', '\n']) {
+            println!("Passwords do not match. Login cancelled.");
+            return Ok(None);
+        }
+        if let Err(e) = db::complete_forced_password_change(conn, &username, new_pw) {
+            println!("{}", e);
+            return Ok(None);
+        }
+        println!("Password updated.");
+    }
+
     // On success: reset anonymous lockout counters
     conn.execute(
         "UPDATE session_state
@@ -536,7 +719,7 @@ pub fn login_user(conn: &Connection) -> Result<Option<(String, String)>> {
 // Verify a password against a stored PHC hash
 pub fn verify_password(password: &str, stored_hash: &str) -> Result<bool> {
     let parsed = PasswordHash::new(stored_hash).context("Invalid password hash format")?; // parse stored hash
-    let hasher = argon2_hasher(); // create argon2id hasher instance
+    let hasher = argon2_hasher()?; // create argon2id hasher instance
     Ok(hasher.verify_password(password.as_bytes(), &parsed).is_ok()) // return true if verified
 }
 
@@ -554,6 +737,13 @@ pub fn logout_user(conn: &Connection) -> Result<()> {
         }
     };
 
+    // Summarise the session before its row (and login_time) goes away
+    if let Ok(Some(login_time)) = db::session_login_time(conn, &username)
+        && let Ok(activity) = db::session_activity_since(conn, &username, &login_time)
+    {
+        println!("{}", activity.summary());
+    }
+
     //End the session in DB
     if let Err(_) = db::end_session(conn, &username) {
         eprintln!("Warning: failed to end DB session.");
@@ -571,6 +761,10 @@ pub fn logout_user(conn: &Connection) -> Result<()> {
 
     println!("User '{}' logged out successfully.", username);
 
+    if let Err(e) = crate::profile::apply_away_if_house_empty(conn) {
+        eprintln!("Warning: failed to apply Away after logout: {e}");
+    }
+
     Ok(())
 }
 
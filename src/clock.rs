@@ -0,0 +1,62 @@
+// ======================================================
+//                      CLOCK
+// ======================================================
+// Time source for grant expiry, lockouts and the profile schedule. The app
+// runs on RealClock; tests hand in a FixedClock and move it by hand, so an
+// expiry boundary can be checked to the second without sleeping.
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime, Utc};
+use std::sync::Mutex;
+
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+
+    // Local wall-clock time, which the profile schedule runs on
+    fn now_local(&self) -> NaiveDateTime {
+        self.now().with_timezone(&Local).naive_local()
+    }
+
+    // UTC in the "YYYY-MM-DD HH:MM:SS" form datetime('now') produces, for binding into SQL
+    fn sqlite_now(&self) -> String {
+        self.now().format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+// Stands still until set or advanced
+#[derive(Debug)]
+pub struct FixedClock {
+    at: Mutex<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    pub fn new(at: DateTime<Utc>) -> Self {
+        FixedClock { at: Mutex::new(at) }
+    }
+
+    pub fn set(&self, at: DateTime<Utc>) {
+        if let Ok(mut current) = self.at.lock() {
+            *current = at;
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        if let Ok(mut current) = self.at.lock() {
+            *current += by;
+        }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.at.lock().map(|at| *at).unwrap_or_else(|poisoned| *poisoned.into_inner())
+    }
+}
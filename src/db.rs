@@ -3,30 +3,50 @@ use base64::{Engine, engine::general_purpose};
 use blake3;
 use chrono::{DateTime, Utc, NaiveDateTime};
 use chrono_tz::America::New_York;
-use rusqlite::{params, Connection, OptionalExtension};
-use rpassword::read_password;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
 use rand::{TryRngCore, rngs::OsRng};
-use std::{io::{self, Write}, path::Path};
+use serde::{Deserialize, Serialize};
+use std::{io::{self, Write}, path::{Path, PathBuf}, str::FromStr};
 use zeroize::Zeroizing;
 
 use crate::auth;
+use crate::clock::{Clock, RealClock};
 use crate::logger;
-use crate::weather::WeatherRecord;
+use crate::function::prompt_input;
+use crate::weather::{WeatherLocation, WeatherRecord};
+use crate::units::TempUnit;
 
-// Converts UTC timestamp strings (e.g. "2025-10-18 13:32:39") into America/New_York time (EDT/EST).
+// Converts UTC timestamp strings (e.g. "2025-10-18 13:32:39" or "2025-10-18T13:32:39Z") into America/New_York time (EDT/EST).
 fn to_eastern_time(utc_str: &str) -> Option<String> {
-    if let Ok(naive) = NaiveDateTime::parse_from_str(utc_str, "%Y-%m-%d %H:%M:%S") {
-        let utc_dt: DateTime<Utc> = DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc);
-        let eastern_dt = utc_dt.with_timezone(&New_York);
-        Some(eastern_dt.format("%Y-%m-%d %H:%M:%S %Z").to_string())
+    let utc_dt: DateTime<Utc> = if let Ok(naive) = NaiveDateTime::parse_from_str(utc_str, "%Y-%m-%d %H:%M:%S") {
+        DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc)
+    } else if let Ok(dt) = DateTime::parse_from_rfc3339(utc_str) {
+        dt.with_timezone(&Utc)
     } else {
-        None
-    }
+        return None;
+    };
+    let eastern_dt = utc_dt.with_timezone(&New_York);
+    Some(eastern_dt.format("%Y-%m-%d %H:%M:%S %Z").to_string())
 }
 
 // Initialize all required database tables and indexes.
 pub fn init_system_db<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
     let conn = Connection::open(db_path).context("Failed to open db")?;
+    init_schema(&conn)?;
+    // A crashed run can leave sessions behind; start from a clean slate
+    cleanup_stale_sessions(&conn)?;
+    Ok(conn)
+}
+
+// Same production schema and migrations, applied to a fresh in-memory database (used by tests).
+pub fn init_system_db_in_memory() -> Result<Connection> {
+    let conn = Connection::open_in_memory().context("Failed to open in-memory db")?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+// Apply PRAGMAs, tables, migrations and seed data to an open connection.
+fn init_schema(conn: &Connection) -> Result<()> {
         //Apply secure PRAGMA settings
         conn.execute_batch(
         r#"
@@ -54,7 +74,9 @@ pub fn init_system_db<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
             is_active       INTEGER DEFAULT 1,
             last_login_time TEXT,
             created_at      TEXT DEFAULT CURRENT_TIMESTAMP,
-            updated_at      TEXT
+            updated_at      TEXT,
+            force_password_change INTEGER NOT NULL DEFAULT 0,
+            bulk_disabled   INTEGER NOT NULL DEFAULT 0
         );
 
         CREATE INDEX IF NOT EXISTS ix_users_homeowner_id ON users(homeowner_id);
@@ -71,11 +93,11 @@ pub fn init_system_db<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
                 event_type IN (
                     'ACCOUNT_CREATED', 'SUCCESS_LOGIN', 'FAILURE_LOGIN', 'LOGOUT', 'LOCKOUT', 'SESSION_LOCKOUT', 'LOCKOUT_CLEARED',
                     'ACCOUNT_DELETED', 'ACCOUNT_DISABLED', 'ACCOUNT_ENABLED', 'ADMIN_LOGIN', 'PASSWORD_CHANGE', 'HVAC',
-                    'ACCESS_GRANTED', 'ACCESS_EXPIRED', 'TECH_ACCESS'
+                    'ACCESS_GRANTED', 'ACCESS_EXPIRED', 'TECH_ACCESS', 'SETTINGS_CHANGED', 'GUEST_LOGIN', 'LOGIN_REFUSED'
                 )
             ),
             description TEXT,
-            timestamp TEXT NOT NULL DEFAULT (datetime('now'))
+            timestamp TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
         );
 
         CREATE INDEX IF NOT EXISTS ix_security_log_actor ON security_log(actor_username);
@@ -106,6 +128,7 @@ pub fn init_system_db<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
             is_locked INTEGER DEFAULT 0,
             locked_until TEXT,
             session_lock_count INTEGER DEFAULT 0,
+            reauth_at TEXT,
             FOREIGN KEY(username) REFERENCES users(username) ON DELETE CASCADE ON UPDATE CASCADE
         );
 
@@ -189,7 +212,7 @@ pub fn init_system_db<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
             old_value TEXT,
             new_value TEXT,
             description TEXT,
-            timestamp TEXT NOT NULL DEFAULT (datetime('now'))
+            timestamp TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
         );
 
         CREATE INDEX IF NOT EXISTS ix_hvac_log_username ON hvac_activity_log(username);
@@ -210,21 +233,182 @@ pub fn init_system_db<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
 
         -- Initialize with default state if empty
         INSERT OR IGNORE INTO hvac_state (id, mode, target_temperature) VALUES (1, 'Off', 22.0);
+
+        -- ===============================
+        -- STATUS SNAPSHOT TABLE
+        -- ===============================
+        -- Copy of the headline HVAC state for the front page, written by save_hvac_state
+        CREATE TABLE IF NOT EXISTS status_snapshot (
+            id INTEGER PRIMARY KEY CHECK(id = 1),
+            mode TEXT NOT NULL,
+            target_temperature REAL NOT NULL,
+            current_profile TEXT,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- ===============================
+        -- GUEST NOTES TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS guest_notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            guest_username TEXT NOT NULL COLLATE NOCASE,
+            homeowner_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            note TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            is_read INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS ix_guest_notes_homeowner ON guest_notes(homeowner_id, is_read);
+
+        -- ===============================
+        -- MENU SHORTCUTS TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS menu_shortcuts (
+            username TEXT NOT NULL COLLATE NOCASE,
+            slot INTEGER NOT NULL CHECK (slot BETWEEN 1 AND 3),
+            action TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (username, slot)
+        );
+
+        -- ===============================
+        -- RECOVERY CODES TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS recovery_codes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL COLLATE NOCASE,
+            code_hash TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            used_at TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS ix_recovery_codes_user ON recovery_codes(username);
+
+        CREATE TABLE IF NOT EXISTS recovery_attempts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL COLLATE NOCASE,
+            success INTEGER NOT NULL DEFAULT 0,
+            attempted_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- ===============================
+        -- MODE DEFAULT TEMPERATURES
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS mode_defaults (
+            mode TEXT PRIMARY KEY CHECK (mode IN ('Heating','Cooling','Auto')),
+            target_temp REAL NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- ===============================
+        -- TEMPORARY OVERRIDES TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS overrides (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL DEFAULT 'temperature' CHECK (kind IN ('temperature','fan')),
+            mode TEXT CHECK (mode IN ('Heating','Cooling','Auto')),
+            target_temp REAL,
+            fan_speed TEXT CHECK (fan_speed IN ('Low','Medium','High')),
+            set_by TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            expires_at TEXT NOT NULL,
+            ended_at TEXT,
+            CHECK ((kind = 'temperature' AND mode IS NOT NULL AND target_temp IS NOT NULL)
+                OR (kind = 'fan' AND fan_speed IS NOT NULL))
+        );
+
+        -- ===============================
+        -- USER PREFERENCES TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS user_preferences (
+            username TEXT PRIMARY KEY COLLATE NOCASE,
+            display_mode TEXT NOT NULL DEFAULT 'verbose' CHECK (display_mode IN ('verbose','compact')),
+            temp_unit TEXT NOT NULL DEFAULT 'celsius' CHECK (temp_unit IN ('celsius','fahrenheit')),
+            temp_precision INTEGER NOT NULL DEFAULT 1 CHECK (temp_precision IN (0, 1)),
+            energy_rate REAL,
+            energy_currency TEXT NOT NULL DEFAULT 'USD' CHECK (energy_currency IN ('USD', 'EUR', 'GBP', 'JPY')),
+            guest_login_notices INTEGER NOT NULL DEFAULT 0 CHECK (guest_login_notices IN (0, 1)),
+            guest_login_seen_id INTEGER NOT NULL DEFAULT 0,
+            guest_temp_min REAL,
+            guest_temp_max REAL,
+            guest_hvac_locked INTEGER NOT NULL DEFAULT 0 CHECK (guest_hvac_locked IN (0, 1)),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- ===============================
+        -- SYSTEM SETTINGS TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS system_settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_by TEXT,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- ===============================
+        -- MENU CONFIG TABLE
+        -- ===============================
+        -- Per-role overrides of the default menu layout (see menu_config.rs)
+        CREATE TABLE IF NOT EXISTS menu_config (
+            role TEXT NOT NULL CHECK(role IN ('admin','homeowner','technician','guest')),
+            action TEXT NOT NULL,
+            position INTEGER,
+            hidden INTEGER NOT NULL DEFAULT 0,
+            updated_by TEXT,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (role, action)
+        );
+
+        -- Dashboard thresholds; no row means the built-in defaults
+        CREATE TABLE IF NOT EXISTS sensor_thresholds (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            temp_comfort_lo REAL NOT NULL,
+            temp_comfort_hi REAL NOT NULL,
+            humidity_lo REAL NOT NULL,
+            humidity_hi REAL NOT NULL,
+            co_warn REAL NOT NULL,
+            co_danger REAL NOT NULL,
+            updated_by TEXT,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- ===============================
+        -- SENSOR READINGS TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS sensor_readings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            temperature_c REAL NOT NULL,
+            humidity_pct REAL,
+            recorded_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS ix_sensor_readings_recorded_at ON sensor_readings(recorded_at);
+
+        -- ===============================
+        -- DIAGNOSTICS LOG TABLE
+        -- ===============================
+        CREATE TABLE IF NOT EXISTS diagnostics_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            actor_username TEXT NOT NULL,
+            run_at TEXT NOT NULL DEFAULT (datetime('now')),
+            outdoor_temp_c REAL,
+            indoor_temp_c REAL,
+            indoor_humidity REAL,
+            indoor_co_ppm REAL,
+            outdoor_ok INTEGER NOT NULL,
+            indoor_ok INTEGER NOT NULL,
+            devices_ok INTEGER NOT NULL,
+            passed INTEGER NOT NULL
+        );
         "#,
     )
     .context("Failed to initialize tables in system.db")?;
 
-    // Migrate existing profiles table to add new columns if they don't exist
-    migrate_profiles_table(&conn)?;
-    
-    // Migrate existing hvac_state table to add light_status if it doesn't exist
-    migrate_hvac_state_table(&conn)?;
-    
-    // Migrate security_log table to add technician event types
-    migrate_security_log_table(&conn)?;
+    // Bring older databases up to the current schema
+    run_migrations(conn)?;
 
     // Seed default profiles if missing
-    seed_default_profiles(&conn)?;
+    seed_default_profiles(conn)?;
     
     // Update Party profile to have light ON (fix for existing databases)
     conn.execute(
@@ -232,7 +416,37 @@ pub fn init_system_db<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
         [],
     )?;
 
-    Ok(conn)
+    Ok(())
+}
+
+// ------------------ DATA DIRECTORY ------------------
+pub const DATA_DIR_ENV: &str = "SMART_THERMOSTAT_DATA_DIR";
+pub const DB_FILE_NAME: &str = "system.db";
+
+/* Base directory for the database and log files, created if missing. Unset or
+   blank keeps the old behaviour of using the current working directory. */
+pub fn resolve_data_dir(setting: Option<&str>) -> Result<PathBuf> {
+    match setting.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(dir) => {
+            let dir = PathBuf::from(dir);
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create data directory {}", dir.display()))?;
+            Ok(dir)
+        }
+        None => Ok(PathBuf::new()),
+    }
+}
+
+// Points the security log file at the data directory; returns the database path
+pub fn use_data_dir(dir: &Path) -> PathBuf {
+    logger::set_security_log_path(dir.join(logger::DEFAULT_SECURITY_LOG_PATH));
+    dir.join(DB_FILE_NAME)
+}
+
+// Data directory from SMART_THERMOSTAT_DATA_DIR, applied to the log file; returns the database path
+pub fn configure_data_dir() -> Result<PathBuf> {
+    let dir = resolve_data_dir(std::env::var(DATA_DIR_ENV).ok().as_deref())?;
+    Ok(use_data_dir(&dir))
 }
 
 // Returns a reusable SQLite connection to the unified database.
@@ -265,6 +479,9 @@ pub fn get_user_id_and_role(conn: &Connection, username: &str) -> Result<Option<
 
 // Insert a new user record (used internally by registration).
 pub fn insert_user(conn: &mut Connection, username: &str, admin_username: &str ,hashed: &str, role: &str, homeowner_id: Option<i64>) -> Result<()> {
+    if is_reserved_username(username) {
+        return Err(anyhow!("Usernames starting with '{}' are reserved.", ANONYMIZED_USERNAME_PREFIX));
+    }
     let tx = conn.transaction().context("Failed to start transaction")?;
     tx.execute(
         "INSERT INTO users (username, hashed_password, user_status, homeowner_id, updated_at)
@@ -413,23 +630,127 @@ pub fn view_all_users(conn: &Connection, current_role: &str) -> Result<()> {
 }
 
 
+// One row of the admin user search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSummary {
+    pub id: i64,
+    pub username: String,
+    pub role: String,
+    pub is_active: bool,
+    pub created_at: Option<String>,
+    pub last_login_time: Option<String>,
+}
 
-// Allows an admin to enable or disable user accounts.
-pub fn manage_user_status(conn: &mut Connection, admin_username: &str, current_role: &str) -> Result<()> {
+// Escapes LIKE wildcards so they match literally (used with ESCAPE '\')
+fn escape_like(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// Users whose username or role contains `query`, case-insensitively; an empty query lists everyone
+pub fn search_users(conn: &Connection, query: &str) -> Result<Vec<UserSummary>> {
+    let pattern = format!("%{}%", escape_like(query.trim()));
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, username, user_status, is_active, created_at, last_login_time FROM users
+         WHERE username LIKE ?1 ESCAPE '\' OR user_status LIKE ?1 ESCAPE '\'
+         ORDER BY username COLLATE NOCASE
+        "#,
+    )?;
+    let rows = stmt.query_map(params![pattern], |r| {
+        Ok(UserSummary {
+            id: r.get(0)?,
+            username: r.get(1)?,
+            role: r.get(2)?,
+            is_active: r.get::<_, i64>(3)? == 1,
+            created_at: r.get(4)?,
+            last_login_time: r.get(5)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Admin-only listing of a search_users() result
+pub fn view_user_search(conn: &Connection, current_role: &str, query: &str) -> Result<()> {
     if current_role != "admin" {
-        println!("Access denied: Only admins can manage accounts.");
+        println!("Access denied: Only administrators can search users.");
+        return Ok(());
+    }
+    let users = search_users(conn, query)?;
+    println!("\n===== Users matching '{}' =====", query.trim());
+    if users.is_empty() {
+        println!("(No matching users.)");
         return Ok(());
     }
+    println!("{:<5} {:<15} {:<12} {:<8} {:<25}", "ID", "Username", "Role", "Active", "Last Login (EST)");
+    println!("{}", "-".repeat(70));
+    for u in users {
+        let last_login = u.last_login_time.as_deref().and_then(to_eastern_time).unwrap_or_else(|| "never".to_string());
+        println!(
+            "{:<5} {:<15} {:<12} {:<8} {:<25}",
+            u.id, u.username, u.role, if u.is_active { "ACTIVE" } else { "INACTIVE" }, last_login
+        );
+    }
+    Ok(())
+}
+
+// ======================================================
+//                  STEP-UP RE-AUTHENTICATION
+// ======================================================
+
+// A successful step-up stays good this long, so back-to-back admin actions don't re-prompt
+pub const REAUTH_WINDOW_SECS: i64 = 120;
+
+// True while a step-up recorded at `reauth_at` is still inside the window
+pub fn reauth_is_fresh(reauth_at: Option<DateTime<Utc>>, now: DateTime<Utc>, window_secs: i64) -> bool {
+    match reauth_at {
+        Some(at) => at <= now && (now - at).num_seconds() < window_secs,
+        None => false,
+    }
+}
+
+// Marks the user's live session as just re-authenticated
+pub fn record_reauth(conn: &Connection, username: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE session_state SET reauth_at = datetime('now') WHERE username = ?1 COLLATE NOCASE",
+        params![username],
+    )?;
+    Ok(())
+}
+
+// When the user last passed a step-up in their current session
+pub fn last_reauth(conn: &Connection, username: &str) -> Result<Option<DateTime<Utc>>> {
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT reauth_at FROM session_state WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?
+        .flatten();
+    Ok(raw
+        .and_then(|t| NaiveDateTime::parse_from_str(&t, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|naive| DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc)))
+}
+
+// Password step-up for sensitive admin actions; returns whether the admin is verified
+fn require_admin_reauth(conn: &Connection, admin_username: &str) -> Result<bool> {
+    if reauth_is_fresh(last_reauth(conn, admin_username)?, Utc::now(), REAUTH_WINDOW_SECS) {
+        println!("\nRecently re-authenticated, continuing.");
+        return Ok(true);
+    }
 
-    // Verify admin identity
     println!("\nAdmin re-authentication required.");
     print!("Enter your password: ");
     io::stdout().flush().ok();
 
-    let admin_pw = {
-        let raw = read_password()?;
-        Zeroizing::new(raw.trim_end_matches(['\r', '\n']).to_string())
-    };
+    let admin_pw = auth::read_secret()?;
 
     let stored_hash: Option<String> = conn
         .query_row(
@@ -444,7 +765,69 @@ pub fn manage_user_status(conn: &mut Connection, admin_username: &str, current_r
         .map(|h| auth::verify_password(&admin_pw, h).unwrap_or(false))
         .unwrap_or(false);
 
-    if !ok {
+    if ok {
+        record_reauth(conn, admin_username)?;
+    }
+    Ok(ok)
+}
+
+/* Admin view of another user's full account details. Needs the same password
+   step-up as manage_user_status (skipped inside the re-auth window); each view
+   is logged as an ADMIN_LOGIN event naming the target. Returns whether shown. */
+pub fn show_user_profile(conn: &Connection, admin_username: &str, current_role: &str, target: &str) -> Result<bool> {
+    show_user_profile_with(conn, admin_username, current_role, target, require_admin_reauth)
+}
+
+// Same, with the step-up check supplied by the caller
+pub fn show_user_profile_with(
+    conn: &Connection,
+    admin_username: &str,
+    current_role: &str,
+    target: &str,
+    reauth: impl FnOnce(&Connection, &str) -> Result<bool>,
+) -> Result<bool> {
+    if current_role != "admin" {
+        println!("Access denied: Only administrators can view other users' profiles.");
+        return Ok(false);
+    }
+    let row: Option<(String, i64, i64, Option<String>)> = conn
+        .query_row(
+            "SELECT u.username, IFNULL(u.is_active, 1), u.force_password_change, h.username
+               FROM users u LEFT JOIN users h ON h.id = u.homeowner_id
+              WHERE u.username = ?1 COLLATE NOCASE",
+            params![target],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )
+        .optional()?;
+    let Some((username, is_active, force_change, homeowner)) = row else {
+        println!("User '{}' not found.", target);
+        return Ok(false);
+    };
+
+    if !reauth(conn, admin_username)? {
+        println!("Authentication failed. Profile not shown.");
+        return Ok(false);
+    }
+    logger::log_event(conn, admin_username, Some(&username), "ADMIN_LOGIN", Some(&format!("Viewed profile of '{}'", username)))?;
+
+    show_own_profile(conn, &username)?;
+    println!("Status: {}", if is_active == 1 { "Active" } else { "Disabled" });
+    println!("Must change password: {}", if force_change == 1 { "yes" } else { "no" });
+    if let Some(h) = homeowner {
+        println!("Homeowner: {}", h);
+    }
+    Ok(true)
+}
+
+// Allows an admin to enable or disable user accounts.
+pub fn manage_user_status(conn: &mut Connection, admin_username: &str, current_role: &str) -> Result<()> {
+    if current_role != "admin" {
+        println!("Access denied: Only admins can manage accounts.");
+        return Ok(());
+    }
+
+    // Verify admin identity (skipped within the re-auth window)
+    if !require_admin_reauth(conn, admin_username)? {
         println!("Authentication failed. Aborting.");
         return Ok(());
     }
@@ -463,9 +846,8 @@ pub fn manage_user_status(conn: &mut Connection, admin_username: &str, current_r
 
     print!("\nEnter username or ID to toggle (or 'cancel' to exit): ");
     io::stdout().flush().ok();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).ok();
-    let input = input.trim();
+    let Some(input) = prompt_input() else { return Ok(()); };
+    let input = input.as_str();
 
     if input.eq_ignore_ascii_case("cancel") {
         println!("Operation cancelled.");
@@ -478,7 +860,7 @@ pub fn manage_user_status(conn: &mut Connection, admin_username: &str, current_r
         |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, i64>(3)?)),
     );
 
-    let (user_id, target_username, target_role, is_active) = match row {
+    let (_user_id, target_username, _target_role, is_active) = match row {
         Ok(v) => v,
         Err(_) => {
             println!("User '{}' not found.", input);
@@ -486,87 +868,470 @@ pub fn manage_user_status(conn: &mut Connection, admin_username: &str, current_r
         }
     };
 
-    // Protection: don't disable self or another admin
-    if target_username.eq_ignore_ascii_case(admin_username) {
-        println!("You cannot disable your own account.");
-        return Ok(());
+    let activate = is_active != 1;
+    let reason = prompt_admin_reason();
+    match set_user_active(conn, admin_username, &target_username, activate, reason.as_deref()) {
+        Ok(()) => println!("User '{}' has been {}.", target_username, if activate { "enabled" } else { "disabled" }),
+        Err(e) => println!("{}", e),
     }
-    if target_role == "admin" {
-        println!("You cannot modify another admin’s account.");
-        return Ok(());
+    Ok(())
+}
+
+// Longest reason/ticket text kept on an admin action; the rest is cut off
+pub const MAX_ADMIN_REASON_CHARS: usize = 120;
+
+// Optional reason or ticket ID for an account action; whitespace runs collapse, blank is None
+pub fn admin_reason(input: &str) -> Option<String> {
+    let reason = input.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!reason.is_empty()).then(|| reason.chars().take(MAX_ADMIN_REASON_CHARS).collect())
+}
+
+// Asks for the optional reason; Enter (or EOF) skips it
+pub fn prompt_admin_reason() -> Option<String> {
+    print!("Reason or ticket ID (optional, Enter to skip): ");
+    io::stdout().flush().ok();
+    prompt_input().and_then(|r| admin_reason(&r))
+}
+
+// Appended to the logged description so the reason shows in the security log view
+fn with_reason(desc: String, reason: Option<&str>) -> String {
+    match reason.and_then(admin_reason) {
+        Some(r) => format!("{} | Reason: {}", desc, r),
+        None => desc,
     }
+}
+
+// Enables or disables one account on an admin's behalf and logs it.
+pub fn set_user_active(
+    conn: &Connection,
+    admin_username: &str,
+    target_username: &str,
+    active: bool,
+    reason: Option<&str>,
+) -> Result<()> {
+    let (user_id, target_name, target_role): (i64, String, String) = conn
+        .query_row(
+            "SELECT id, username, user_status FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![target_username],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("User '{}' not found.", target_username))?;
 
-    let new_status = if is_active == 1 { 0 } else { 1 };
-    let action = if new_status == 1 { "enabled" } else { "disabled" };
+    // Protection: keep an admin around, and don't disable self or another admin
+    if !active {
+        ensure_not_last_admin(conn, &target_name)?;
+    }
+    if target_name.eq_ignore_ascii_case(admin_username) {
+        return Err(anyhow!("You cannot disable your own account."));
+    }
+    if target_role == "admin" {
+        return Err(anyhow!("You cannot modify another admin’s account."));
+    }
 
     conn.execute(
-        "UPDATE users SET is_active = ?1, updated_at = datetime('now') WHERE id = ?2",
-        params![new_status, user_id],
+        "UPDATE users SET is_active = ?1, bulk_disabled = 0, updated_at = datetime('now') WHERE id = ?2",
+        params![active as i64, user_id],
     )?;
 
-    println!("User '{}' has been {}.", target_username, action);
+    let action = if active { "enabled" } else { "disabled" };
+    let event_type = if active { "ACCOUNT_ENABLED" } else { "ACCOUNT_DISABLED" };
+    let desc = with_reason(format!("User '{}' {} by Admin '{}'", target_name, action, admin_username), reason);
+
+    logger::log_event(conn, admin_username, Some(&target_name), event_type, Some(&desc))?;
+    Ok(())
+}
 
-    let event_type = if new_status == 1 { "ACCOUNT_ENABLED" } else { "ACCOUNT_DISABLED" };
-    let desc = format!("User '{}' {} by Admin '{}'", target_username, action, admin_username);
+// Refuses to disable, demote or delete `username` when it is the only active admin left.
+pub fn ensure_not_last_admin(conn: &Connection, username: &str) -> Result<()> {
+    let is_active_admin: bool = conn
+        .query_row(
+            "SELECT user_status = 'admin' AND is_active = 1 FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?
+        .unwrap_or(false);
+    if !is_active_admin {
+        return Ok(());
+    }
 
-    logger::log_event(conn, admin_username, Some(&target_username), event_type, Some(&desc))?;
+    let other_admins: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users
+          WHERE user_status = 'admin' AND is_active = 1 AND username <> ?1 COLLATE NOCASE",
+        params![username],
+        |r| r.get(0),
+    )?;
+    if other_admins == 0 {
+        return Err(anyhow!(
+            "'{}' is the last active admin; create another admin before removing this one.",
+            username
+        ));
+    }
     Ok(())
 }
 
 // ======================================================
-//                   TECHNICIANS
+//                 ADMIN PASSWORD RESET
 // ======================================================
 
-pub fn grant_technician_access(conn: &mut Connection, 
-    homeowner_username: &str, technician_username: &str, 
-    access_minutes: i64, job_desc_raw: &str) -> Result<i64> {
-    
-        //validate access time
-    if ![30, 60, 90, 120].contains(&access_minutes) {
-        return Err(anyhow!("Invalid access time; must be one of 30, 60, 90, 120."));
+// Builds a random temporary password that always satisfies password_is_strong.
+pub fn generate_temporary_password() -> Result<String> {
+    const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+    const LOWER: &[u8] = b"abcdefghijkmnpqrstuvwxyz";
+    const DIGIT: &[u8] = b"23456789";
+    const SPECIAL: &[u8] = b"@$!%*?&-_#";
+
+    let mut rng = OsRng;
+    let mut pick = |set: &[u8]| -> Result<char> {
+        let idx = (rng.try_next_u32().context("OS RNG failed")? as usize) % set.len();
+        Ok(set[idx] as char)
+    };
+
+    // two of each class
+    let mut chars: Vec<char> = Vec::with_capacity(12);
+    for set in [UPPER, LOWER, DIGIT, SPECIAL, UPPER, LOWER, DIGIT, SPECIAL] {
+        chars.push(pick(set)?);
     }
-    
-    // sanitize and length bounds
-    let mut desc = job_desc_raw.trim().to_string();
-    desc.retain(|c| !c.is_control());
-    let desc = desc.split_whitespace().collect::<Vec<_>>().join(" ");
-    if desc.is_empty() {
-        return Err(anyhow!("Description cannot be empty."));
+    let all = [UPPER, LOWER, DIGIT].concat();
+    for _ in 0..4 {
+        chars.push(pick(&all)?);
     }
-    let len = desc.chars().count();
-    if len < 20 || len > 200 {
-        return Err(anyhow!("Description must be 20–200 characters (current: {}).", len));
+
+    // shuffle so the classes are not in a fixed order
+    for i in (1..chars.len()).rev() {
+        let j = (rng.try_next_u32().context("OS RNG failed")? as usize) % (i + 1);
+        chars.swap(i, j);
     }
-    
-    //validation of actors and their roles
-    let (h_role, h_active): (String, i64) = conn.query_row(
-        "SELECT user_status, is_active FROM users WHERE username = ?1 COLLATE NOCASE",
-        params![homeowner_username],
-        |r| Ok((r.get(0)?, r.get(1)?)),
-    ).context("Failed to fetch homeowner record")?;
+    Ok(chars.into_iter().collect())
+}
 
-    if h_role != "homeowner" || h_active != 1 {
-        return Err(anyhow!("Invalid homeowner account or account is inactive."));
+// Sets a temporary password on a non-admin account and flags it for change on next login.
+pub fn set_temporary_password(conn: &Connection, admin_username: &str, target_username: &str, temp_password: &str) -> Result<()> {
+    let (target_name, target_role): (String, String) = conn
+        .query_row(
+            "SELECT username, user_status FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![target_username],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("User '{}' not found.", target_username))?;
+
+    if target_name.eq_ignore_ascii_case(admin_username) {
+        return Err(anyhow!("You cannot reset your own password here."));
+    }
+    if target_role == "admin" {
+        return Err(anyhow!("You cannot reset another admin's password."));
+    }
+    if !auth::password_is_strong(temp_password, &target_name) {
+        return Err(anyhow!("Temporary password does not meet the strength policy."));
     }
 
-    let (t_role, t_active): (String, i64) = conn.query_row(
-        "SELECT user_status, is_active FROM users WHERE username = ?1 COLLATE NOCASE",
-        params![technician_username],
-        |r| Ok((r.get(0)?, r.get(1)?)),
-    ).context("Failed to fetch technician record")?;
+    let hashed = auth::hash_password(temp_password)?;
+    conn.execute(
+        "UPDATE users SET hashed_password = ?1, force_password_change = 1, updated_at = datetime('now')
+          WHERE username = ?2 COLLATE NOCASE",
+        params![hashed, target_name],
+    )?;
 
-    if t_role != "technician" || t_active != 1 {
-        return Err(anyhow!("Invalid technician account or account is inactive."));
+    let desc = format!("Temporary password set for '{}' by Admin '{}'", target_name, admin_username);
+    logger::log_event(conn, admin_username, Some(&target_name), "PASSWORD_CHANGE", Some(&desc))?;
+    Ok(())
+}
+
+// Admin flow: re-authenticate, then set a generated or typed temporary password.
+pub fn admin_reset_password(conn: &Connection, admin_username: &str, target_username: &str) -> Result<()> {
+    match get_user_id_and_role(conn, admin_username)? {
+        Some((_id, role)) if role == "admin" => {}
+        _ => {
+            println!("Access denied: Only admins can reset passwords.");
+            return Ok(());
+        }
     }
 
-  // insert & transaction
-    let job_id = {
-        let tx = conn.transaction()?;
-        tx.execute(
-            r#"
-            INSERT INTO technician_jobs
-                (homeowner_username, technician_username, status, access_minutes, job_desc, updated_at)
-            VALUES (?1, ?2, 'ACCESS_GRANTED', ?3, ?4, datetime('now'))
+    // Verify admin identity (skipped within the re-auth window)
+    if !require_admin_reauth(conn, admin_username)? {
+        println!("Authentication failed. Aborting.");
+        return Ok(());
+    }
+
+    print!("Enter a temporary password (leave blank to generate one): ");
+    io::stdout().flush().ok();
+    let typed = auth::read_secret()?;
+
+    let generated = typed.is_empty();
+    let temp_pw = if generated {
+        Zeroizing::new(generate_temporary_password()?)
+    } else {
+        typed
+    };
+
+    match set_temporary_password(conn, admin_username, target_username, &temp_pw) {
+        Ok(()) => {
+            println!("Password for '{}' has been reset. They must change it at next login.", target_username);
+            if generated {
+                println!("Temporary password: {}", temp_pw.as_str());
+            }
+        }
+        Err(e) => println!("Password reset failed: {}", e),
+    }
+    Ok(())
+}
+
+// Anonymized accounts are renamed into this namespace, so new names may not start with it
+pub const ANONYMIZED_USERNAME_PREFIX: &str = "deleted_user_";
+
+// Stable replacement name for an anonymized account
+pub fn anonymized_username(user_id: i64) -> String {
+    format!("{}{}", ANONYMIZED_USERNAME_PREFIX, user_id)
+}
+
+pub fn is_reserved_username(username: &str) -> bool {
+    username
+        .get(..ANONYMIZED_USERNAME_PREFIX.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(ANONYMIZED_USERNAME_PREFIX))
+}
+
+/* Removes a user without breaking the audit trail: after admin re-auth the account
+   is renamed to deleted_user_<id>, disabled and its password cleared, and every log
+   row that named it is rewritten to the pseudonym, all in one transaction.
+   Sessions, lockouts, recovery codes, shortcuts and preferences are dropped. */
+pub fn anonymize_user(
+    conn: &mut Connection,
+    admin_username: &str,
+    target_username: &str,
+    reason: Option<&str>,
+) -> Result<()> {
+    match get_user_id_and_role(conn, admin_username)? {
+        Some((_id, role)) if role == "admin" => {}
+        _ => return Err(anyhow!("Access denied: Only admins can anonymize users.")),
+    }
+    let (user_id, target_name, target_role): (i64, String, String) = conn
+        .query_row(
+            "SELECT id, username, user_status FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![target_username],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("User '{}' not found.", target_username))?;
+
+    if target_name.eq_ignore_ascii_case(admin_username) {
+        return Err(anyhow!("You cannot anonymize your own account."));
+    }
+    if target_role == "admin" {
+        return Err(anyhow!("You cannot anonymize another admin."));
+    }
+    if !require_admin_reauth(conn, admin_username)? {
+        return Err(anyhow!("Authentication failed. Aborting."));
+    }
+
+    let pseudonym = anonymized_username(user_id);
+    let tx = conn.transaction().context("Failed to start transaction")?;
+    // technician_jobs references users(username) without ON UPDATE; check it once everything is renamed
+    tx.execute_batch("PRAGMA defer_foreign_keys = ON;")?;
+
+    // hashed_password is NOT NULL, so an empty string stands in; it can never verify
+    tx.execute(
+        "UPDATE users SET username = ?1, hashed_password = '', is_active = 0, updated_at = datetime('now')
+          WHERE id = ?2",
+        params![pseudonym, user_id],
+    )?;
+    for sql in [
+        "UPDATE security_log SET actor_username = ?1 WHERE actor_username = ?2 COLLATE NOCASE",
+        "UPDATE security_log SET target_username = ?1 WHERE target_username = ?2 COLLATE NOCASE",
+        "UPDATE hvac_activity_log SET username = ?1 WHERE username = ?2 COLLATE NOCASE",
+        "UPDATE diagnostics_log SET actor_username = ?1 WHERE actor_username = ?2 COLLATE NOCASE",
+        "UPDATE technician_jobs SET homeowner_username = ?1 WHERE homeowner_username = ?2",
+        "UPDATE technician_jobs SET technician_username = ?1 WHERE technician_username = ?2",
+        "UPDATE guest_notes SET guest_username = ?1 WHERE guest_username = ?2",
+        "UPDATE overrides SET set_by = ?1 WHERE set_by = ?2 COLLATE NOCASE",
+        "UPDATE system_settings SET updated_by = ?1 WHERE updated_by = ?2 COLLATE NOCASE",
+    ] {
+        tx.execute(sql, params![pseudonym, target_name])?;
+    }
+    for table in ["session_state", "lockouts", "recovery_codes", "recovery_attempts", "menu_shortcuts", "user_preferences"] {
+        tx.execute(&format!("DELETE FROM {table} WHERE username IN (?1, ?2)"), params![pseudonym, target_name])?;
+    }
+    tx.commit().context("Failed to commit anonymization")?;
+
+    let desc = with_reason(format!("Account #{} ({}) anonymized by Admin '{}'", user_id, target_role, admin_username), reason);
+    logger::log_event(conn, admin_username, Some(&pseudonym), "ACCOUNT_DELETED", Some(&desc))?;
+    Ok(())
+}
+
+// True when the account was given a temporary password that must be replaced.
+pub fn password_change_required(conn: &Connection, username: &str) -> Result<bool> {
+    let flag: Option<i64> = conn
+        .query_row(
+            "SELECT force_password_change FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(flag.unwrap_or(0) == 1)
+}
+
+// Replaces a temporary password with a new strong one and clears the change flag.
+pub fn complete_forced_password_change(conn: &Connection, username: &str, new_password: &str) -> Result<()> {
+    let stored_hash: String = conn
+        .query_row(
+            "SELECT hashed_password FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("User '{}' not found.", username))?;
+
+    if auth::verify_password(new_password, &stored_hash)? {
+        return Err(anyhow!("New password must differ from the temporary password."));
+    }
+    if !auth::password_is_strong(new_password, username) {
+        return Err(anyhow!("New password does not meet the strength policy."));
+    }
+
+    let hashed = auth::hash_password(new_password)?;
+    conn.execute(
+        "UPDATE users SET hashed_password = ?1, force_password_change = 0, updated_at = datetime('now')
+          WHERE username = ?2 COLLATE NOCASE",
+        params![hashed, username],
+    )?;
+
+    logger::log_event(conn, username, Some(username), "PASSWORD_CHANGE", Some("Temporary password replaced at login"))?;
+    Ok(())
+}
+
+// ======================================================
+//                   TECHNICIANS
+// ======================================================
+
+// The only access windows a homeowner can grant; stored as integer minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDuration {
+    Minutes30,
+    Minutes60,
+    Minutes90,
+    Minutes120,
+}
+
+impl AccessDuration {
+    pub const ALL: [AccessDuration; 4] = [
+        AccessDuration::Minutes30,
+        AccessDuration::Minutes60,
+        AccessDuration::Minutes90,
+        AccessDuration::Minutes120,
+    ];
+
+    pub fn to_minutes(self) -> i64 {
+        match self {
+            AccessDuration::Minutes30 => 30,
+            AccessDuration::Minutes60 => 60,
+            AccessDuration::Minutes90 => 90,
+            AccessDuration::Minutes120 => 120,
+        }
+    }
+
+    pub fn from_minutes(minutes: i64) -> Option<AccessDuration> {
+        AccessDuration::ALL.into_iter().find(|d| d.to_minutes() == minutes)
+    }
+}
+
+// Accepts "60", " 60 " or "60m"
+impl FromStr for AccessDuration {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let digits = s.trim().trim_end_matches(['m', 'M']);
+        digits
+            .parse::<i64>()
+            .ok()
+            .and_then(AccessDuration::from_minutes)
+            .ok_or_else(|| anyhow!("Invalid access time; must be one of 30, 60, 90, 120."))
+    }
+}
+
+// What the homeowner is shown before a grant is committed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrantSummary {
+    pub technician_username: String,
+    pub duration: AccessDuration,
+    pub job_desc: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrantOutcome {
+    Granted(i64),
+    Cancelled,
+}
+
+pub fn grant_technician_access(conn: &mut Connection, 
+    homeowner_username: &str, technician_username: &str, 
+    duration: AccessDuration, job_desc_raw: &str) -> Result<i64> {
+    match grant_technician_access_confirmed(conn, homeowner_username, technician_username,
+        duration, job_desc_raw, |_| true)? {
+        GrantOutcome::Granted(job_id) => Ok(job_id),
+        GrantOutcome::Cancelled => Err(anyhow!("Grant was cancelled.")),
+    }
+}
+
+// Validates everything first, then lets `confirm` review the normalized grant;
+// nothing is inserted unless it returns true.
+pub fn grant_technician_access_confirmed(conn: &mut Connection,
+    homeowner_username: &str, technician_username: &str,
+    duration: AccessDuration, job_desc_raw: &str,
+    confirm: impl FnOnce(&GrantSummary) -> bool) -> Result<GrantOutcome> {
+
+    let access_minutes = duration.to_minutes();
+
+    // sanitize and length bounds
+    let mut desc = job_desc_raw.trim().to_string();
+    desc.retain(|c| !c.is_control());
+    let desc = desc.split_whitespace().collect::<Vec<_>>().join(" ");
+    if desc.is_empty() {
+        return Err(anyhow!("Description cannot be empty."));
+    }
+    let len = desc.chars().count();
+    if len < 20 || len > 200 {
+        return Err(anyhow!("Description must be 20–200 characters (current: {}).", len));
+    }
+    
+    //validation of actors and their roles
+    let (h_role, h_active): (String, i64) = conn.query_row(
+        "SELECT user_status, is_active FROM users WHERE username = ?1 COLLATE NOCASE",
+        params![homeowner_username],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    ).context("Failed to fetch homeowner record")?;
+
+    if h_role != "homeowner" || h_active != 1 {
+        return Err(anyhow!("Invalid homeowner account or account is inactive."));
+    }
+
+    let (t_role, t_active): (String, i64) = conn.query_row(
+        "SELECT user_status, is_active FROM users WHERE username = ?1 COLLATE NOCASE",
+        params![technician_username],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    ).context("Failed to fetch technician record")?;
+
+    if t_role != "technician" || t_active != 1 {
+        return Err(anyhow!("Invalid technician account or account is inactive."));
+    }
+
+    let summary = GrantSummary {
+        technician_username: technician_username.to_string(),
+        duration,
+        job_desc: desc.clone(),
+    };
+    if !confirm(&summary) {
+        return Ok(GrantOutcome::Cancelled);
+    }
+
+  // insert & transaction
+    let job_id = {
+        let tx = conn.transaction()?;
+        tx.execute(
+            r#"
+            INSERT INTO technician_jobs
+                (homeowner_username, technician_username, status, access_minutes, job_desc, updated_at)
+            VALUES (?1, ?2, 'ACCESS_GRANTED', ?3, ?4, datetime('now'))
             "#,
             // PASS &desc so it is NOT moved and can be reused below
             params![homeowner_username, technician_username, access_minutes, &desc],
@@ -582,11 +1347,21 @@ pub fn grant_technician_access(conn: &mut Connection,
         eprintln!("(log_event failed: {e})");
     }
 
-    Ok(job_id)
+    Ok(GrantOutcome::Granted(job_id))
 }
 
 
 pub fn access_job(conn: &Connection, job_id: i64, technician_username: &str) -> Result<Option<(String, String, String)>> {
+    access_job_with_clock(conn, job_id, technician_username, &RealClock)
+}
+
+pub fn access_job_with_clock(
+    conn: &Connection,
+    job_id: i64,
+    technician_username: &str,
+    clock: &dyn Clock,
+) -> Result<Option<(String, String, String)>> {
+    let now = clock.sqlite_now();
     // Load & validate ownership
     let row = conn
         .query_row(
@@ -607,13 +1382,13 @@ pub fn access_job(conn: &Connection, job_id: i64, technician_username: &str) ->
     let claimed = conn.execute(
         r#"
         UPDATE technician_jobs
-           SET status = 'TECH_ACCESS', updated_at = datetime('now')
+           SET status = 'TECH_ACCESS', updated_at = ?3
          WHERE job_id = ?1
            AND technician_username = ?2 COLLATE NOCASE
            AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-           AND grant_expires > datetime('now')
+           AND grant_expires > ?3
         "#,
-        params![job_id, technician_username],
+        params![job_id, technician_username, now],
     )?;
     if claimed > 0 {
         let _ = crate::logger::log_event(
@@ -627,12 +1402,12 @@ pub fn access_job(conn: &Connection, job_id: i64, technician_username: &str) ->
     let flipped = conn.execute(
         r#"
         UPDATE technician_jobs
-           SET status = 'ACCESS_EXPIRED', updated_at = datetime('now')
+           SET status = 'ACCESS_EXPIRED', updated_at = ?2
          WHERE job_id = ?1
            AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-           AND grant_expires <= datetime('now')
+           AND grant_expires <= ?2
         "#,
-        params![job_id],
+        params![job_id, now],
     )?;
     if flipped > 0 {
         let _ = crate::logger::log_event(
@@ -647,18 +1422,22 @@ pub fn access_job(conn: &Connection, job_id: i64, technician_username: &str) ->
 // Flip any grants that have expired right now to ACCESS_EXPIRED.
 // Returns how many rows were updated. Best-effort logs per row.
 pub fn sweep_expire_grants(conn: &Connection) -> Result<usize> {
+    sweep_expire_grants_with_clock(conn, &RealClock)
+}
+
+pub fn sweep_expire_grants_with_clock(conn: &Connection, clock: &dyn Clock) -> Result<usize> {
     let mut stmt = conn.prepare(
         r#"
         UPDATE technician_jobs
            SET status = 'ACCESS_EXPIRED',
-               updated_at = datetime('now')
+               updated_at = ?1
          WHERE status IN ('ACCESS_GRANTED', 'TECH_ACCESS')
-           AND grant_expires <= datetime('now')
+           AND grant_expires <= ?1
          RETURNING job_id, homeowner_username, technician_username
         "#,
     )?;
 
-    let mut rows = stmt.query([])?;
+    let mut rows = stmt.query(params![clock.sqlite_now()])?;
     let mut changed = 0usize;
 
     while let Some(r) = rows.next()? {
@@ -681,9 +1460,50 @@ pub fn sweep_expire_grants(conn: &Connection) -> Result<usize> {
 }
 
 
+// Homeowner a technician currently holds a live grant for (most recent grant wins).
+pub fn tech_active_homeowner(conn: &Connection, technician_username: &str) -> Result<Option<String>> {
+    let homeowner_opt: Option<String> = conn
+        .query_row(
+            r#"
+            SELECT homeowner_username
+              FROM technician_jobs
+             WHERE technician_username = ?1 COLLATE NOCASE
+               AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
+               AND grant_expires > datetime('now')
+             ORDER BY updated_at DESC
+             LIMIT 1
+            "#,
+            params![technician_username],
+            |r| r.get(0),
+        )
+        .optional()?;
+
+    match homeowner_opt {
+        Some(h) if tech_has_perm(conn, technician_username, &h)? => Ok(Some(h)),
+        _ => Ok(None),
+    }
+}
+
+// Homeowner context for profile management.
+// Homeowners act for themselves, technicians only under a live grant, admins system-wide (None).
+pub fn profile_edit_context(conn: &Connection, username: &str, role: &str) -> Result<Option<String>> {
+    match role {
+        "homeowner" => Ok(Some(username.to_string())),
+        "admin" => Ok(None),
+        "technician" => match tech_active_homeowner(conn, username)? {
+            Some(h) => Ok(Some(h)),
+            None => Err(anyhow!("Access denied: technician '{}' has no active job grant for profile changes.", username)),
+        },
+        _ => Err(anyhow!("Access denied: role '{}' cannot manage profiles.", role)),
+    }
+}
+
 pub fn tech_has_perm(conn: &Connection, acting_username: &str, homeowner_username: &str) -> Result<bool> {
-    
-    let _ = crate::db::sweep_expire_grants(conn);
+    tech_has_perm_with_clock(conn, acting_username, homeowner_username, &RealClock)
+}
+
+pub fn tech_has_perm_with_clock(conn: &Connection, acting_username: &str, homeowner_username: &str, clock: &dyn Clock) -> Result<bool> {
+    let _ = sweep_expire_grants_with_clock(conn, clock);
 
     //fetch roles/enabled once
     let target: Option<(String, i64)> = conn
@@ -730,10 +1550,10 @@ pub fn tech_has_perm(conn: &Connection, acting_username: &str, homeowner_usernam
                 SELECT 1
                   FROM technician_jobs
                  WHERE technician_username = ?1 COLLATE NOCASE AND homeowner_username  = ?2 COLLATE NOCASE AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-                   AND datetime(updated_at, printf('+%d minutes', access_minutes)) > datetime('now')
+                   AND grant_expires > ?3
                  LIMIT 1
                 "#,
-                params![acting_username, homeowner_username],
+                params![acting_username, homeowner_username, clock.sqlite_now()],
                 |r| r.get(0),
             )
             .optional()?;
@@ -744,29 +1564,179 @@ pub fn tech_has_perm(conn: &Connection, acting_username: &str, homeowner_usernam
     Ok(false)
 }
 
+/* Who the user can act on right now (technicians), or who can act on them
+   (homeowners); one name per live grant holder, soonest to expire first. Admins get
+   ["all"], anyone else an empty list. */
+pub fn my_permissions(conn: &Connection, username: &str) -> Result<Vec<String>> {
+    my_permissions_with_clock(conn, username, &RealClock)
+}
+
+pub fn my_permissions_with_clock(conn: &Connection, username: &str, clock: &dyn Clock) -> Result<Vec<String>> {
+    sweep_expire_grants_with_clock(conn, clock)?;
+
+    let role: Option<String> = conn
+        .query_row(
+            "SELECT user_status FROM users WHERE username = ?1 COLLATE NOCASE AND is_active = 1",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+
+    let (other, mine) = match role.as_deref() {
+        Some("admin") => return Ok(vec!["all".to_string()]),
+        Some("technician") => ("homeowner_username", "technician_username"),
+        Some("homeowner") => ("technician_username", "homeowner_username"),
+        _ => return Ok(Vec::new()),
+    };
+
+    let sql = format!(
+        "SELECT {other}, MIN(grant_expires) AS expires
+           FROM technician_jobs
+          WHERE {mine} = ?1 COLLATE NOCASE
+            AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
+            AND grant_expires > ?2
+          GROUP BY {other} COLLATE NOCASE
+          ORDER BY expires, {other}"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let names = stmt
+        .query_map(params![username, clock.sqlite_now()], |r| r.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    // A technician's list must agree with tech_has_perm (live homeowner accounts only)
+    if role.as_deref() == Some("technician") {
+        let mut allowed = Vec::new();
+        for h in names {
+            if tech_has_perm_with_clock(conn, username, &h, clock)? {
+                allowed.push(h);
+            }
+        }
+        return Ok(allowed);
+    }
+    Ok(names)
+}
+
+// Where a job stands now. A grant whose time ran out counts as expired even if
+// sweep_expire_grants has not updated the stored status yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Granted,   // ACCESS_GRANTED
+    InAccess,  // TECH_ACCESS
+    Expired,   // ACCESS_EXPIRED
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Granted => "ACCESS_GRANTED",
+            JobStatus::InAccess => "TECH_ACCESS",
+            JobStatus::Expired => "ACCESS_EXPIRED",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "ACCESS_GRANTED" => Some(JobStatus::Granted),
+            "TECH_ACCESS" => Some(JobStatus::InAccess),
+            "ACCESS_EXPIRED" => Some(JobStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+// One technician_jobs row; times are UTC
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobRecord {
+    pub job_id: i64,
+    pub homeowner: String,
+    pub technician: String,
+    pub status: JobStatus,
+    pub access_minutes: i64,
+    pub grant_start: NaiveDateTime,
+    pub grant_expires: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub job_desc: String,
+    pub notes: Option<String>,
+}
+
+// Every field narrows the result; the default matches all jobs
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter {
+    pub homeowner: Option<String>,
+    pub technician: Option<String>,
+    pub participant: Option<String>, // homeowner or technician
+    pub status: Option<JobStatus>,
+}
+
+fn parse_db_time(idx: usize, raw: &str) -> rusqlite::Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S").map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+// Jobs matching `filter`, latest expiry first
+pub fn list_jobs(conn: &Connection, filter: JobFilter) -> Result<Vec<JobRecord>> {
+    list_jobs_with_clock(conn, filter, &RealClock)
+}
+
+pub fn list_jobs_with_clock(conn: &Connection, filter: JobFilter, clock: &dyn Clock) -> Result<Vec<JobRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT job_id, homeowner_username, technician_username, effective_status, access_minutes,
+               grant_start, grant_expires, created_at, updated_at, job_desc, notes
+        FROM (
+            SELECT *,
+                   CASE WHEN status IN ('ACCESS_GRANTED', 'TECH_ACCESS') AND grant_expires <= ?5
+                        THEN 'ACCESS_EXPIRED' ELSE status END AS effective_status
+            FROM technician_jobs
+        )
+        WHERE (?1 IS NULL OR homeowner_username = ?1 COLLATE NOCASE)
+          AND (?2 IS NULL OR technician_username = ?2 COLLATE NOCASE)
+          AND (?3 IS NULL OR homeowner_username = ?3 COLLATE NOCASE OR technician_username = ?3 COLLATE NOCASE)
+          AND (?4 IS NULL OR effective_status = ?4)
+        ORDER BY grant_expires DESC, job_id DESC
+        "#,
+    )?;
+    let rows = stmt.query_map(
+        params![filter.homeowner, filter.technician, filter.participant, filter.status.map(JobStatus::as_str), clock.sqlite_now()],
+        |r| {
+            let status: String = r.get(3)?;
+            Ok(JobRecord {
+                job_id: r.get(0)?,
+                homeowner: r.get(1)?,
+                technician: r.get(2)?,
+                status: JobStatus::from_db(&status).unwrap_or(JobStatus::Expired),
+                access_minutes: r.get(4)?,
+                grant_start: parse_db_time(5, &r.get::<_, String>(5)?)?,
+                grant_expires: parse_db_time(6, &r.get::<_, String>(6)?)?,
+                created_at: parse_db_time(7, &r.get::<_, String>(7)?)?,
+                updated_at: parse_db_time(8, &r.get::<_, String>(8)?)?,
+                job_desc: r.get(9)?,
+                notes: r.get(10)?,
+            })
+        },
+    )?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
 // List all active technician assignments
 pub fn list_active_grants(conn: &Connection, username: &str) -> Result<()> {
     
     let _ = crate::db::sweep_expire_grants(conn);
 
-    let mut stmt = conn.prepare(
-        r#"
-        SELECT job_id, homeowner_username, technician_username, status, grant_start, grant_expires, access_minutes
-        FROM technician_jobs
-        WHERE (homeowner_username = ?1 COLLATE NOCASE OR technician_username = ?1 COLLATE NOCASE)
-          AND grant_expires > datetime('now')
-          AND status = 'ACCESS_GRANTED'
-        ORDER BY grant_expires DESC
-        "#
-    )?;
-    let mut rows = stmt.query(params![username])?;
+    let jobs = list_jobs(conn, JobFilter {
+        participant: Some(username.to_string()),
+        status: Some(JobStatus::Granted),
+        ..JobFilter::default()
+    })?;
     println!("Active grants visible to '{}':", username);
     println!("{:<8} {:<15} {:<15} {:<12} {:<20} {:<20} {:<5}",
         "job_id","homeowner","technician","status","start","expires","mins");
-    while let Some(r) = rows.next()? {
-        let (jid,h,t,st,gs,ge,m):(i64,String,String,String,String,String,i64) =
-            (r.get(0)?,r.get(1)?,r.get(2)?,r.get(3)?,r.get(4)?,r.get(5)?,r.get(6)?);
-        println!("{:<8} {:<15} {:<15} {:<12} {:<20} {:<20} {:<5}", jid,h,t,st,gs,ge,m);
+    for job in jobs {
+        println!("{:<8} {:<15} {:<15} {:<12} {:<20} {:<20} {:<5}",
+            job.job_id, job.homeowner, job.technician, job.status.as_str(),
+            job.grant_start.to_string(), job.grant_expires.to_string(), job.access_minutes);
     }
     Ok(())
 }
@@ -882,6 +1852,72 @@ pub fn update_session(conn: &Connection, username: Option<&str>) -> Result<Strin
     Ok(token_plain.to_string())
 }
 
+// Remove every expired session row (any user, including the anonymous one); returns how many
+pub fn cleanup_stale_sessions(conn: &Connection) -> Result<usize> {
+    Ok(conn.execute(
+        "DELETE FROM session_state WHERE session_expires <= datetime('now')",
+        [],
+    )?)
+}
+
+// When the user's current session started (UTC), if they have one
+pub fn session_login_time(conn: &Connection, username: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT login_time FROM session_state WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .flatten())
+}
+
+// What a user did in hvac_activity_log during one session, for the logout summary
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SessionActivity {
+    pub hvac_changes: usize,     // mode and temperature changes
+    pub profiles_applied: usize,
+    pub profiles_edited: usize,  // edits and resets
+}
+
+impl SessionActivity {
+    // e.g. "This session: 3 HVAC changes, 1 profile applied."
+    pub fn summary(&self) -> String {
+        let plural = |n: usize, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+        let mut parts = vec![
+            plural(self.hvac_changes, "HVAC change", "HVAC changes"),
+            plural(self.profiles_applied, "profile applied", "profiles applied"),
+        ];
+        if self.profiles_edited > 0 {
+            parts.push(plural(self.profiles_edited, "profile edited", "profiles edited"));
+        }
+        format!("This session: {}.", parts.join(", "))
+    }
+}
+
+// Counts the user's activity rows at or after `since_utc` ("YYYY-MM-DD HH:MM:SS")
+pub fn session_activity_since(conn: &Connection, username: &str, since_utc: &str) -> Result<SessionActivity> {
+    let mut stmt = conn.prepare(
+        "SELECT action_type, COUNT(*) FROM hvac_activity_log
+          WHERE username = ?1 COLLATE NOCASE AND datetime(timestamp) >= datetime(?2)
+          GROUP BY action_type",
+    )?;
+    let rows = stmt.query_map(params![username, since_utc], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?;
+
+    let mut activity = SessionActivity::default();
+    for row in rows {
+        let (action, count) = row?;
+        let count = count as usize;
+        match action.as_str() {
+            "MODE_CHANGED" | "TEMPERATURE_CHANGED" => activity.hvac_changes += count,
+            "PROFILE_APPLIED" => activity.profiles_applied += count,
+            "PROFILE_EDITED" | "PROFILE_RESET" => activity.profiles_edited += count,
+            _ => {}
+        }
+    }
+    Ok(activity)
+}
+
 // Delete the active session of the user
 pub fn end_session(conn: &Connection, username: &str) -> Result<()> {
     conn.execute(
@@ -889,28 +1925,145 @@ pub fn end_session(conn: &Connection, username: &str) -> Result<()> {
     Ok(())
 }
 
+/* Admin tool for a stuck or ghost session that keeps a user out (concurrent
+   logins are denied). Removes every session_state row for the user, live or
+   not, and returns how many went. An admin cannot clear their own session. */
+pub fn logout_all_for_user(conn: &Connection, admin_username: &str, target_username: &str) -> Result<usize> {
+    let target_name: String = conn
+        .query_row(
+            "SELECT username FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![target_username],
+            |r| r.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("User '{}' not found.", target_username))?;
+    if target_name.eq_ignore_ascii_case(admin_username) {
+        return Err(anyhow!("Use [0] Log out to end your own session."));
+    }
+
+    let removed = conn.execute(
+        "DELETE FROM session_state WHERE username = ?1 COLLATE NOCASE",
+        params![target_name],
+    )?;
+    let desc = format!("All sessions of '{}' ended by Admin '{}' ({} removed)", target_name, admin_username, removed);
+    logger::log_event(conn, admin_username, Some(&target_name), "LOGOUT", Some(&desc))?;
+    Ok(removed)
+}
+
 // ======================================================
-//                     PROFILES (HVAC)
+//                 LOGICAL INTEGRITY CHECK
 // ======================================================
 
-#[derive(Debug, Clone)]
-pub struct ProfileRow {
-    pub name: String,
-    pub mode: String,
-    pub target_temp: f32,
-    pub greeting: Option<String>,
-    pub description: Option<String>,
-    pub heater_status: String,
-    pub ac_status: String,
-    pub light_status: String,
-    pub fan_speed: String,
-    pub vacation_start_date: Option<String>,
-    pub vacation_end_date: Option<String>,
-}
+/* Consistency problems PRAGMA integrity_check can't see, one readable line each:
+   guests tied to a missing or non-homeowner account, jobs naming users that
+   are gone or have the wrong role, sessions left behind by deleted users, and
+   hvac_state not holding exactly one row. Empty means nothing was found. */
+pub fn logical_integrity_check(conn: &Connection) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
 
-fn default_profile_row(name: &str) -> Option<ProfileRow> {
-    match name {
-        "Day" => Some(ProfileRow {
+    let mut stmt = conn.prepare(
+        "SELECT g.username, g.homeowner_id, h.username, h.user_status
+           FROM users g
+           LEFT JOIN users h ON h.id = g.homeowner_id
+          WHERE g.user_status = 'guest'
+            AND (h.id IS NULL OR h.user_status != 'homeowner')
+          ORDER BY g.username",
+    )?;
+    let rows = stmt.query_map([], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, Option<i64>>(1)?,
+            r.get::<_, Option<String>>(2)?,
+            r.get::<_, Option<String>>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (guest, homeowner_id, owner, owner_role) = row?;
+        problems.push(match (homeowner_id, owner, owner_role) {
+            (None, _, _) => format!("Guest '{}' has no homeowner", guest),
+            (Some(id), None, _) => format!("Guest '{}' points to missing homeowner id {}", guest, id),
+            (Some(_), Some(owner), role) => format!(
+                "Guest '{}' points to '{}', who is a {} rather than a homeowner",
+                guest, owner, role.unwrap_or_default()
+            ),
+        });
+    }
+
+    // (column, role the column must name)
+    for (column, role) in [("homeowner_username", "homeowner"), ("technician_username", "technician")] {
+        let sql = format!(
+            "SELECT j.job_id, j.{col}, u.user_status
+               FROM technician_jobs j
+               LEFT JOIN users u ON u.username = j.{col} COLLATE NOCASE
+              WHERE u.id IS NULL OR u.user_status != ?1
+              ORDER BY j.job_id",
+            col = column
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![role], |r| {
+            Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<String>>(2)?))
+        })?;
+        for row in rows {
+            let (job_id, name, status) = row?;
+            problems.push(match status {
+                None => format!("Job #{} references missing {} '{}'", job_id, role, name),
+                Some(s) => format!("Job #{} lists '{}' as {}, but they are a {}", job_id, name, role, s),
+            });
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT s.username
+           FROM session_state s
+           LEFT JOIN users u ON u.username = s.username COLLATE NOCASE
+          WHERE u.id IS NULL AND s.session_token_hash IS NOT NULL
+          ORDER BY s.username",
+    )?;
+    let orphans = stmt.query_map([], |r| r.get::<_, String>(0))?;
+    for name in orphans {
+        problems.push(format!("Active session for deleted user '{}'", name?));
+    }
+
+    let hvac_rows: i64 = conn.query_row("SELECT COUNT(*) FROM hvac_state", [], |r| r.get(0))?;
+    if hvac_rows != 1 {
+        problems.push(format!("hvac_state has {} rows instead of exactly one", hvac_rows));
+    }
+
+    Ok(problems)
+}
+
+// ======================================================
+//                     PROFILES (HVAC)
+// ======================================================
+
+// Field names double as the JSON keys, so renaming one is a breaking change for API users
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileRow {
+    pub name: String,
+    pub mode: String,
+    pub target_temp: f32,
+    #[serde(default)]
+    pub greeting: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub heater_status: String,
+    pub ac_status: String,
+    pub light_status: String,
+    pub fan_speed: String,
+    #[serde(default)]
+    pub vacation_start_date: Option<String>,
+    #[serde(default)]
+    pub vacation_end_date: Option<String>,
+    #[serde(default)]
+    pub target_humidity: Option<f32>,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+fn default_profile_row(name: &str) -> Option<ProfileRow> {
+    match name {
+        "Day" => Some(ProfileRow {
             name: "Day".to_string(),
             mode: "Auto".to_string(),
             target_temp: 22.0,
@@ -922,6 +2075,8 @@ fn default_profile_row(name: &str) -> Option<ProfileRow> {
             fan_speed: "Medium".to_string(),
             vacation_start_date: None,
             vacation_end_date: None,
+            target_humidity: None,
+            icon: None,
         }),
         "Night" => Some(ProfileRow {
             name: "Night".to_string(),
@@ -935,6 +2090,8 @@ fn default_profile_row(name: &str) -> Option<ProfileRow> {
             fan_speed: "Low".to_string(),
             vacation_start_date: None,
             vacation_end_date: None,
+            target_humidity: None,
+            icon: None,
         }),
         "Sleep" => Some(ProfileRow {
             name: "Sleep".to_string(),
@@ -948,6 +2105,8 @@ fn default_profile_row(name: &str) -> Option<ProfileRow> {
             fan_speed: "Low".to_string(),
             vacation_start_date: None,
             vacation_end_date: None,
+            target_humidity: None,
+            icon: None,
         }),
         "Party" => Some(ProfileRow {
             name: "Party".to_string(),
@@ -961,6 +2120,8 @@ fn default_profile_row(name: &str) -> Option<ProfileRow> {
             fan_speed: "High".to_string(),
             vacation_start_date: None,
             vacation_end_date: None,
+            target_humidity: None,
+            icon: None,
         }),
         "Vacation" => Some(ProfileRow {
             name: "Vacation".to_string(),
@@ -974,6 +2135,8 @@ fn default_profile_row(name: &str) -> Option<ProfileRow> {
             fan_speed: "Low".to_string(),
             vacation_start_date: None,
             vacation_end_date: None,
+            target_humidity: None,
+            icon: None,
         }),
         "Away" => Some(ProfileRow {
             name: "Away".to_string(),
@@ -987,11 +2150,97 @@ fn default_profile_row(name: &str) -> Option<ProfileRow> {
             fan_speed: "Low".to_string(),
             vacation_start_date: None,
             vacation_end_date: None,
+            target_humidity: None,
+            icon: None,
         }),
         _ => None,
     }
 }
 
+// ======================================================
+//                      MIGRATIONS
+// ======================================================
+// The schema version lives in PRAGMA user_version. Each step is idempotent
+// (it checks before altering), so databases that predate versioning can run
+// every step safely. Append new steps; never reorder or remove old ones.
+
+type Migration = (i64, &'static str, fn(&Connection) -> Result<()>);
+
+const MIGRATIONS: &[Migration] = &[
+    (1, "users: force_password_change and bulk_disabled", migrate_users_table),
+    (2, "profiles: light_status, fan_speed and target_humidity", migrate_profiles_table),
+    (3, "hvac_state: light_status and current_profile", migrate_hvac_state_table),
+    (4, "security_log: technician event types", migrate_security_log_table),
+    (5, "user_preferences: temp_unit", migrate_user_preferences_table),
+    (6, "session_state: reauth_at", migrate_session_state_table),
+    (7, "user_preferences: temp_precision", migrate_user_preferences_precision),
+    (8, "security_log: SETTINGS_CHANGED event type", migrate_security_log_settings_event),
+    (9, "profiles: icon", migrate_profiles_icon),
+    (10, "user_preferences: greet_once", migrate_user_preferences_greet_once),
+    (11, "security_log: UTC RFC3339 timestamps", migrate_security_log_timestamps),
+    (12, "status_snapshot: seed from hvac_state", migrate_status_snapshot),
+    (13, "security_log: GUEST_LOGIN event type", migrate_security_log_guest_login_event),
+    (14, "hvac_state: target_humidity", migrate_hvac_state_target_humidity),
+    (15, "hvac_activity_log: UTC RFC3339 timestamps", migrate_hvac_activity_log_timestamps),
+    (16, "security_log: LOGIN_REFUSED event type", migrate_security_log_login_refused_event),
+];
+
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.last().map(|(v, _, _)| *v).unwrap_or(0)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    Ok(conn.query_row("PRAGMA user_version", [], |r| r.get(0))?)
+}
+
+// Steps newer than the database's version, in order
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<(i64, &'static str)>> {
+    let current = schema_version(conn)?;
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|(v, _, _)| *v > current)
+        .map(|(v, desc, _)| (*v, *desc))
+        .collect())
+}
+
+// Apply pending steps, bumping the version after each; returns (before, after)
+fn run_migrations(conn: &Connection) -> Result<(i64, i64)> {
+    let before = schema_version(conn)?;
+    for (version, desc, step) in MIGRATIONS.iter().filter(|(v, _, _)| *v > before) {
+        step(conn).with_context(|| format!("Migration {} ({}) failed", version, desc))?;
+        conn.pragma_update(None, "user_version", version)?;
+    }
+    Ok((before, schema_version(conn)?))
+}
+
+// Open without touching the schema, for status checks
+pub fn open_without_migrating<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
+    Connection::open(db_path).context("Failed to open db")
+}
+
+/* Read-only handle for reports: no init, migrations or session cleanup, and
+   SQLite refuses any write. An out-of-date schema is refused rather than read. */
+pub fn open_read_only<P: AsRef<Path>>(db_path: P) -> Result<Connection> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .context("Failed to open db read-only")?;
+    let version = schema_version(&conn)?;
+    if version < latest_schema_version() {
+        return Err(anyhow!(
+            "Database schema is at version {} but {} is required; run --migrate first",
+            version,
+            latest_schema_version()
+        ));
+    }
+    Ok(conn)
+}
+
+// Full initialization (tables, migrations, seed data) on an already-open database
+pub fn migrate_database(conn: &Connection) -> Result<(i64, i64)> {
+    let before = schema_version(conn)?;
+    init_schema(conn)?;
+    Ok((before, schema_version(conn)?))
+}
+
 fn migrate_profiles_table(conn: &Connection) -> Result<()> {
     // Check if heater_status column exists
     let column_check: Result<i64, _> = conn.query_row(
@@ -1151,10 +2400,118 @@ fn migrate_profiles_table(conn: &Connection) -> Result<()> {
             )?;
         }
     }
+
+    // target_humidity is optional, so it can be added in place after the rebuilds above
+    let humidity_check: Result<i64, _> = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('profiles') WHERE name='target_humidity'",
+        [],
+        |r| r.get(0),
+    );
+    if let Ok(0) = humidity_check {
+        conn.execute_batch(
+            "ALTER TABLE profiles ADD COLUMN target_humidity REAL
+                 CHECK (target_humidity IS NULL OR target_humidity BETWEEN 30 AND 60);",
+        )?;
+    }
     
     Ok(())
 }
 
+fn migrate_user_preferences_greet_once(conn: &Connection) -> Result<()> {
+    let has_column: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('user_preferences') WHERE name='greet_once'",
+        [],
+        |r| r.get(0),
+    )?;
+    if has_column == 0 {
+        conn.execute_batch(
+            "ALTER TABLE user_preferences ADD COLUMN greet_once INTEGER NOT NULL DEFAULT 0
+                CHECK (greet_once IN (0, 1));",
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_profiles_icon(conn: &Connection) -> Result<()> {
+    let has_icon: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('profiles') WHERE name='icon'",
+        [],
+        |r| r.get(0),
+    )?;
+    if has_icon == 0 {
+        conn.execute_batch("ALTER TABLE profiles ADD COLUMN icon TEXT;")?;
+    }
+    Ok(())
+}
+
+fn migrate_users_table(conn: &Connection) -> Result<()> {
+    let column_check: Result<i64, _> = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('users') WHERE name='force_password_change'",
+        [],
+        |r| r.get(0),
+    );
+
+    // users is referenced by other tables, so add the column in place instead of recreating it
+    if let Ok(0) = column_check {
+        conn.execute_batch(
+            "ALTER TABLE users ADD COLUMN force_password_change INTEGER NOT NULL DEFAULT 0;",
+        )?;
+    }
+
+    let bulk_check: Result<i64, _> = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('users') WHERE name='bulk_disabled'",
+        [],
+        |r| r.get(0),
+    );
+    if let Ok(0) = bulk_check {
+        conn.execute_batch("ALTER TABLE users ADD COLUMN bulk_disabled INTEGER NOT NULL DEFAULT 0;")?;
+    }
+
+    Ok(())
+}
+
+fn migrate_user_preferences_table(conn: &Connection) -> Result<()> {
+    let unit_check: Result<i64, _> = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('user_preferences') WHERE name='temp_unit'",
+        [],
+        |r| r.get(0),
+    );
+    if let Ok(0) = unit_check {
+        conn.execute_batch(
+            "ALTER TABLE user_preferences ADD COLUMN temp_unit TEXT NOT NULL DEFAULT 'celsius'
+                 CHECK (temp_unit IN ('celsius','fahrenheit'));",
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_user_preferences_precision(conn: &Connection) -> Result<()> {
+    let precision_check: Result<i64, _> = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('user_preferences') WHERE name='temp_precision'",
+        [],
+        |r| r.get(0),
+    );
+    if let Ok(0) = precision_check {
+        conn.execute_batch(
+            "ALTER TABLE user_preferences ADD COLUMN temp_precision INTEGER NOT NULL DEFAULT 1
+                 CHECK (temp_precision IN (0, 1));",
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_session_state_table(conn: &Connection) -> Result<()> {
+    let reauth_check: Result<i64, _> = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('session_state') WHERE name='reauth_at'",
+        [],
+        |r| r.get(0),
+    );
+    if let Ok(0) = reauth_check {
+        conn.execute_batch("ALTER TABLE session_state ADD COLUMN reauth_at TEXT;")?;
+    }
+    Ok(())
+}
+
 fn migrate_hvac_state_table(conn: &Connection) -> Result<()> {
     // Check if light_status column exists in hvac_state table
     let light_column_check: Result<i64, _> = conn.query_row(
@@ -1210,7 +2567,128 @@ fn migrate_hvac_state_table(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+// Copies the current state row so the snapshot starts in step with hvac_state
+fn migrate_status_snapshot(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "INSERT OR IGNORE INTO status_snapshot (id, mode, target_temperature, current_profile)
+             SELECT id, mode, target_temperature, current_profile FROM hvac_state WHERE id = 1;",
+    )?;
+    Ok(())
+}
+
 fn migrate_security_log_table(conn: &Connection) -> Result<()> {
+    rebuild_security_log_if_missing(conn, &["ACCESS_GRANTED", "TECH_ACCESS"])
+}
+
+fn migrate_security_log_settings_event(conn: &Connection) -> Result<()> {
+    rebuild_security_log_if_missing(conn, &["SETTINGS_CHANGED"])
+}
+
+fn migrate_security_log_guest_login_event(conn: &Connection) -> Result<()> {
+    rebuild_security_log_if_missing(conn, &["GUEST_LOGIN"])
+}
+
+fn migrate_security_log_login_refused_event(conn: &Connection) -> Result<()> {
+    rebuild_security_log_if_missing(conn, &["LOGIN_REFUSED"])
+}
+
+// Nullable, so it is added in place; seeded from the profile that is active now
+fn migrate_hvac_state_target_humidity(conn: &Connection) -> Result<()> {
+    let has_column: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('hvac_state') WHERE name='target_humidity'",
+        [],
+        |r| r.get(0),
+    )?;
+    if has_column == 0 {
+        conn.execute_batch(
+            "ALTER TABLE hvac_state ADD COLUMN target_humidity REAL;
+             UPDATE hvac_state SET target_humidity =
+                 (SELECT p.target_humidity FROM profiles p WHERE p.name = hvac_state.current_profile);",
+        )?;
+    }
+    Ok(())
+}
+
+// The rebuilt table defaults to the canonical format too; existing rows are then converted
+fn migrate_security_log_timestamps(conn: &Connection) -> Result<()> {
+    rebuild_security_log_if_missing(conn, &["%Y-%m-%dT%H:%M:%SZ"])?;
+    normalize_security_log_timestamps(conn)?;
+    Ok(())
+}
+
+/* Same canonical UTC RFC3339 as security_log. The rebuilt table gets the new
+   default; the SQLite UTC text of existing rows converts in place. */
+fn migrate_hvac_activity_log_timestamps(conn: &Connection) -> Result<()> {
+    let schema: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type='table' AND name='hvac_activity_log'",
+            [],
+            |r| r.get(0),
+        )
+        .optional()?;
+    if schema.is_some_and(|sql| !sql.contains("%Y-%m-%dT%H:%M:%SZ")) {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE hvac_activity_log_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                username TEXT NOT NULL,
+                user_role TEXT NOT NULL,
+                action_type TEXT NOT NULL CHECK(
+                    action_type IN ('PROFILE_APPLIED', 'PROFILE_EDITED', 'PROFILE_RESET', 'TEMPERATURE_CHANGED', 'MODE_CHANGED')
+                ),
+                profile_name TEXT,
+                old_value TEXT,
+                new_value TEXT,
+                description TEXT,
+                timestamp TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+            );
+
+            INSERT INTO hvac_activity_log_new (id, username, user_role, action_type, profile_name, old_value, new_value, description, timestamp)
+            SELECT id, username, user_role, action_type, profile_name, old_value, new_value, description, timestamp
+            FROM hvac_activity_log;
+
+            DROP TABLE hvac_activity_log;
+            ALTER TABLE hvac_activity_log_new RENAME TO hvac_activity_log;
+
+            CREATE INDEX IF NOT EXISTS ix_hvac_log_username ON hvac_activity_log(username);
+            CREATE INDEX IF NOT EXISTS ix_hvac_log_timestamp ON hvac_activity_log(timestamp);
+            CREATE INDEX IF NOT EXISTS ix_hvac_log_action ON hvac_activity_log(action_type);
+            "#,
+        )?;
+    }
+    conn.execute(
+        "UPDATE hvac_activity_log SET timestamp = strftime('%Y-%m-%dT%H:%M:%SZ', timestamp)
+          WHERE strftime('%Y-%m-%dT%H:%M:%SZ', timestamp) IS NOT NULL
+            AND timestamp <> strftime('%Y-%m-%dT%H:%M:%SZ', timestamp)",
+        [],
+    )?;
+    Ok(())
+}
+
+/* Rewrites security_log timestamps written as Eastern RFC3339 or SQLite UTC text
+   into UTC RFC3339 (see logger::canonical_log_timestamp). Unparseable values are left alone.
+   Returns how many rows changed. */
+pub fn normalize_security_log_timestamps(conn: &Connection) -> Result<usize> {
+    let rows: Vec<(i64, String)> = {
+        let mut stmt = conn.prepare("SELECT id, timestamp FROM security_log")?;
+        stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?
+    };
+    let mut changed = 0;
+    for (id, raw) in rows {
+        if let Some(canonical) = logger::canonical_log_timestamp(&raw)
+            && canonical != raw
+        {
+            conn.execute("UPDATE security_log SET timestamp = ?1 WHERE id = ?2", params![canonical, id])?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+// Recreate security_log with the current schema when its SQL lacks any of `required`
+// (event types in the CHECK list, or the timestamp default)
+fn rebuild_security_log_if_missing(conn: &Connection, required: &[&str]) -> Result<()> {
     // Check if we need to migrate by examining the table schema
     let needs_migration = conn.query_row(
         "SELECT sql FROM sqlite_master WHERE type='table' AND name='security_log'",
@@ -1220,7 +2698,7 @@ fn migrate_security_log_table(conn: &Connection) -> Result<()> {
     
     if let Some(schema) = needs_migration {
         // Check if schema contains the new event types
-        if !schema.contains("ACCESS_GRANTED") || !schema.contains("TECH_ACCESS") {
+        if required.iter().any(|t| !schema.contains(t)) {
             // Recreate table with updated CHECK constraint
             conn.execute_batch(
                 r#"
@@ -1235,11 +2713,12 @@ fn migrate_security_log_table(conn: &Connection) -> Result<()> {
                             'LOCKOUT', 'SESSION_LOCKOUT', 'LOCKOUT_CLEARED',
                             'ACCOUNT_DELETED', 'ACCOUNT_DISABLED', 'ACCOUNT_ENABLED', 
                             'ADMIN_LOGIN', 'PASSWORD_CHANGE', 'HVAC',
-                            'ACCESS_GRANTED', 'TECH_ACCESS', 'ACCESS_EXPIRED'
+                            'ACCESS_GRANTED', 'TECH_ACCESS', 'ACCESS_EXPIRED', 'SETTINGS_CHANGED',
+                            'GUEST_LOGIN', 'LOGIN_REFUSED'
                         )
                     ),
                     description TEXT,
-                    timestamp TEXT NOT NULL DEFAULT (datetime('now'))
+                    timestamp TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
                 );
 
                 -- Copy existing data
@@ -1278,55 +2757,87 @@ fn seed_default_profiles(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+const PROFILE_COLUMNS: &str = "name, mode, target_temp, greeting, description, heater_status, ac_status, light_status, fan_speed, vacation_start_date, vacation_end_date, target_humidity, icon";
+
+// One profiles row selected with PROFILE_COLUMNS
+fn profile_row(r: &rusqlite::Row) -> rusqlite::Result<ProfileRow> {
+    Ok(ProfileRow {
+        name: r.get(0)?,
+        mode: r.get(1)?,
+        target_temp: r.get(2)?,
+        greeting: r.get(3)?,
+        description: r.get(4)?,
+        heater_status: r.get::<_, Option<String>>(5)?.unwrap_or_else(|| "Auto".to_string()),
+        ac_status: r.get::<_, Option<String>>(6)?.unwrap_or_else(|| "Auto".to_string()),
+        light_status: r.get::<_, Option<String>>(7)?.unwrap_or_else(|| "OFF".to_string()),
+        fan_speed: r.get::<_, Option<String>>(8)?.unwrap_or_else(|| "Medium".to_string()),
+        vacation_start_date: r.get(9)?,
+        vacation_end_date: r.get(10)?,
+        target_humidity: r.get(11)?,
+        icon: r.get(12)?,
+    })
+}
+
 pub fn get_profile_row(conn: &Connection, name: &str) -> Result<Option<ProfileRow>> {
-    let mut stmt = conn.prepare(
-        "SELECT name, mode, target_temp, greeting, description, heater_status, ac_status, light_status, fan_speed, vacation_start_date, vacation_end_date FROM profiles WHERE name = ?1",
-    )?;
-    let row = stmt
-        .query_row(params![name], |r| {
-            Ok(ProfileRow {
-                name: r.get::<_, String>(0)?,
-                mode: r.get::<_, String>(1)?,
-                target_temp: r.get::<_, f32>(2)?,
-                greeting: r.get::<_, Option<String>>(3)?,
-                description: r.get::<_, Option<String>>(4)?,
-                heater_status: r.get::<_, Option<String>>(5)?.unwrap_or_else(|| "Auto".to_string()),
-                ac_status: r.get::<_, Option<String>>(6)?.unwrap_or_else(|| "Auto".to_string()),
-                light_status: r.get::<_, Option<String>>(7)?.unwrap_or_else(|| "OFF".to_string()),
-                fan_speed: r.get::<_, Option<String>>(8)?.unwrap_or_else(|| "Medium".to_string()),
-                vacation_start_date: r.get::<_, Option<String>>(9)?,
-                vacation_end_date: r.get::<_, Option<String>>(10)?,
-            })
-        })
-        .optional()?;
-    Ok(row)
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles WHERE name = ?1", PROFILE_COLUMNS))?;
+    Ok(stmt.query_row(params![name], profile_row).optional()?)
 }
 
 pub fn list_profile_rows(conn: &Connection) -> Result<Vec<ProfileRow>> {
-    let mut stmt = conn.prepare(
-        "SELECT name, mode, target_temp, greeting, description, heater_status, ac_status, light_status, fan_speed, vacation_start_date, vacation_end_date FROM profiles ORDER BY name",
-    )?;
-    let rows = stmt
-        .query_map([], |r| {
-            Ok(ProfileRow {
-                name: r.get(0)?,
-                mode: r.get(1)?,
-                target_temp: r.get(2)?,
-                greeting: r.get(3)?,
-                description: r.get(4)?,
-                heater_status: r.get::<_, Option<String>>(5)?.unwrap_or_else(|| "Auto".to_string()),
-                ac_status: r.get::<_, Option<String>>(6)?.unwrap_or_else(|| "Auto".to_string()),
-                light_status: r.get::<_, Option<String>>(7)?.unwrap_or_else(|| "OFF".to_string()),
-                fan_speed: r.get::<_, Option<String>>(8)?.unwrap_or_else(|| "Medium".to_string()),
-                vacation_start_date: r.get(9)?,
-                vacation_end_date: r.get(10)?,
-            })
-        })?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles ORDER BY name", PROFILE_COLUMNS))?;
+    let rows = stmt.query_map([], profile_row)?;
     let mut out = Vec::new();
     for r in rows { out.push(r?); }
     Ok(out)
 }
 
+// A typed mode name as stored in profiles.mode, case-insensitive ("cooling" -> "Cooling")
+pub fn profile_mode_name(input: &str) -> Result<&'static str> {
+    const MODES: [&str; 5] = ["Off", "Heating", "Cooling", "FanOnly", "Auto"];
+    let typed = input.trim();
+    MODES
+        .iter()
+        .find(|m| m.eq_ignore_ascii_case(typed) || (**m == "FanOnly" && typed.eq_ignore_ascii_case("fan only")))
+        .copied()
+        .ok_or_else(|| anyhow!("Unknown mode '{}'. Valid modes: {}", typed, MODES.join(", ")))
+}
+
+// Profiles whose mode matches `mode` (validated first), ordered by name
+pub fn list_profiles_by_mode(conn: &Connection, mode: &str) -> Result<Vec<ProfileRow>> {
+    let mode = profile_mode_name(mode)?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles WHERE mode = ?1 ORDER BY name", PROFILE_COLUMNS))?;
+    let rows = stmt.query_map(params![mode], profile_row)?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Side-by-side values of two profiles as (field, a_value, b_value)
+pub fn compare_profiles(conn: &Connection, a: &str, b: &str) -> Result<Vec<(String, String, String)>> {
+    let first = get_profile_row(conn, a)?.ok_or_else(|| anyhow!("Profile '{}' not found", a))?;
+    let second = get_profile_row(conn, b)?.ok_or_else(|| anyhow!("Profile '{}' not found", b))?;
+    let fields = |p: &ProfileRow| {
+        [
+            ("mode", p.mode.clone()),
+            ("temp", format!("{:.1}°C", p.target_temp)),
+            ("heater", p.heater_status.clone()),
+            ("ac", p.ac_status.clone()),
+            ("light", p.light_status.clone()),
+            ("fan", p.fan_speed.clone()),
+            ("greeting", p.greeting.clone().unwrap_or_else(|| "-".to_string())),
+        ]
+    };
+    Ok(fields(&first)
+        .into_iter()
+        .zip(fields(&second))
+        .map(|((field, a_value), (_, b_value))| (field.to_string(), a_value, b_value))
+        .collect())
+}
+
+// Every profile, ordered by name, as a JSON array of ProfileRow objects
+pub fn list_profiles_json(conn: &Connection) -> Result<String> {
+    let rows = list_profile_rows(conn)?;
+    serde_json::to_string_pretty(&rows).context("Failed to serialize profiles")
+}
+
 pub fn reset_profile_to_default(conn: &Connection, name: &str) -> Result<()> {
     if let Some(def) = default_profile_row(name) {
         conn.execute(
@@ -1340,6 +2851,145 @@ pub fn reset_profile_to_default(conn: &Connection, name: &str) -> Result<()> {
     Ok(())
 }
 
+/* Default profiles whose stored values no longer match default_profile_row,
+   in DEFAULT_PROFILES order. Only the fields a reset restores are compared;
+   vacation dates, humidity target and icon are the user's own. */
+pub fn default_profile_drift(conn: &Connection) -> Result<Vec<String>> {
+    let mut drifted = Vec::new();
+    for name in DEFAULT_PROFILES {
+        let (Some(stored), Some(def)) = (get_profile_row(conn, name)?, default_profile_row(name)) else {
+            continue;
+        };
+        let differs = stored.mode != def.mode
+            || (stored.target_temp - def.target_temp).abs() > 0.05
+            || stored.greeting != def.greeting
+            || stored.description != def.description
+            || stored.heater_status != def.heater_status
+            || stored.ac_status != def.ac_status
+            || stored.light_status != def.light_status
+            || stored.fan_speed != def.fan_speed;
+        if differs {
+            drifted.push(name.to_string());
+        }
+    }
+    Ok(drifted)
+}
+
+// Resets all six default profiles in one transaction with a single summary log entry;
+// if any reset fails, none of them are kept.
+pub fn reset_all_defaults(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
+    let tx = conn.transaction()?;
+    for name in DEFAULT_PROFILES {
+        reset_profile_to_default(&tx, name)
+            .with_context(|| format!("Failed to reset profile '{}'", name))?;
+    }
+    tx.execute(
+        "INSERT INTO hvac_activity_log (username, user_role, action_type, description)
+         VALUES (?1, ?2, 'PROFILE_RESET', ?3)",
+        params![username, user_role, format!("🔄 All default profiles restored: {}", DEFAULT_PROFILES.join(", "))],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+// Sane indoor humidity targets (%)
+pub const TARGET_HUMIDITY_MIN: f32 = 30.0;
+pub const TARGET_HUMIDITY_MAX: f32 = 60.0;
+
+// Sets or clears (None) a profile's humidity target
+pub fn set_profile_target_humidity(conn: &Connection, name: &str, target: Option<f32>) -> Result<()> {
+    if let Some(t) = target
+        && !(TARGET_HUMIDITY_MIN..=TARGET_HUMIDITY_MAX).contains(&t)
+    {
+        return Err(anyhow!(
+            "Target humidity must be between {:.0}% and {:.0}%.",
+            TARGET_HUMIDITY_MIN, TARGET_HUMIDITY_MAX
+        ));
+    }
+    let updated = conn.execute(
+        "UPDATE profiles SET target_humidity = ?1, updated_at = datetime('now') WHERE name = ?2",
+        params![target, name],
+    )?;
+    if updated == 0 {
+        return Err(anyhow!("Profile '{}' not found.", name));
+    }
+    // Editing the active profile moves the live setpoint with it
+    let (_, _, _, current) = get_hvac_state(conn)?;
+    if current.is_some_and(|c| c.eq_ignore_ascii_case(name)) && profile_sets_humidity(conn)? {
+        set_hvac_target_humidity(conn, target)?;
+    }
+    Ok(())
+}
+
+// Whether applying a profile also replaces the system humidity setpoint (on unless turned off)
+pub const PROFILE_SETS_HUMIDITY_KEY: &str = "profile_sets_humidity";
+
+pub fn profile_sets_humidity(conn: &Connection) -> Result<bool> {
+    Ok(get_setting(conn, PROFILE_SETS_HUMIDITY_KEY)?.as_deref() != Some("off"))
+}
+
+pub fn set_profile_sets_humidity(conn: &Connection, username: &str, enabled: bool) -> Result<()> {
+    let value = if enabled { "on" } else { "off" };
+    set_setting(conn, PROFILE_SETS_HUMIDITY_KEY, value, username)?;
+    logger::log_event(conn, username, None, "SETTINGS_CHANGED", Some(&format!("Profile sets humidity target: {}", value)))?;
+    Ok(())
+}
+
+// System humidity setpoint (%); None when the last applied profile set none
+pub fn hvac_target_humidity(conn: &Connection) -> Result<Option<f32>> {
+    Ok(conn.query_row("SELECT target_humidity FROM hvac_state WHERE id = 1", [], |r| r.get(0))?)
+}
+
+pub fn set_hvac_target_humidity(conn: &Connection, target: Option<f32>) -> Result<()> {
+    conn.execute(
+        "UPDATE hvac_state SET target_humidity = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+        params![target],
+    )?;
+    Ok(())
+}
+
+// Short label shown next to a profile's name in menus
+pub const MAX_PROFILE_ICON_CHARS: usize = 4;
+pub const DEFAULT_PROFILE_ICON: &str = "🔹";
+
+pub fn validate_profile_icon(icon: &str) -> Result<()> {
+    let len = icon.chars().count();
+    if icon.trim().is_empty() || len > MAX_PROFILE_ICON_CHARS {
+        return Err(anyhow!("Icon must be 1 to {} characters.", MAX_PROFILE_ICON_CHARS));
+    }
+    Ok(())
+}
+
+pub fn set_profile_icon(conn: &Connection, name: &str, icon: Option<&str>) -> Result<()> {
+    if let Some(i) = icon {
+        validate_profile_icon(i)?;
+    }
+    let updated = conn.execute(
+        "UPDATE profiles SET icon = ?1, updated_at = datetime('now') WHERE name = ?2",
+        params![icon, name],
+    )?;
+    if updated == 0 {
+        return Err(anyhow!("Profile '{}' not found.", name));
+    }
+    Ok(())
+}
+
+// The profile's own icon, else the built-in one for default profiles, else a neutral marker
+pub fn profile_icon(profile: &ProfileRow) -> &str {
+    if let Some(icon) = profile.icon.as_deref() {
+        return icon;
+    }
+    match profile.name.as_str() {
+        "Day" => "☀️",
+        "Night" => "🌙",
+        "Sleep" => "😴",
+        "Party" => "🎊",
+        "Vacation" => "🏖️",
+        "Away" => "🚗",
+        _ => DEFAULT_PROFILE_ICON,
+    }
+}
+
 // Set vacation dates for the Vacation profile
 pub fn set_vacation_dates(conn: &Connection, start_date: &str, end_date: &str) -> Result<()> {
     conn.execute(
@@ -1358,34 +3008,127 @@ pub fn clear_vacation_dates(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
-// ======================================================
-//          PROFILE MANAGEMENT (CREATE/DELETE)
-// ======================================================
+// Vacation mode is active while the Vacation profile has both dates set
+pub fn vacation_mode_active(conn: &Connection) -> Result<bool> {
+    Ok(get_profile_row(conn, "Vacation")?
+        .is_some_and(|p| p.vacation_start_date.is_some() && p.vacation_end_date.is_some()))
+}
 
-const DEFAULT_PROFILES: [&str; 6] = ["Day", "Night", "Sleep", "Party", "Vacation", "Away"];
+// How far back a finished vacation still counts as "recent" for the away report
+pub const RECENT_VACATION_DAYS: i64 = 30;
+
+/* Start of the current or most recent vacation (UTC): the booked start date
+   (mm-dd-yyyy, from Eastern midnight) while vacation mode is on, otherwise the
+   last time the Vacation profile was applied within RECENT_VACATION_DAYS. */
+pub fn recent_vacation_start(conn: &Connection) -> Result<Option<DateTime<Utc>>> {
+    let booked = get_profile_row(conn, "Vacation")?
+        .and_then(|p| p.vacation_start_date)
+        .and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%m-%d-%Y").ok())
+        .and_then(|d| d.and_hms_opt(0, 0, 0)?.and_local_timezone(New_York).earliest())
+        .map(|t| t.with_timezone(&Utc));
+    if booked.is_some() {
+        return Ok(booked);
+    }
 
-// Check if a profile name is a default/protected profile
-pub fn is_default_profile(name: &str) -> bool {
-    DEFAULT_PROFILES.iter().any(|&p| p.eq_ignore_ascii_case(name))
+    let applied: Option<String> = conn.query_row(
+        "SELECT MAX(datetime(timestamp)) FROM hvac_activity_log
+          WHERE action_type = 'PROFILE_APPLIED' AND profile_name = 'Vacation'
+            AND datetime(timestamp) >= datetime('now', ?1)",
+        params![format!("-{} days", RECENT_VACATION_DAYS)],
+        |r| r.get(0),
+    )?;
+    Ok(applied
+        .and_then(|t| NaiveDateTime::parse_from_str(&t, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|t| t.and_utc()))
+}
+
+// Most recent PROFILE_APPLIED time (UTC), if any profile was ever applied
+pub fn last_profile_applied_at(conn: &Connection) -> Result<Option<DateTime<Utc>>> {
+    let raw: Option<String> = conn.query_row(
+        "SELECT MAX(datetime(timestamp)) FROM hvac_activity_log WHERE action_type = 'PROFILE_APPLIED'",
+        [],
+        |r| r.get(0),
+    )?;
+    Ok(raw
+        .and_then(|t| NaiveDateTime::parse_from_str(&t, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|naive| DateTime::<Utc>::from_naive_utc_and_offset(naive, Utc)))
+}
+
+// The profile this user applied most recently, if any
+pub fn last_applied_profile(conn: &Connection, username: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT profile_name FROM hvac_activity_log
+             WHERE action_type = 'PROFILE_APPLIED' AND username = ?1 COLLATE NOCASE AND profile_name IS NOT NULL
+             ORDER BY datetime(timestamp) DESC, id DESC LIMIT 1",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?)
+}
+
+// ======================================================
+//          PROFILE MANAGEMENT (CREATE/DELETE)
+// ======================================================
+
+const DEFAULT_PROFILES: [&str; 6] = ["Day", "Night", "Sleep", "Party", "Vacation", "Away"];
+
+// Check if a profile name is a default/protected profile
+pub fn is_default_profile(name: &str) -> bool {
+    DEFAULT_PROFILES.iter().any(|&p| p.eq_ignore_ascii_case(name))
 }
 
 // Validate profile name (3-20 chars, letters/numbers/spaces only, no duplicates)
-pub fn validate_profile_name(conn: &Connection, name: &str) -> Result<Option<String>> {
+// Why a custom profile name was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileNameError {
+    TooShort,
+    TooLong,
+    InvalidChars,
+    Reserved,
+    Duplicate(String),
+}
+
+impl std::fmt::Display for ProfileNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileNameError::TooShort | ProfileNameError::TooLong => {
+                write!(f, "Profile name must be 3-20 characters long")
+            }
+            ProfileNameError::InvalidChars => {
+                write!(f, "Profile name can only contain letters, numbers, and spaces")
+            }
+            ProfileNameError::Reserved => {
+                write!(f, "Cannot use default profile names (Day/Night/Sleep/Party/Vacation/Away)")
+            }
+            ProfileNameError::Duplicate(name) => write!(f, "Profile '{}' already exists", name),
+        }
+    }
+}
+
+impl std::error::Error for ProfileNameError {}
+
+/* Checks a new custom profile name. A refused name comes back as a ProfileNameError
+   inside the anyhow error (use downcast_ref); anything else is a database failure. */
+pub fn check_profile_name(conn: &Connection, name: &str) -> Result<()> {
     let trimmed = name.trim();
     
     // Check length
-    if trimmed.len() < 3 || trimmed.len() > 20 {
-        return Ok(Some("Profile name must be 3-20 characters long".to_string()));
+    if trimmed.len() < 3 {
+        return Err(ProfileNameError::TooShort.into());
+    }
+    if trimmed.len() > 20 {
+        return Err(ProfileNameError::TooLong.into());
     }
     
     // Check allowed characters (letters, numbers, spaces)
     if !trimmed.chars().all(|c| c.is_alphanumeric() || c.is_whitespace()) {
-        return Ok(Some("Profile name can only contain letters, numbers, and spaces".to_string()));
+        return Err(ProfileNameError::InvalidChars.into());
     }
     
     // Check if it's a protected default profile name
     if is_default_profile(trimmed) {
-        return Ok(Some("Cannot use default profile names (Day/Night/Sleep/Party/Vacation/Away)".to_string()));
+        return Err(ProfileNameError::Reserved.into());
     }
     
     // Check for duplicates (case-insensitive)
@@ -1396,10 +3139,44 @@ pub fn validate_profile_name(conn: &Connection, name: &str) -> Result<Option<Str
     )?;
     
     if exists > 0 {
-        return Ok(Some(format!("Profile '{}' already exists", trimmed)));
+        return Err(ProfileNameError::Duplicate(trimmed.to_string()).into());
     }
     
-    Ok(None) // No error
+    Ok(())
+}
+
+// check_profile_name with the refusal as its message text; None when the name is fine
+pub fn validate_profile_name(conn: &Connection, name: &str) -> Result<Option<String>> {
+    match check_profile_name(conn, name) {
+        Ok(()) => Ok(None),
+        Err(e) => match e.downcast_ref::<ProfileNameError>() {
+            Some(reason) => Ok(Some(reason.to_string())),
+            None => Err(e),
+        },
+    }
+}
+
+// Heater and AC can never both be forced on; "Auto" on either side is fine
+pub fn validate_device_interlock(heater_status: &str, ac_status: &str) -> Result<()> {
+    if heater_status.trim().eq_ignore_ascii_case("on") && ac_status.trim().eq_ignore_ascii_case("on") {
+        return Err(anyhow!("Heater and AC cannot both be On; set one of them to Off or Auto."));
+    }
+    Ok(())
+}
+
+// Reject a profile whose target temperature falls outside its mode's range
+pub fn validate_profile_temperature(mode: &str, target_temp: f32) -> Result<()> {
+    let Some(hvac_mode) = crate::hvac::HVACMode::from_name(mode) else {
+        return Err(anyhow!("Invalid mode '{}'", mode));
+    };
+    if !hvac_mode.is_valid_temperature_for_mode(target_temp) {
+        let (min_t, max_t) = hvac_mode.temperature_range();
+        return Err(anyhow!(
+            "{:.1}°C is outside the {} range ({:.0}–{:.0}°C)",
+            target_temp, mode, min_t, max_t
+        ));
+    }
+    Ok(())
 }
 
 // Create a new custom profile
@@ -1416,9 +3193,9 @@ pub fn create_profile(
     fan_speed: &str,
 ) -> Result<()> {
     // Validate the profile name
-    if let Some(error) = validate_profile_name(conn, name)? {
-        return Err(anyhow!(error));
-    }
+    check_profile_name(conn, name)?;
+    validate_profile_temperature(mode, target_temp)?;
+    validate_device_interlock(heater_status, ac_status)?;
     
     // Insert the new profile
     conn.execute(
@@ -1470,6 +3247,8 @@ pub fn update_profile_full(
     light_status: &str,
     fan_speed: &str,
 ) -> Result<()> {
+    validate_profile_temperature(mode, target_temp)?;
+    validate_device_interlock(heater_status, ac_status)?;
     conn.execute(
         "UPDATE profiles SET mode = ?2, target_temp = ?3, greeting = ?4, description = ?5, 
          heater_status = ?6, ac_status = ?7, light_status = ?8, fan_speed = ?9, updated_at = datetime('now') 
@@ -1502,6 +3281,25 @@ pub fn log_profile_applied(
     Ok(())
 }
 
+// Log profile create/edit/delete along with the homeowner context it was made under
+pub fn log_profile_edited(
+    conn: &Connection,
+    username: &str,
+    user_role: &str,
+    profile_name: &str,
+    change: &str,
+    homeowner_context: Option<&str>,
+) -> Result<()> {
+    let context = homeowner_context.unwrap_or("system");
+    let description = format!("✏️ Profile {}: {} (homeowner context: {})", change, profile_name, context);
+    conn.execute(
+        "INSERT INTO hvac_activity_log (username, user_role, action_type, profile_name, new_value, description) 
+         VALUES (?1, ?2, 'PROFILE_EDITED', ?3, ?4, ?5)",
+        params![username, user_role, profile_name, context, description],
+    )?;
+    Ok(())
+}
+
 // Log when a profile is reset to defaults
 pub fn log_profile_reset(
     conn: &Connection,
@@ -1553,36 +3351,56 @@ pub fn log_mode_changed(
 }
 
 // View HVAC activity logs (for admins/homeowners)
-pub fn view_hvac_activity_log(conn: &Connection, _username: &str, user_role: &str) -> Result<()> {
-    // Only admins, homeowners, and technicians can view logs
-    if user_role != "admin" && user_role != "homeowner" && user_role != "technician" {
-        println!("Access denied: Only admins, homeowners, and technicians can view HVAC activity logs.");
-        return Ok(());
-    }
+#[derive(Debug, Clone)]
+pub struct HvacActivityEntry {
+    pub timestamp: String, // UTC as stored
+    pub username: String,
+    pub user_role: String,
+    pub action_type: String,
+    pub profile_name: Option<String>,
+    pub description: Option<String>,
+}
 
+// Latest `limit` activity rows, newest first, optionally only those for one profile
+pub fn hvac_activity_entries(conn: &Connection, profile_name: Option<&str>, limit: i64) -> Result<Vec<HvacActivityEntry>> {
     let mut stmt = conn.prepare(
         "SELECT timestamp, username, user_role, action_type, profile_name, description 
          FROM hvac_activity_log 
+         WHERE ?1 IS NULL OR profile_name = ?1 COLLATE NOCASE
          ORDER BY id DESC 
-         LIMIT 50"
+         LIMIT ?2"
     )?;
 
-    let logs = stmt.query_map([], |r| {
-        Ok((
-            r.get::<_, String>(0)?,  // timestamp
-            r.get::<_, String>(1)?,  // username
-            r.get::<_, String>(2)?,  // user_role
-            r.get::<_, String>(3)?,  // action_type
-            r.get::<_, Option<String>>(4)?,  // profile_name
-            r.get::<_, Option<String>>(5)?,  // description
-        ))
+    let logs = stmt.query_map(params![profile_name, limit], |r| {
+        Ok(HvacActivityEntry {
+            timestamp: r.get(0)?,
+            username: r.get(1)?,
+            user_role: r.get(2)?,
+            action_type: r.get(3)?,
+            profile_name: r.get(4)?,
+            description: r.get(5)?,
+        })
     })?;
+    Ok(logs.collect::<Result<Vec<_>, _>>()?)
+}
 
-    println!("\n=== HVAC ACTIVITY LOG (Last 50 Entries) ===\n");
+pub fn view_hvac_activity_log(conn: &Connection, _username: &str, user_role: &str, profile_name: Option<&str>) -> Result<()> {
+    // Only admins, homeowners, and technicians can view logs
+    if user_role != "admin" && user_role != "homeowner" && user_role != "technician" {
+        println!("Access denied: Only admins, homeowners, and technicians can view HVAC activity logs.");
+        return Ok(());
+    }
+
+    let logs = hvac_activity_entries(conn, profile_name, 50)?;
+
+    match profile_name {
+        Some(name) => println!("\n=== HVAC ACTIVITY LOG: {} (Last 50 Entries) ===\n", name),
+        None => println!("\n=== HVAC ACTIVITY LOG (Last 50 Entries) ===\n"),
+    }
 
     let mut found_any = false;
     for log in logs {
-        let (ts, user, role, action, profile, desc) = log?;
+        let HvacActivityEntry { timestamp: ts, username: user, user_role: role, action_type: action, profile_name: profile, description: desc } = log;
         found_any = true;
         
         // Convert UTC to EST for display
@@ -1602,7 +3420,10 @@ pub fn view_hvac_activity_log(conn: &Connection, _username: &str, user_role: &st
     }
 
     if !found_any {
-        println!("(No HVAC activity logged yet.)");
+        match profile_name {
+            Some(name) => println!("(No HVAC activity logged for '{}'.)", name),
+            None => println!("(No HVAC activity logged yet.)"),
+        }
     }
 
     println!("─────────────────────────────────────────────────────────────────────");
@@ -1649,20 +3470,1487 @@ pub fn get_hvac_state(conn: &Connection) -> Result<(String, f32, String, Option<
     Ok(result)
 }
 
+// One-line key=value status for scripts (no emoji/color). Spaces in values become '_'.
+pub fn status_line(conn: &Connection, indoor_c: Option<f32>) -> Result<String> {
+    let (mode, target, light, profile) = get_hvac_state(conn)?;
+    let fan = current_fan_speed(conn)?;
+    let clean = |v: &str| v.split_whitespace().collect::<Vec<_>>().join("_");
+
+    Ok(format!(
+        "mode={} target={:.1} light={} fan={} profile={} indoor={}",
+        clean(&mode),
+        target,
+        clean(&light),
+        fan.as_deref().map(clean).unwrap_or_else(|| "none".to_string()),
+        profile.as_deref().map(clean).unwrap_or_else(|| "none".to_string()),
+        indoor_c.map(|t| format!("{:.1}", t)).unwrap_or_else(|| "NA".to_string()),
+    ))
+}
+
+// ======================================================
+//                    SYSTEM REPORT
+// ======================================================
+
+// Most recently stored weather observation, if any
+pub fn latest_weather(conn: &Connection) -> Result<Option<WeatherRecord>> {
+    Ok(conn.query_row(
+        "SELECT time, temperature_f, temperature_c, dewpoint_f, dewpoint_c, humidity, wind_speed_mph, wind_direction_deg, condition
+         FROM weather ORDER BY id DESC LIMIT 1",
+        [],
+        |r| Ok(WeatherRecord {
+            time: r.get(0)?,
+            temperature_f: r.get(1)?,
+            temperature_c: r.get(2)?,
+            dewpoint_f: r.get(3)?,
+            dewpoint_c: r.get(4)?,
+            humidity: r.get(5)?,
+            wind_speed_mph: r.get(6)?,
+            wind_direction_deg: r.get(7)?,
+            condition: r.get(8)?,
+        }),
+    ).optional()?)
+}
+
+// (role, total, active) for every role that has at least one account
+pub fn user_counts_by_role(conn: &Connection) -> Result<Vec<(String, i64, i64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT user_status, COUNT(*), COALESCE(SUM(is_active = 1), 0)
+         FROM users GROUP BY user_status ORDER BY user_status",
+    )?;
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+// Logged-in sessions that have not expired yet (the anonymous front-page session is not counted)
+pub fn active_session_count(conn: &Connection) -> Result<i64> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM session_state
+         WHERE username IS NOT NULL AND session_expires > datetime('now')",
+        [],
+        |r| r.get(0),
+    )?)
+}
+
+// (username, locked_until) for lockouts still in force
+pub fn active_lockouts(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT username, locked_until FROM lockouts
+         WHERE datetime(locked_until) > datetime('now') ORDER BY datetime(locked_until)",
+    )?;
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+// Read-only, plain-text overview of the whole system for `--report`
+pub fn system_report(conn: &Connection) -> Result<String> {
+    let mut out = String::new();
+    let mut line = |s: String| { out.push_str(&s); out.push('\n'); };
+
+    line("==== SMART THERMOSTAT SYSTEM REPORT ====".to_string());
+    line(format!("Generated: {}", logger::now_est().format("%Y-%m-%d %H:%M:%S %Z")));
+
+    let (mode, target, light, profile) = get_hvac_state(conn)?;
+    line(String::new());
+    line("[HVAC]".to_string());
+    line(format!("Mode: {}", mode));
+    line(format!("Target: {:.1}°C", target));
+    line(format!("Light: {}", light));
+    line(format!("Active profile: {}", profile.as_deref().unwrap_or("none")));
+    line(format!("Presence: {}", get_presence(conn)?.as_str()));
+
+    line(String::new());
+    line("[INDOOR SENSORS]".to_string());
+    match crate::senser::read_all() {
+        Ok(r) => {
+            line(format!("Temperature: {:.1}°C", r.temperature_c));
+            line(format!("Humidity: {:.1}%", r.humidity_pct));
+            line(format!("CO: {:.1} ppm", r.co_ppm));
+        }
+        Err(e) => line(format!("Unavailable ({})", e)),
+    }
+
+    line(String::new());
+    line("[WEATHER]".to_string());
+    match latest_weather(conn)? {
+        Some(w) => {
+            line(format!("Observed: {}", w.time));
+            line(format!("Condition: {}", w.condition));
+            let temp = w.temperature_c.map(|c| format!("{:.1}°C", c)).unwrap_or_else(|| "N/A".to_string());
+            let humidity = w.humidity.map(|h| format!("{:.0}%", h)).unwrap_or_else(|| "N/A".to_string());
+            line(format!("Temperature: {}", temp));
+            line(format!("Humidity: {}", humidity));
+        }
+        None => line("No weather recorded yet".to_string()),
+    }
+
+    line(String::new());
+    line("[SESSIONS]".to_string());
+    line(format!("Active sessions: {}", active_session_count(conn)?));
+
+    line(String::new());
+    line("[USERS]".to_string());
+    let counts = user_counts_by_role(conn)?;
+    if counts.is_empty() {
+        line("No users registered".to_string());
+    }
+    for (role, total, active) in counts {
+        line(format!("{}: {} ({} active)", role, total, active));
+    }
+
+    line(String::new());
+    line("[LOCKOUTS]".to_string());
+    let lockouts = active_lockouts(conn)?;
+    if lockouts.is_empty() {
+        line("None".to_string());
+    }
+    for (username, until) in lockouts {
+        line(format!("{} locked until {}", username, until));
+    }
+
+    Ok(out)
+}
+
+// Keep an indoor reading so later reports can look back at how the house actually felt
+pub fn record_sensor_reading(conn: &Connection, temperature_c: f32, humidity_pct: Option<f32>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sensor_readings (temperature_c, humidity_pct) VALUES (?1, ?2)",
+        params![temperature_c, humidity_pct],
+    )?;
+    Ok(())
+}
+
 // Save current HVAC state to database
 pub fn save_hvac_state(conn: &Connection, mode: &str, target_temperature: f32, light_status: &str, current_profile: Option<&str>) -> Result<()> {
     conn.execute(
         "UPDATE hvac_state SET mode = ?1, target_temperature = ?2, light_status = ?3, current_profile = ?4, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
         params![mode, target_temperature, light_status, current_profile],
     )?;
+    conn.execute(
+        "INSERT INTO status_snapshot (id, mode, target_temperature, current_profile, updated_at)
+         VALUES (1, ?1, ?2, ?3, CURRENT_TIMESTAMP)
+         ON CONFLICT(id) DO UPDATE SET mode = excluded.mode, target_temperature = excluded.target_temperature,
+             current_profile = excluded.current_profile, updated_at = excluded.updated_at",
+        params![mode, target_temperature, current_profile],
+    )?;
+    Ok(())
+
+}
+
+// One-line summary for the front page, read from the snapshot rather than the full HVAC state
+pub fn front_page_summary(conn: &Connection) -> Result<String> {
+    let row: Option<(String, f32, Option<String>)> = conn
+        .query_row(
+            "SELECT mode, target_temperature, current_profile FROM status_snapshot WHERE id = 1",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()?;
+    Ok(match row {
+        Some((mode, target, profile)) => format!(
+            "Mode: {} | Target: {:.1}°C | Profile: {}",
+            mode,
+            target,
+            profile.as_deref().unwrap_or("None")
+        ),
+        None => "No status recorded yet".to_string(),
+    })
+}
+
+// ======================================================
+//                      GUEST NOTES
+// ======================================================
+
+pub const MAX_GUEST_NOTE_LEN: usize = 200;
+
+#[derive(Debug, Clone)]
+pub struct GuestNote {
+    pub id: i64,
+    pub guest_username: String,
+    pub note: String,
+    pub created_at: String,
+}
+
+// Store a short note from a guest for the homeowner that owns the guest account.
+pub fn submit_guest_note(conn: &Connection, guest_username: &str, note_raw: &str) -> Result<i64> {
+    // sanitize and length bounds
+    let note: String = note_raw.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    let note = note.split_whitespace().collect::<Vec<_>>().join(" ");
+    if note.is_empty() {
+        return Err(anyhow!("Note cannot be empty."));
+    }
+    let len = note.chars().count();
+    if len > MAX_GUEST_NOTE_LEN {
+        return Err(anyhow!("Note must be at most {} characters (current: {}).", MAX_GUEST_NOTE_LEN, len));
+    }
+
+    // Notes only go to the homeowner the guest is linked to
+    let homeowner_id: Option<i64> = conn
+        .query_row(
+            "SELECT g.homeowner_id
+               FROM users g
+               JOIN users h ON h.id = g.homeowner_id AND h.user_status = 'homeowner'
+              WHERE g.username = ?1 COLLATE NOCASE
+                AND g.user_status = 'guest'
+                AND g.is_active = 1",
+            params![guest_username],
+            |r| r.get(0),
+        )
+        .optional()?;
+
+    let Some(homeowner_id) = homeowner_id else {
+        return Err(anyhow!("Guest account is not linked to an active homeowner."));
+    };
+
+    conn.execute(
+        "INSERT INTO guest_notes (guest_username, homeowner_id, note) VALUES (?1, ?2, ?3)",
+        params![guest_username, homeowner_id, note],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Unread notes left by the homeowner's own guests, oldest first
+pub fn list_unread_guest_notes(conn: &Connection, homeowner_username: &str) -> Result<Vec<GuestNote>> {
+    let Some((homeowner_id, role)) = get_user_id_and_role(conn, homeowner_username)? else {
+        return Ok(Vec::new());
+    };
+    if role != "homeowner" {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, guest_username, note, created_at
+           FROM guest_notes
+          WHERE homeowner_id = ?1 AND is_read = 0
+          ORDER BY id ASC",
+    )?;
+    let rows = stmt.query_map(params![homeowner_id], |r| {
+        Ok(GuestNote {
+            id: r.get(0)?,
+            guest_username: r.get(1)?,
+            note: r.get(2)?,
+            created_at: r.get(3)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for r in rows { out.push(r?); }
+    Ok(out)
+}
+
+// Mark all unread notes for the homeowner as read. Returns how many were cleared.
+pub fn mark_guest_notes_read(conn: &Connection, homeowner_username: &str) -> Result<usize> {
+    let affected = conn.execute(
+        "UPDATE guest_notes SET is_read = 1
+          WHERE is_read = 0
+            AND homeowner_id = (SELECT id FROM users WHERE username = ?1 COLLATE NOCASE AND user_status = 'homeowner')",
+        params![homeowner_username],
+    )?;
+    Ok(affected)
+}
+
+// Converts a stored UTC timestamp into Eastern time for display, falling back to the raw value.
+pub fn display_time(utc_str: &str) -> String {
+    to_eastern_time(utc_str).unwrap_or_else(|| utc_str.to_string())
+}
+
+// ======================================================
+//                    MENU SHORTCUTS
+// ======================================================
+
+pub const MAX_MENU_SHORTCUTS: i64 = 3;
+
+// (action key, label) for every action that can be pinned to a role menu
+pub const SHORTCUT_ACTIONS: &[(&str, &str)] = &[
+    ("apply_day", "Apply Day profile"),
+    ("apply_night", "Apply Night profile"),
+    ("apply_sleep", "Apply Sleep profile"),
+    ("view_weather", "View outdoor weather"),
+    ("indoor_sensing", "View indoor sensors"),
+    ("system_status", "View system status"),
+    ("nudge_up", "Nudge temperature +1°C"),
+    ("nudge_down", "Nudge temperature -1°C"),
+];
+
+pub fn shortcut_label(action: &str) -> Option<&'static str> {
+    SHORTCUT_ACTIONS.iter().find(|(a, _)| *a == action).map(|(_, l)| *l)
+}
+
+// Technicians only get read-only shortcuts; HVAC changes stay with homeowners and guests
+pub fn shortcut_allowed_for_role(action: &str, role: &str) -> bool {
+    match role {
+        "homeowner" | "guest" => shortcut_label(action).is_some(),
+        "technician" => matches!(action, "view_weather" | "indoor_sensing" | "system_status"),
+        _ => false,
+    }
+}
+
+// Pin an action to one of the user's shortcut slots (1–3), replacing what was there.
+pub fn set_menu_shortcut(conn: &Connection, username: &str, slot: i64, action: &str) -> Result<()> {
+    if !(1..=MAX_MENU_SHORTCUTS).contains(&slot) {
+        return Err(anyhow!("Shortcut slot must be between 1 and {}.", MAX_MENU_SHORTCUTS));
+    }
+    let Some((_id, role)) = get_user_id_and_role(conn, username)? else {
+        return Err(anyhow!("User '{}' not found.", username));
+    };
+    if !shortcut_allowed_for_role(action, &role) {
+        return Err(anyhow!("Action '{}' is not available as a shortcut for {}s.", action, role));
+    }
+
+    conn.execute(
+        "INSERT INTO menu_shortcuts (username, slot, action, updated_at)
+         VALUES (?1, ?2, ?3, datetime('now'))
+         ON CONFLICT(username, slot) DO UPDATE SET action = excluded.action, updated_at = excluded.updated_at",
+        params![username, slot, action],
+    )?;
     Ok(())
+}
+
+pub fn clear_menu_shortcut(conn: &Connection, username: &str, slot: i64) -> Result<bool> {
+    let n = conn.execute(
+        "DELETE FROM menu_shortcuts WHERE username = ?1 COLLATE NOCASE AND slot = ?2",
+        params![username, slot],
+    )?;
+    Ok(n > 0)
+}
+
+// The user's shortcuts as (slot, action), ordered by slot
+pub fn list_menu_shortcuts(conn: &Connection, username: &str) -> Result<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT slot, action FROM menu_shortcuts WHERE username = ?1 COLLATE NOCASE ORDER BY slot",
+    )?;
+    let rows = stmt.query_map(params![username], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    let mut out = Vec::new();
+    for r in rows { out.push(r?); }
+    Ok(out)
+}
+
+// ======================================================
+//                   USER PREFERENCES
+// ======================================================
+
+// How HVAC status and profile banners are printed for a user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayMode {
+    #[default]
+    Verbose,
+    Compact,
+}
+
+impl DisplayMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DisplayMode::Verbose => "verbose",
+            DisplayMode::Compact => "compact",
+        }
+    }
+}
+
+// Users without a stored preference get the verbose layout
+pub fn get_display_mode(conn: &Connection, username: &str) -> Result<DisplayMode> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT display_mode FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(match stored.as_deref() {
+        Some("compact") => DisplayMode::Compact,
+        _ => DisplayMode::Verbose,
+    })
+}
 
+pub fn set_display_mode(conn: &Connection, username: &str, mode: DisplayMode) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, display_mode, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET display_mode = excluded.display_mode, updated_at = excluded.updated_at",
+        params![username, mode.as_str()],
+    )?;
+    Ok(())
 }
 
+// Users without a stored preference see Celsius (and km/h for wind)
+pub fn get_temp_unit(conn: &Connection, username: &str) -> Result<TempUnit> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT temp_unit FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(match stored.as_deref() {
+        Some("fahrenheit") => TempUnit::Fahrenheit,
+        _ => TempUnit::Celsius,
+    })
+}
 
+pub fn set_temp_unit(conn: &Connection, username: &str, unit: TempUnit) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, temp_unit, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET temp_unit = excluded.temp_unit, updated_at = excluded.updated_at",
+        params![username, unit.as_str()],
+    )?;
+    Ok(())
+}
 
+// Decimal places for temperatures (0 or 1); one decimal unless the user chose whole degrees
+pub fn get_temp_precision(conn: &Connection, username: &str) -> Result<u8> {
+    let stored: Option<i64> = conn
+        .query_row(
+            "SELECT temp_precision FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(match stored {
+        Some(0) => 0,
+        _ => crate::units::DEFAULT_TEMP_PRECISION,
+    })
+}
 
+pub fn set_temp_precision(conn: &Connection, username: &str, precision: u8) -> Result<()> {
+    if precision > 1 {
+        return Err(anyhow!("Temperature precision must be 0 or 1 decimals."));
+    }
+    conn.execute(
+        "INSERT INTO user_preferences (username, temp_precision, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET temp_precision = excluded.temp_precision, updated_at = excluded.updated_at",
+        params![username, precision],
+    )?;
+    Ok(())
+}
 
+// When on, a profile's greeting is shown only the first time it is applied in a row
+pub fn get_greet_once(conn: &Connection, username: &str) -> Result<bool> {
+    let stored: Option<i64> = conn
+        .query_row(
+            "SELECT greet_once FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(stored == Some(1))
+}
 
+pub fn set_greet_once(conn: &Connection, username: &str, enabled: bool) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, greet_once, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET greet_once = excluded.greet_once, updated_at = excluded.updated_at",
+        params![username, enabled as i64],
+    )?;
+    Ok(())
+}
 
+// ======================================================
+//                    SYSTEM SETTINGS
+// ======================================================
 
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row("SELECT value FROM system_settings WHERE key = ?1", params![key], |r| r.get(0))
+        .optional()?)
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str, updated_by: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO system_settings (key, value, updated_by, updated_at)
+         VALUES (?1, ?2, ?3, datetime('now'))
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+        params![key, value, updated_by],
+    )?;
+    Ok(())
+}
+
+// Whether anyone is home; Away lets Auto mode drift further before acting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Presence {
+    #[default]
+    Home,
+    Away,
+}
+
+impl Presence {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Presence::Home => "home",
+            Presence::Away => "away",
+        }
+    }
+}
+
+pub fn get_presence(conn: &Connection) -> Result<Presence> {
+    Ok(match get_setting(conn, "presence")?.as_deref() {
+        Some("away") => Presence::Away,
+        _ => Presence::Home,
+    })
+}
+
+pub fn set_presence(conn: &Connection, username: &str, presence: Presence) -> Result<()> {
+    set_setting(conn, "presence", presence.as_str(), username)?;
+    logger::log_event(conn, username, None, "HVAC", Some(&format!("Presence set to {}", presence.as_str())))?;
+    Ok(())
+}
+
+// Auto's comfort band at home, in °C either side of target (default hvac::HOME_DEADBAND_C)
+pub const COMFORT_BAND_KEY: &str = "comfort_band_c";
+pub const COMFORT_BAND_MIN: f32 = 0.1;
+pub const COMFORT_BAND_MAX: f32 = 3.0;
+
+pub fn comfort_band(conn: &Connection) -> Result<f32> {
+    Ok(get_setting(conn, COMFORT_BAND_KEY)?
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(crate::hvac::HOME_DEADBAND_C))
+}
+
+pub fn set_comfort_band(conn: &Connection, band: f32, username: &str) -> Result<()> {
+    if !(COMFORT_BAND_MIN..=COMFORT_BAND_MAX).contains(&band) {
+        return Err(anyhow!(
+            "Comfort band must be between {:.1} and {:.1}°C",
+            COMFORT_BAND_MIN, COMFORT_BAND_MAX
+        ));
+    }
+    set_setting(conn, COMFORT_BAND_KEY, &format!("{:.1}", band), username)?;
+    logger::log_event(conn, username, None, "HVAC", Some(&format!("Comfort band set to ±{:.1}°C", band)))?;
+    Ok(())
+}
+
+// Electricity price per kWh for cost estimates, kept as a plain number in user_preferences
+pub const ENERGY_RATE_MAX: f64 = 100.0;
+
+pub fn energy_rate(conn: &Connection, username: &str) -> Result<Option<f64>> {
+    let stored: Option<Option<f64>> = conn
+        .query_row(
+            "SELECT energy_rate FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(stored.flatten())
+}
+
+// None removes the rate, which hides the cost lines
+pub fn set_energy_rate(conn: &Connection, username: &str, rate: Option<f64>) -> Result<()> {
+    if let Some(r) = rate
+        && !(r.is_finite() && r > 0.0 && r <= ENERGY_RATE_MAX)
+    {
+        return Err(anyhow!("Rate must be above 0 and at most {}.", ENERGY_RATE_MAX));
+    }
+    conn.execute(
+        "INSERT INTO user_preferences (username, energy_rate, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET energy_rate = excluded.energy_rate, updated_at = excluded.updated_at",
+        params![username, rate],
+    )?;
+    let desc = match rate {
+        Some(r) => format!("Energy rate set to {} per kWh", r),
+        None => "Energy rate cleared".to_string(),
+    };
+    logger::log_event(conn, username, None, "SETTINGS_CHANGED", Some(&desc))?;
+    Ok(())
+}
+
+// Currency the user's cost estimates are shown in (USD by default)
+pub fn energy_currency(conn: &Connection, username: &str) -> Result<crate::energy::Currency> {
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT energy_currency FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(stored.and_then(|v| crate::energy::Currency::from_code(&v)).unwrap_or_default())
+}
+
+pub fn set_energy_currency(conn: &Connection, username: &str, currency: crate::energy::Currency) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, energy_currency, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET energy_currency = excluded.energy_currency, updated_at = excluded.updated_at",
+        params![username, currency.code()],
+    )?;
+    logger::log_event(conn, username, None, "SETTINGS_CHANGED", Some(&format!("Energy currency set to {}", currency.code())))?;
+    Ok(())
+}
+
+// Outdoor °C below which switching to Off needs an explicit override
+pub const FREEZE_THRESHOLD_KEY: &str = "freeze_threshold_c";
+pub const FREEZE_THRESHOLD_MIN: f32 = -20.0;
+pub const FREEZE_THRESHOLD_MAX: f32 = 10.0;
+
+pub fn freeze_threshold(conn: &Connection) -> Result<f32> {
+    Ok(get_setting(conn, FREEZE_THRESHOLD_KEY)?
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(crate::hvac::DEFAULT_FREEZE_THRESHOLD_C))
+}
+
+pub fn set_freeze_threshold(conn: &Connection, threshold_c: f32, admin_username: &str) -> Result<()> {
+    if !(FREEZE_THRESHOLD_MIN..=FREEZE_THRESHOLD_MAX).contains(&threshold_c) {
+        return Err(anyhow!(
+            "Freeze threshold must be between {:.0} and {:.0}°C",
+            FREEZE_THRESHOLD_MIN, FREEZE_THRESHOLD_MAX
+        ));
+    }
+    set_setting(conn, FREEZE_THRESHOLD_KEY, &format!("{:.1}", threshold_c), admin_username)?;
+    logger::log_event(conn, admin_username, None, "SETTINGS_CHANGED", Some(&format!("Freeze threshold set to {:.1}°C", threshold_c)))?;
+    Ok(())
+}
+
+// Per-homeowner cap on the targets their guests may set, in °C, kept in user_preferences
+pub fn guest_temp_bounds(conn: &Connection, homeowner: &str) -> Result<Option<(f32, f32)>> {
+    let stored: Option<(Option<f32>, Option<f32>)> = conn
+        .query_row(
+            "SELECT guest_temp_min, guest_temp_max FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![homeowner],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?;
+    Ok(stored.and_then(|(lo, hi)| lo.zip(hi)))
+}
+
+fn store_guest_temp_bounds(conn: &Connection, homeowner: &str, bounds: Option<(f32, f32)>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, guest_temp_min, guest_temp_max, updated_at)
+         VALUES (?1, ?2, ?3, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET guest_temp_min = excluded.guest_temp_min,
+             guest_temp_max = excluded.guest_temp_max, updated_at = excluded.updated_at",
+        params![homeowner, bounds.map(|b| b.0), bounds.map(|b| b.1)],
+    )?;
+    Ok(())
+}
+
+// None removes the limits; the homeowner's own changes are never restricted
+pub fn set_guest_temp_bounds(conn: &Connection, homeowner: &str, bounds: Option<(f32, f32)>) -> Result<()> {
+    use crate::hvac::{MAX_TEMPERATURE, MIN_TEMPERATURE};
+    let Some((lo, hi)) = bounds else {
+        store_guest_temp_bounds(conn, homeowner, None)?;
+        logger::log_event(conn, homeowner, None, "SETTINGS_CHANGED", Some("Guest temperature limits removed"))?;
+        return Ok(());
+    };
+    if !(MIN_TEMPERATURE..=MAX_TEMPERATURE).contains(&lo) || !(MIN_TEMPERATURE..=MAX_TEMPERATURE).contains(&hi) {
+        return Err(anyhow!(
+            "Guest limits must be within {:.0}–{:.0}°C", MIN_TEMPERATURE, MAX_TEMPERATURE
+        ));
+    }
+    if lo >= hi {
+        return Err(anyhow!("The lower guest limit must be below the upper one"));
+    }
+    store_guest_temp_bounds(conn, homeowner, Some((lo, hi)))?;
+    logger::log_event(conn, homeowner, None, "SETTINGS_CHANGED", Some(&format!(
+        "Guest temperature limits set to {:.1}–{:.1}°C", lo, hi
+    )))?;
+    Ok(())
+}
+
+// The homeowner a guest account belongs to; None for any other account
+pub fn guest_homeowner(conn: &Connection, username: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT h.username FROM users g JOIN users h ON h.id = g.homeowner_id
+             WHERE g.username = ?1 COLLATE NOCASE AND g.user_status = 'guest'",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?)
+}
+
+// The limits that apply to `username`: their homeowner's, when they are a guest
+pub fn guest_temp_bounds_for(conn: &Connection, username: &str) -> Result<Option<(f32, f32)>> {
+    match guest_homeowner(conn, username)? {
+        Some(h) => guest_temp_bounds(conn, &h),
+        None => Ok(None),
+    }
+}
+
+// Per-homeowner switch that stops their guests changing the HVAC at all
+pub fn guest_hvac_locked(conn: &Connection, homeowner: &str) -> Result<bool> {
+    let stored: Option<i64> = conn
+        .query_row(
+            "SELECT guest_hvac_locked FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![homeowner],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(stored == Some(1))
+}
+
+pub fn set_guest_hvac_locked(conn: &Connection, homeowner: &str, locked: bool) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, guest_hvac_locked, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET guest_hvac_locked = excluded.guest_hvac_locked, updated_at = excluded.updated_at",
+        params![homeowner, locked as i64],
+    )?;
+    let desc = if locked { "Guest HVAC changes locked" } else { "Guest HVAC changes unlocked" };
+    logger::log_event(conn, homeowner, None, "SETTINGS_CHANGED", Some(desc))?;
+    Ok(())
+}
+
+// Start of every blocked-attempt description, so the homeowner's summary can find them
+pub const BLOCKED_GUEST_HVAC: &str = "Blocked guest HVAC change";
+
+/* True when `username` is a guest whose homeowner has locked the HVAC. The
+   attempt is then logged as an HVAC event against the homeowner, naming the
+   guest and the mode/target they tried to set. */
+pub fn guest_hvac_change_blocked(conn: &Connection, username: &str, mode: &str, target_c: Option<f32>) -> Result<bool> {
+    let Some(homeowner) = guest_homeowner(conn, username)? else { return Ok(false) };
+    if !guest_hvac_locked(conn, &homeowner)? {
+        return Ok(false);
+    }
+    let attempted = match target_c {
+        Some(t) => format!("{} at {:.1}°C", mode, t),
+        None => mode.to_string(),
+    };
+    logger::log_event(conn, username, Some(&homeowner), "HVAC", Some(&format!(
+        "{}: guest '{}' tried {} (HVAC locked by '{}')", BLOCKED_GUEST_HVAC, username, attempted, homeowner
+    )))?;
+    Ok(true)
+}
+
+// HVAC change rate limit: at most HVAC_CHANGE_LIMIT changes per user in any rolling window
+pub const HVAC_CHANGE_LIMIT: i64 = 10;
+pub const HVAC_CHANGE_WINDOW_SECS: i64 = 60;
+
+pub fn next_allowed_change_in(conn: &Connection, username: &str) -> Result<i64> {
+    next_allowed_change_in_with_clock(conn, username, &RealClock)
+}
+
+/* Seconds until `username` may make another HVAC change; 0 means now. Profile
+   applies and mode/temperature changes in the window count, and once it is
+   full the wait runs until the oldest of the last HVAC_CHANGE_LIMIT ages out. */
+pub fn next_allowed_change_in_with_clock(conn: &Connection, username: &str, clock: &dyn Clock) -> Result<i64> {
+    let now = clock.now().timestamp();
+    let mut stmt = conn.prepare(
+        "SELECT CAST(strftime('%s', timestamp) AS INTEGER) FROM hvac_activity_log
+          WHERE username = ?1 COLLATE NOCASE
+            AND action_type IN ('PROFILE_APPLIED', 'MODE_CHANGED', 'TEMPERATURE_CHANGED')
+            AND CAST(strftime('%s', timestamp) AS INTEGER) > ?2
+          ORDER BY datetime(timestamp) DESC, id DESC
+          LIMIT ?3",
+    )?;
+    let recent: Vec<i64> = stmt
+        .query_map(params![username, now - HVAC_CHANGE_WINDOW_SECS, HVAC_CHANGE_LIMIT], |r| r.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    if (recent.len() as i64) < HVAC_CHANGE_LIMIT {
+        return Ok(0);
+    }
+    let oldest = recent.last().copied().unwrap_or(now);
+    Ok((oldest + HVAC_CHANGE_WINDOW_SECS - now).max(0))
+}
+
+/* Guest login notices: a homeowner who opts in gets a GUEST_LOGIN event each
+   time one of their guests signs in. The id of the newest notice they have
+   seen is kept in their preferences, so everything after it counts as unread. */
+pub fn guest_login_notices_enabled(conn: &Connection, homeowner: &str) -> Result<bool> {
+    let stored: Option<i64> = conn
+        .query_row(
+            "SELECT guest_login_notices FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![homeowner],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(stored == Some(1))
+}
+
+pub fn set_guest_login_notices(conn: &Connection, homeowner: &str, enabled: bool) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, guest_login_notices, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET guest_login_notices = excluded.guest_login_notices, updated_at = excluded.updated_at",
+        params![homeowner, enabled as i64],
+    )?;
+    let desc = if enabled { "Guest login notices turned on" } else { "Guest login notices turned off" };
+    logger::log_event(conn, homeowner, None, "SETTINGS_CHANGED", Some(desc))?;
+    Ok(())
+}
+
+// Called after a successful guest login; a no-op unless the guest's homeowner opted in
+pub fn record_guest_login_notice(conn: &Connection, guest_username: &str) -> Result<()> {
+    let Some(homeowner) = guest_homeowner(conn, guest_username)? else { return Ok(()) };
+    if !guest_login_notices_enabled(conn, &homeowner)? {
+        return Ok(());
+    }
+    logger::log_event(conn, guest_username, Some(&homeowner), "GUEST_LOGIN", Some(&format!(
+        "Guest '{}' logged in", guest_username
+    )))
+}
+
+// Notices the homeowner has not seen yet, oldest first, as (id, Eastern time, guest)
+pub fn unread_guest_login_notices(conn: &Connection, homeowner: &str) -> Result<Vec<(i64, String, String)>> {
+    let seen: i64 = conn
+        .query_row(
+            "SELECT guest_login_seen_id FROM user_preferences WHERE username = ?1 COLLATE NOCASE",
+            params![homeowner],
+            |r| r.get(0),
+        )
+        .optional()?
+        .unwrap_or(0);
+    let mut stmt = conn.prepare(
+        "SELECT id, datetime(timestamp), actor_username FROM security_log
+          WHERE event_type = 'GUEST_LOGIN' AND target_username = ?1 COLLATE NOCASE AND id > ?2
+          ORDER BY id ASC",
+    )?;
+    let rows = stmt.query_map(params![homeowner, seen], |r| {
+        let ts: Option<String> = r.get(1)?;
+        Ok((r.get(0)?, ts.map(|t| display_time(&t)).unwrap_or_default(), r.get(2)?))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Everything up to and including `last_id` counts as seen
+pub fn mark_guest_login_notices_read(conn: &Connection, homeowner: &str, last_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO user_preferences (username, guest_login_seen_id, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(username) DO UPDATE SET guest_login_seen_id = excluded.guest_login_seen_id, updated_at = excluded.updated_at",
+        params![homeowner, last_id],
+    )?;
+    Ok(())
+}
+
+// Latest blocked guest attempts against a homeowner, newest first, as (Eastern time, guest, description)
+pub fn blocked_guest_hvac_attempts(conn: &Connection, homeowner: &str, limit: usize) -> Result<Vec<(String, String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT datetime(timestamp), actor_username, description FROM security_log
+          WHERE event_type = 'HVAC' AND target_username = ?1 COLLATE NOCASE
+            AND description LIKE ?2 || '%'
+          ORDER BY datetime(timestamp) DESC, id DESC
+          LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![homeowner, BLOCKED_GUEST_HVAC, limit as i64], |r| {
+        let ts: Option<String> = r.get(0)?;
+        Ok((ts.map(|t| display_time(&t)).unwrap_or_default(), r.get(1)?, r.get(2)?))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Weather location; unset keys fall back to WeatherLocation::default()
+pub const WEATHER_LATITUDE_KEY: &str = "weather_latitude";
+pub const WEATHER_LONGITUDE_KEY: &str = "weather_longitude";
+pub const WEATHER_STATION_KEY: &str = "weather_station";
+
+pub fn weather_location(conn: &Connection) -> Result<WeatherLocation> {
+    let stored = |key: &str| -> Result<Option<f64>> {
+        Ok(get_setting(conn, key)?.and_then(|v| v.parse::<f64>().ok()))
+    };
+    match (stored(WEATHER_LATITUDE_KEY)?, stored(WEATHER_LONGITUDE_KEY)?) {
+        (Some(latitude), Some(longitude)) => Ok(WeatherLocation {
+            latitude,
+            longitude,
+            station: get_setting(conn, WEATHER_STATION_KEY)?.filter(|s| !s.is_empty()),
+        }),
+        _ => Ok(WeatherLocation::default()),
+    }
+}
+
+pub fn set_weather_location(conn: &Connection, location: &WeatherLocation, username: &str) -> Result<()> {
+    crate::weather::validate_coordinates(location.latitude, location.longitude)?;
+    if let Some(station) = &location.station {
+        crate::weather::validate_station(station)?;
+    }
+    set_setting(conn, WEATHER_LATITUDE_KEY, &format!("{:.4}", location.latitude), username)?;
+    set_setting(conn, WEATHER_LONGITUDE_KEY, &format!("{:.4}", location.longitude), username)?;
+    set_setting(conn, WEATHER_STATION_KEY, location.station.as_deref().unwrap_or(""), username)?;
+    logger::log_event(conn, username, None, "SETTINGS_CHANGED", Some(&format!(
+        "Weather location set to {:.4}, {:.4} (station {})",
+        location.latitude, location.longitude, location.station.as_deref().unwrap_or("nearest")
+    )))?;
+    Ok(())
+}
+
+// Strict installs refuse guest logins while the owning homeowner is disabled (off by default)
+pub const STRICT_GUEST_OWNER_KEY: &str = "strict_guest_owner_check";
+
+pub fn strict_guest_owner_check(conn: &Connection) -> Result<bool> {
+    Ok(get_setting(conn, STRICT_GUEST_OWNER_KEY)?.as_deref() == Some("on"))
+}
+
+pub fn set_strict_guest_owner_check(conn: &Connection, admin_username: &str, enabled: bool) -> Result<()> {
+    set_setting(conn, STRICT_GUEST_OWNER_KEY, if enabled { "on" } else { "off" }, admin_username)
+}
+
+// Apply the Away profile once the last signed-in user logs out (off by default)
+pub const AWAY_ON_LAST_LOGOUT_KEY: &str = "away_on_last_logout";
+// Set while the system applied Away for an empty house, so the next login hands control back
+pub const IDLE_AWAY_KEY: &str = "idle_away_applied";
+
+pub fn away_on_last_logout(conn: &Connection) -> Result<bool> {
+    Ok(get_setting(conn, AWAY_ON_LAST_LOGOUT_KEY)?.as_deref() == Some("on"))
+}
+
+pub fn set_away_on_last_logout(conn: &Connection, admin_username: &str, enabled: bool) -> Result<()> {
+    set_setting(conn, AWAY_ON_LAST_LOGOUT_KEY, if enabled { "on" } else { "off" }, admin_username)?;
+    let desc = format!("Away on last logout turned {}", if enabled { "on" } else { "off" });
+    logger::log_event(conn, admin_username, None, "SETTINGS_CHANGED", Some(&desc))?;
+    Ok(())
+}
+
+pub fn idle_away_applied(conn: &Connection) -> Result<bool> {
+    Ok(get_setting(conn, IDLE_AWAY_KEY)?.as_deref() == Some("on"))
+}
+
+pub fn set_idle_away_applied(conn: &Connection, applied: bool) -> Result<()> {
+    set_setting(conn, IDLE_AWAY_KEY, if applied { "on" } else { "off" }, "system")
+}
+
+// The guest's homeowner, if that account is currently disabled
+pub fn inactive_owner_of_guest(conn: &Connection, guest_username: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT h.username FROM users g JOIN users h ON h.id = g.homeowner_id
+             WHERE g.username = ?1 COLLATE NOCASE AND g.user_status = 'guest' AND h.is_active = 0",
+            params![guest_username],
+            |r| r.get(0),
+        )
+        .optional()?)
+}
+
+/* Factory reset for configuration only: system settings (except live presence), per-user
+   display preferences, menu shortcuts and mode default temperatures all go back to their
+   defaults in one transaction. Users, profiles, schedules and logs are left alone. */
+pub fn reset_settings_to_defaults(conn: &mut Connection, admin_username: &str) -> Result<()> {
+    let tx = conn.transaction().context("Failed to start transaction")?;
+    let settings = tx.execute("DELETE FROM system_settings WHERE key <> 'presence'", [])?;
+    // Read markers are state, not settings; they outlive the reset
+    let seen: Vec<(String, i64)> = {
+        let mut stmt = tx.prepare("SELECT username, guest_login_seen_id FROM user_preferences WHERE guest_login_seen_id > 0")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let preferences = tx.execute("DELETE FROM user_preferences", [])?;
+    for (user, seen_id) in &seen {
+        tx.execute(
+            "INSERT INTO user_preferences (username, guest_login_seen_id) VALUES (?1, ?2)",
+            params![user, seen_id],
+        )?;
+    }
+    let shortcuts = tx.execute("DELETE FROM menu_shortcuts", [])?;
+    let mode_defaults = tx.execute("DELETE FROM mode_defaults", [])?;
+    let menu_rows = tx.execute("DELETE FROM menu_config", [])?;
+    let thresholds = tx.execute("DELETE FROM sensor_thresholds", [])?;
+    let desc = format!(
+        "All settings restored to defaults ({} system, {} preference, {} shortcut, {} mode default, {} menu layout, {} threshold rows cleared)",
+        settings, preferences, shortcuts, mode_defaults, menu_rows, thresholds
+    );
+    logger::log_event(&tx, admin_username, None, "SETTINGS_CHANGED", Some(&desc))?;
+    tx.commit()?;
+    Ok(())
+}
+
+// ======================================================
+//                    RECOVERY CODES
+// ======================================================
+
+pub const RECOVERY_CODE_COUNT: usize = 5;
+// Failed code entries allowed per user inside the attempt window
+pub const MAX_RECOVERY_ATTEMPTS: i64 = 3;
+const RECOVERY_WINDOW_MINUTES: i64 = 15;
+
+// Uppercase, no separators, so "abcde-fghij" and "ABCDEFGHIJ" hash the same
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    blake3::hash(normalize_recovery_code(code).as_bytes()).to_hex().to_string()
+}
+
+/* Replaces any existing codes for the user with a fresh set.
+   Only the hashes are stored; the plain codes are returned once for display. */
+pub fn generate_recovery_codes(conn: &Connection, username: &str) -> Result<Vec<String>> {
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut rng = OsRng;
+
+    let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+    for _ in 0..RECOVERY_CODE_COUNT {
+        let raw = (0..10)
+            .map(|_| -> Result<char> {
+                let idx = (rng.try_next_u32().context("OS RNG failed")? as usize) % ALPHABET.len();
+                Ok(ALPHABET[idx] as char)
+            })
+            .collect::<Result<String>>()?;
+        codes.push(format!("{}-{}", &raw[..5], &raw[5..]));
+    }
+
+    conn.execute("DELETE FROM recovery_codes WHERE username = ?1 COLLATE NOCASE", params![username])?;
+    for code in &codes {
+        conn.execute(
+            "INSERT INTO recovery_codes (username, code_hash) VALUES (?1, ?2)",
+            params![username, hash_recovery_code(code)],
+        )?;
+    }
+    Ok(codes)
+}
+
+pub fn remaining_recovery_codes(conn: &Connection, username: &str) -> Result<i64> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM recovery_codes WHERE username = ?1 COLLATE NOCASE AND used_at IS NULL",
+        params![username],
+        |r| r.get(0),
+    )?)
+}
+
+/* Clears the user's lockout when `code` matches one of their unused codes.
+   Returns Ok(false) for a wrong or already-used code, Err once too many attempts failed. */
+pub fn redeem_recovery_code(conn: &Connection, username: &str, code: &str) -> Result<bool> {
+    let recent_failures: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM recovery_attempts
+          WHERE username = ?1 COLLATE NOCASE AND success = 0
+            AND attempted_at > datetime('now', printf('-%d minutes', ?2))",
+        params![username, RECOVERY_WINDOW_MINUTES],
+        |r| r.get(0),
+    )?;
+    if recent_failures >= MAX_RECOVERY_ATTEMPTS {
+        return Err(anyhow!(
+            "Too many recovery attempts. Try again in {} minutes or contact an admin.",
+            RECOVERY_WINDOW_MINUTES
+        ));
+    }
+
+    let used = conn.execute(
+        "UPDATE recovery_codes SET used_at = datetime('now')
+          WHERE id = (SELECT id FROM recovery_codes
+                       WHERE username = ?1 COLLATE NOCASE AND code_hash = ?2 AND used_at IS NULL
+                       LIMIT 1)",
+        params![username, hash_recovery_code(code)],
+    )?;
+
+    conn.execute(
+        "INSERT INTO recovery_attempts (username, success) VALUES (?1, ?2)",
+        params![username, (used == 1) as i64],
+    )?;
+    if used == 0 {
+        return Ok(false);
+    }
+
+    conn.execute("DELETE FROM lockouts WHERE username = ?1 COLLATE NOCASE", params![username])?;
+    conn.execute(
+        "UPDATE session_state SET failed_attempts = 0, is_locked = 0, locked_until = NULL
+          WHERE username = ?1 COLLATE NOCASE",
+        params![username],
+    )?;
+    logger::log_event(conn, username, Some(username), "LOCKOUT_CLEARED", Some("Lockout cleared with recovery code"))?;
+    Ok(true)
+}
+
+// ======================================================
+//               PER-MODE DEFAULT TEMPERATURES
+// ======================================================
+
+/* Temperature offered when switching into `mode`: the last one set in that mode,
+   or the middle of the mode's range. Always clamped into the current valid range. */
+pub fn mode_default_temperature(conn: &Connection, mode: crate::hvac::HVACMode) -> Result<f32> {
+    let stored: Option<f64> = conn
+        .query_row(
+            "SELECT target_temp FROM mode_defaults WHERE mode = ?1",
+            params![format!("{:?}", mode)],
+            |r| r.get(0),
+        )
+        .optional()?;
+    let (min, max) = mode.temperature_range();
+    let temp = stored.map(|t| t as f32).unwrap_or((min + max) / 2.0);
+    Ok(mode.clamp_temperature(temp))
+}
+
+// Remember the temperature a user picked for a mode that takes one
+pub fn set_mode_default_temperature(conn: &Connection, mode: crate::hvac::HVACMode, temp: f32) -> Result<()> {
+    use crate::hvac::HVACMode;
+    if !matches!(mode, HVACMode::Heating | HVACMode::Cooling | HVACMode::Auto) {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO mode_defaults (mode, target_temp, updated_at) VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(mode) DO UPDATE SET target_temp = excluded.target_temp, updated_at = excluded.updated_at",
+        params![format!("{:?}", mode), temp as f64],
+    )?;
+    Ok(())
+}
+
+// ======================================================
+//                 BULK GUEST DISABLE
+// ======================================================
+
+fn homeowner_id_for(conn: &Connection, homeowner_username: &str) -> Result<i64> {
+    match get_user_id_and_role(conn, homeowner_username)? {
+        Some((id, role)) if role == "homeowner" => Ok(id),
+        _ => Err(anyhow!("'{}' is not a homeowner.", homeowner_username)),
+    }
+}
+
+/* Disables every active guest of the homeowner in one transaction.
+   Guests are flagged so enable_all_guests only restores the ones disabled here. */
+pub fn disable_all_guests(conn: &mut Connection, homeowner_username: &str) -> Result<usize> {
+    let homeowner_id = homeowner_id_for(conn, homeowner_username)?;
+    let tx = conn.transaction().context("Failed to start transaction")?;
+
+    let guests: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "SELECT username FROM users
+              WHERE user_status = 'guest' AND homeowner_id = ?1 AND COALESCE(is_active,1) = 1",
+        )?;
+        stmt.query_map(params![homeowner_id], |r| r.get(0))?
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    for guest in &guests {
+        tx.execute(
+            "UPDATE users SET is_active = 0, bulk_disabled = 1, updated_at = datetime('now')
+              WHERE username = ?1 COLLATE NOCASE",
+            params![guest],
+        )?;
+        let desc = format!("Guest {} disabled by {} (all guests)", guest, homeowner_username);
+        logger::log_event(&tx, homeowner_username, Some(guest), "ACCOUNT_DISABLED", Some(&desc))?;
+    }
+
+    tx.commit()?;
+    Ok(guests.len())
+}
+
+// Re-enables the guests switched off by disable_all_guests; individually disabled guests stay off
+pub fn enable_all_guests(conn: &mut Connection, homeowner_username: &str) -> Result<usize> {
+    let homeowner_id = homeowner_id_for(conn, homeowner_username)?;
+    let tx = conn.transaction().context("Failed to start transaction")?;
+
+    let guests: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "SELECT username FROM users
+              WHERE user_status = 'guest' AND homeowner_id = ?1 AND bulk_disabled = 1",
+        )?;
+        stmt.query_map(params![homeowner_id], |r| r.get(0))?
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    for guest in &guests {
+        tx.execute(
+            "UPDATE users SET is_active = 1, bulk_disabled = 0, updated_at = datetime('now')
+              WHERE username = ?1 COLLATE NOCASE",
+            params![guest],
+        )?;
+        let desc = format!("Guest {} re-enabled by {} (all guests)", guest, homeowner_username);
+        logger::log_event(&tx, homeowner_username, Some(guest), "ACCOUNT_ENABLED", Some(&desc))?;
+    }
+
+    tx.commit()?;
+    Ok(guests.len())
+}
+
+// ======================================================
+//                 TEMPORARY OVERRIDES
+// ======================================================
+
+// Longest hold a user can set in one go (12 hours)
+pub const MAX_OVERRIDE_MINUTES: i64 = 720;
+
+#[derive(Debug, Clone)]
+pub struct TemporaryOverride {
+    pub mode: String,
+    pub target_temp: f32,
+    pub set_by: String,
+    pub expires_at: String, // UTC, "YYYY-MM-DD HH:MM:SS"
+}
+
+fn map_override(r: &rusqlite::Row) -> rusqlite::Result<TemporaryOverride> {
+    Ok(TemporaryOverride {
+        mode: r.get(0)?,
+        target_temp: r.get::<_, f64>(1)? as f32,
+        set_by: r.get(2)?,
+        expires_at: r.get(3)?,
+    })
+}
+
+/* Records a hold of `mode`/`target_temp` for the next `minutes`.
+   Any override still running is ended first, so at most one is active. */
+pub fn set_override(conn: &Connection, username: &str, mode: &str, target_temp: f32, minutes: i64) -> Result<i64> {
+    set_override_with_clock(conn, username, mode, target_temp, minutes, &RealClock)
+}
+
+pub fn set_override_with_clock(
+    conn: &Connection,
+    username: &str,
+    mode: &str,
+    target_temp: f32,
+    minutes: i64,
+    clock: &dyn Clock,
+) -> Result<i64> {
+    if !(1..=MAX_OVERRIDE_MINUTES).contains(&minutes) {
+        return Err(anyhow!("Override duration must be 1–{} minutes.", MAX_OVERRIDE_MINUTES));
+    }
+    if !matches!(mode, "Heating" | "Cooling" | "Auto") {
+        return Err(anyhow!("Overrides need a temperature mode (Heating, Cooling or Auto)."));
+    }
+    validate_profile_temperature(mode, target_temp)?;
+
+    end_active_override_with_clock(conn, clock)?;
+    conn.execute(
+        "INSERT INTO overrides (mode, target_temp, set_by, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?5, datetime(?5, printf('+%d minutes', ?4)))",
+        params![mode, target_temp as f64, username, minutes, clock.sqlite_now()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// The override currently holding the schedule back, if any
+pub fn active_override(conn: &Connection) -> Result<Option<TemporaryOverride>> {
+    active_override_with_clock(conn, &RealClock)
+}
+
+pub fn active_override_with_clock(conn: &Connection, clock: &dyn Clock) -> Result<Option<TemporaryOverride>> {
+    Ok(conn
+        .query_row(
+            "SELECT mode, target_temp, set_by, expires_at FROM overrides
+              WHERE kind = 'temperature' AND ended_at IS NULL AND expires_at > ?1
+              ORDER BY id DESC LIMIT 1",
+            params![clock.sqlite_now()],
+            map_override,
+        )
+        .optional()?)
+}
+
+// Ends the running override early; returns whether there was one
+pub fn end_active_override(conn: &Connection) -> Result<bool> {
+    end_active_override_with_clock(conn, &RealClock)
+}
+
+pub fn end_active_override_with_clock(conn: &Connection, clock: &dyn Clock) -> Result<bool> {
+    let n = conn.execute(
+        "UPDATE overrides SET ended_at = ?1
+          WHERE kind = 'temperature' AND ended_at IS NULL AND expires_at > ?1",
+        params![clock.sqlite_now()],
+    )?;
+    Ok(n > 0)
+}
+
+/* Marks overrides whose time ran out as ended and returns the latest one.
+   The caller reverts to the scheduled profile when this returns Some. */
+pub fn take_expired_override(conn: &Connection) -> Result<Option<TemporaryOverride>> {
+    take_expired_override_with_clock(conn, &RealClock)
+}
+
+pub fn take_expired_override_with_clock(conn: &Connection, clock: &dyn Clock) -> Result<Option<TemporaryOverride>> {
+    let now = clock.sqlite_now();
+    let expired = conn
+        .query_row(
+            "SELECT mode, target_temp, set_by, expires_at FROM overrides
+              WHERE kind = 'temperature' AND ended_at IS NULL AND expires_at <= ?1
+              ORDER BY id DESC LIMIT 1",
+            params![now],
+            map_override,
+        )
+        .optional()?;
+    if expired.is_some() {
+        conn.execute(
+            "UPDATE overrides SET ended_at = expires_at
+              WHERE kind = 'temperature' AND ended_at IS NULL AND expires_at <= ?1",
+            params![now],
+        )?;
+    }
+    Ok(expired)
+}
+
+#[derive(Debug, Clone)]
+pub struct FanOverride {
+    pub fan_speed: String,
+    pub set_by: String,
+    pub expires_at: String, // UTC, "YYYY-MM-DD HH:MM:SS"
+}
+
+/* Runs the fan at `fan_speed` for the next `minutes`, leaving mode and target alone.
+   A boost already running is replaced. */
+pub fn set_fan_override(conn: &Connection, username: &str, fan_speed: &str, minutes: i64) -> Result<i64> {
+    set_fan_override_with_clock(conn, username, fan_speed, minutes, &RealClock)
+}
+
+pub fn set_fan_override_with_clock(
+    conn: &Connection,
+    username: &str,
+    fan_speed: &str,
+    minutes: i64,
+    clock: &dyn Clock,
+) -> Result<i64> {
+    if !(1..=MAX_OVERRIDE_MINUTES).contains(&minutes) {
+        return Err(anyhow!("Fan boost duration must be 1–{} minutes.", MAX_OVERRIDE_MINUTES));
+    }
+    if !matches!(fan_speed, "Low" | "Medium" | "High") {
+        return Err(anyhow!("Fan speed must be Low, Medium or High."));
+    }
+
+    end_active_fan_override_with_clock(conn, clock)?;
+    conn.execute(
+        "INSERT INTO overrides (kind, fan_speed, set_by, created_at, expires_at)
+         VALUES ('fan', ?1, ?2, ?4, datetime(?4, printf('+%d minutes', ?3)))",
+        params![fan_speed, username, minutes, clock.sqlite_now()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn active_fan_override(conn: &Connection) -> Result<Option<FanOverride>> {
+    active_fan_override_with_clock(conn, &RealClock)
+}
+
+pub fn active_fan_override_with_clock(conn: &Connection, clock: &dyn Clock) -> Result<Option<FanOverride>> {
+    Ok(conn
+        .query_row(
+            "SELECT fan_speed, set_by, expires_at FROM overrides
+              WHERE kind = 'fan' AND ended_at IS NULL AND expires_at > ?1
+              ORDER BY id DESC LIMIT 1",
+            params![clock.sqlite_now()],
+            |r| Ok(FanOverride { fan_speed: r.get(0)?, set_by: r.get(1)?, expires_at: r.get(2)? }),
+        )
+        .optional()?)
+}
+
+pub fn end_active_fan_override(conn: &Connection) -> Result<bool> {
+    end_active_fan_override_with_clock(conn, &RealClock)
+}
+
+pub fn end_active_fan_override_with_clock(conn: &Connection, clock: &dyn Clock) -> Result<bool> {
+    let n = conn.execute(
+        "UPDATE overrides SET ended_at = ?1
+          WHERE kind = 'fan' AND ended_at IS NULL AND expires_at > ?1",
+        params![clock.sqlite_now()],
+    )?;
+    Ok(n > 0)
+}
+
+// Fan speed in effect: a running boost, else the active profile's own speed.
+// hvac_state keeps no fan column, so an expired boost needs no revert step.
+pub fn current_fan_speed(conn: &Connection) -> Result<Option<String>> {
+    if let Some(boost) = active_fan_override(conn)? {
+        return Ok(Some(boost.fan_speed));
+    }
+    let (_, _, _, profile) = get_hvac_state(conn)?;
+    match profile {
+        Some(name) => Ok(get_profile_row(conn, &name)?.map(|p| p.fan_speed)),
+        None => Ok(None),
+    }
+}
+
+// ======================================================
+//                  EMERGENCY ALL-OFF
+// ======================================================
+
+// Start of the HVAC event an all-off writes; the cool-down report measures from it
+pub const EMERGENCY_ALL_OFF: &str = "Emergency all-off";
+// How long after an all-off the sweeper keeps taking indoor snapshots
+pub const COOLDOWN_WINDOW_MINUTES: i64 = 60;
+
+/* Logs the all-off and its first indoor snapshot. The caller (HVACSystem)
+   has already switched everything off; holds and fan boosts end here. */
+pub fn record_emergency_all_off(conn: &Connection, username: &str, indoor_c: Option<f32>) -> Result<()> {
+    end_active_override(conn)?;
+    end_active_fan_override(conn)?;
+    logger::log_event(conn, username, None, "HVAC", Some(&format!(
+        "{} by '{}': HVAC, light, holds and fan boosts switched off", EMERGENCY_ALL_OFF, username
+    )))?;
+    if let Some(t) = indoor_c {
+        record_sensor_reading(conn, t, None)?;
+    }
+    Ok(())
+}
+
+// UTC "YYYY-MM-DD HH:MM:SS" of the latest all-off, from the security log
+pub fn last_emergency_all_off(conn: &Connection) -> Result<Option<String>> {
+    Ok(conn.query_row(
+        "SELECT MAX(datetime(timestamp)) FROM security_log
+          WHERE event_type = 'HVAC' AND description LIKE ?1 || '%'",
+        params![EMERGENCY_ALL_OFF],
+        |r| r.get(0),
+    )?)
+}
+
+// True while the latest all-off is recent enough that snapshots are still being taken
+pub fn cooldown_in_progress(conn: &Connection, clock: &dyn Clock) -> Result<bool> {
+    let Some(at) = last_emergency_all_off(conn)? else { return Ok(false) };
+    Ok(conn.query_row(
+        "SELECT datetime(?1, printf('+%d minutes', ?2)) > ?3",
+        params![at, COOLDOWN_WINDOW_MINUTES, clock.sqlite_now()],
+        |r| r.get(0),
+    )?)
+}
+
+// Indoor temperature over time since `since` (UTC), oldest first, as (UTC time, °C)
+pub fn cooldown_report(conn: &Connection, since: &str) -> Result<Vec<(String, f32)>> {
+    let mut stmt = conn.prepare(
+        "SELECT datetime(recorded_at), temperature_c FROM sensor_readings
+          WHERE datetime(recorded_at) >= datetime(?1)
+          ORDER BY datetime(recorded_at), id",
+    )?;
+    let rows = stmt.query_map(params![since], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// ======================================================
+//                      DEMO DATA
+// ======================================================
+
+pub const DEMO_PASSWORD: &str = "Demo!Pass2025";
+pub const DEMO_GUEST_PIN: &str = "4826";
+
+/* Fills a database with a small, believable household for demos and manual testing.
+   Refuses when users already exist unless `force` is set; existing usernames are kept. */
+pub fn seed_demo_data(conn: &mut Connection, force: bool) -> Result<()> {
+    let existing: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |r| r.get(0))?;
+    if existing > 0 && !force {
+        return Err(anyhow!(
+            "Refusing to seed demo data: {} user(s) already exist (use --force to seed anyway).",
+            existing
+        ));
+    }
+
+    // One hash per secret keeps seeding quick; these accounts are for demos only
+    let password_hash = auth::hash_password(DEMO_PASSWORD)?;
+    let pin_hash = auth::hash_password(DEMO_GUEST_PIN)?;
+
+    {
+        let tx = conn.transaction()?;
+        // The demo password is published, so the admin has to replace it at first login
+        for (username, role) in [("demo_admin", "admin"), ("demo_home", "homeowner"), ("demo_tech", "technician")] {
+            tx.execute(
+                "INSERT OR IGNORE INTO users (username, hashed_password, user_status, force_password_change, updated_at)
+                 VALUES (?1, ?2, ?3, ?3 = 'admin', datetime('now'))",
+                params![username, password_hash, role],
+            )?;
+        }
+        for guest in ["demo_guest1", "demo_guest2"] {
+            tx.execute(
+                "INSERT OR IGNORE INTO users (username, hashed_password, user_status, homeowner_id, updated_at)
+                 VALUES (?1, ?2, 'guest', (SELECT id FROM users WHERE username = 'demo_home'), datetime('now'))",
+                params![guest, pin_hash],
+            )?;
+        }
+
+        log_profile_applied(&tx, "demo_home", "homeowner", "Day", "Auto", 22.0)?;
+        log_temperature_changed(&tx, "demo_home", "homeowner", 22.0, 23.5)?;
+        log_mode_changed(&tx, "demo_guest1", "guest", "Auto", "Cooling")?;
+        log_profile_applied(&tx, "demo_home", "homeowner", "Night", "Heating", 20.0)?;
+        tx.commit()?;
+    }
+
+    grant_technician_access(conn, "demo_home", "demo_tech", AccessDuration::Minutes60,
+        "Annual furnace inspection and filter change")?;
+
+    for (time, temp_c, humidity, condition) in [
+        ("Jan 08, 2025 08:00 AM EST", -2.0, 71.0, "Light Snow"),
+        ("Jan 08, 2025 12:00 PM EST", 1.5, 60.0, "Cloudy"),
+        ("Jan 08, 2025 04:00 PM EST", 3.0, 55.0, "Partly Sunny"),
+    ] {
+        let temp_f = crate::units::celsius_to_fahrenheit(temp_c as f32) as f64;
+        insert_weather(conn, &WeatherRecord {
+            time: time.to_string(),
+            temperature_f: Some(temp_f),
+            temperature_c: Some(temp_c),
+            dewpoint_f: None,
+            dewpoint_c: None,
+            humidity: Some(humidity),
+            wind_speed_mph: Some(8.0),
+            wind_direction_deg: Some(300.0),
+            condition: condition.to_string(),
+        })?;
+    }
+    Ok(())
+}
@@ -1,10 +1,39 @@
 use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
+use rusqlite::{params, Connection};
 use std::{thread, time::Duration};
 
-use crate::{senser, weather};
+use crate::{db, senser, weather};
 
-pub fn run_diagnostics() -> Result<()> {
+// Key readings and pass/fail flags for one diagnostics run
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticResult {
+    pub outdoor_temp_c: Option<f64>,
+    pub indoor_temp_c: Option<f32>,
+    pub indoor_humidity: Option<f32>,
+    pub indoor_co_ppm: Option<f32>,
+    pub outdoor_ok: bool,
+    pub indoor_ok: bool,
+    pub devices_ok: bool,
+}
+
+impl DiagnosticResult {
+    pub fn passed(&self) -> bool {
+        self.outdoor_ok && self.indoor_ok && self.devices_ok
+    }
+}
+
+// A stored diagnostics run, as listed in the history
+#[derive(Debug, Clone)]
+pub struct DiagnosticRun {
+    pub id: i64,
+    pub actor_username: String,
+    pub run_at: String,
+    pub result: DiagnosticResult,
+    pub passed: bool,
+}
+
+pub fn run_diagnostics(conn: &Connection, actor_username: &str) -> Result<()> {
     let steps = [
         "🌡️  Outdoor Temperature Sensor",
         "💧  Outdoor Humidity Sensor",
@@ -21,6 +50,13 @@ pub fn run_diagnostics() -> Result<()> {
     let total = steps.len();
     println!("🧰 Starting Smart Thermostat Diagnostics ({total} items total)…\n");
 
+    let mut result = DiagnosticResult {
+        outdoor_ok: true,
+        indoor_ok: true,
+        devices_ok: true,
+        ..Default::default()
+    };
+
     for (i, name) in steps.iter().enumerate() {
         let pb = ProgressBar::new(100);
         pb.set_style(
@@ -42,31 +78,51 @@ pub fn run_diagnostics() -> Result<()> {
             "🌡️  Outdoor Temperature Sensor"
             | "💧  Outdoor Humidity Sensor"
             | "🌬️  Outdoor Wind Sensor" => {
-                // Only one call to fetch_weather() to validate outdoor sensors
-                if let Err(e) = weather::fetch_weather() {
-                    pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
-                    continue;
+                // Only one fetch, at the configured location, to validate outdoor sensors
+                match weather::fetch_weather_at(&db::weather_location(conn).unwrap_or_default()) {
+                    Ok(w) => {
+                        if result.outdoor_temp_c.is_none() {
+                            result.outdoor_temp_c = w.temperature_c;
+                        }
+                    }
+                    Err(e) => {
+                        result.outdoor_ok = false;
+                        pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
+                        continue;
+                    }
                 }
             }
 
             "🏠🌡️  Indoor Temperature Sensor" => {
-                if let Err(e) = senser::get_indoor_temperature() {
-                    pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
-                    continue;
+                match senser::get_indoor_temperature() {
+                    Ok(v) => result.indoor_temp_c = Some(v),
+                    Err(e) => {
+                        result.indoor_ok = false;
+                        pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
+                        continue;
+                    }
                 }
             }
 
             "🏠💧  Indoor Humidity Sensor" => {
-                if let Err(e) = senser::get_indoor_humidity() {
-                    pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
-                    continue;
+                match senser::get_indoor_humidity() {
+                    Ok(v) => result.indoor_humidity = Some(v),
+                    Err(e) => {
+                        result.indoor_ok = false;
+                        pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
+                        continue;
+                    }
                 }
             }
 
             "🏠🫧  Indoor CO Sensor" => {
-                if let Err(e) = senser::get_indoor_colevel() {
-                    pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
-                    continue;
+                match senser::get_indoor_colevel() {
+                    Ok(v) => result.indoor_co_ppm = Some(v),
+                    Err(e) => {
+                        result.indoor_ok = false;
+                        pb.abandon_with_message(format!("({}/{}) {} failed: {}", i + 1, total, name, e));
+                        continue;
+                    }
                 }
             }
 
@@ -84,6 +140,112 @@ pub fn run_diagnostics() -> Result<()> {
         pb.finish_with_message(format!("({}/{}) {} OK ✓", i + 1, total, name));
     }
 
-    println!("\n✅ All systems are functioning normally! Diagnostics completed successfully.\n");
+    // Keep a record so repeated checks can be compared later
+    record_diagnostics(conn, actor_username, &result)?;
+
+    if result.passed() {
+        println!("\n✅ All systems are functioning normally! Diagnostics completed successfully.\n");
+    } else {
+        println!("\n⚠️  Diagnostics completed with failures. See the history for past runs.\n");
+    }
+    Ok(())
+}
+
+// ======================================================
+//                  DIAGNOSTICS HISTORY
+// ======================================================
+
+// Persist one diagnostics run, returning its row id.
+pub fn record_diagnostics(conn: &Connection, actor_username: &str, result: &DiagnosticResult) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO diagnostics_log
+            (actor_username, outdoor_temp_c, indoor_temp_c, indoor_humidity, indoor_co_ppm,
+             outdoor_ok, indoor_ok, devices_ok, passed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            actor_username,
+            result.outdoor_temp_c,
+            result.indoor_temp_c,
+            result.indoor_humidity,
+            result.indoor_co_ppm,
+            result.outdoor_ok as i64,
+            result.indoor_ok as i64,
+            result.devices_ok as i64,
+            result.passed() as i64,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Most recent diagnostics runs first.
+pub fn diagnostics_history(conn: &Connection, limit: usize) -> Result<Vec<DiagnosticRun>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, actor_username, run_at, outdoor_temp_c, indoor_temp_c, indoor_humidity, indoor_co_ppm,
+                outdoor_ok, indoor_ok, devices_ok, passed
+           FROM diagnostics_log
+          ORDER BY id DESC
+          LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![limit as i64], |r| {
+        Ok(DiagnosticRun {
+            id: r.get(0)?,
+            actor_username: r.get(1)?,
+            run_at: r.get(2)?,
+            result: DiagnosticResult {
+                outdoor_temp_c: r.get(3)?,
+                indoor_temp_c: r.get(4)?,
+                indoor_humidity: r.get(5)?,
+                indoor_co_ppm: r.get(6)?,
+                outdoor_ok: r.get::<_, i64>(7)? == 1,
+                indoor_ok: r.get::<_, i64>(8)? == 1,
+                devices_ok: r.get::<_, i64>(9)? == 1,
+            },
+            passed: r.get::<_, i64>(10)? == 1,
+        })
+    })?;
+    let mut out = Vec::new();
+    for r in rows { out.push(r?); }
+    Ok(out)
+}
+
+// Print past diagnostics runs (technicians and admins only).
+pub fn view_diagnostics_history(conn: &Connection, role: &str, limit: usize) -> Result<()> {
+    if role != "technician" && role != "admin" {
+        println!("Access denied: only technicians and admins can view diagnostics history.");
+        return Ok(());
+    }
+
+    let runs = diagnostics_history(conn, limit)?;
+    println!("\n======= Diagnostics History (last {}) =======", limit);
+    if runs.is_empty() {
+        println!("No diagnostics runs recorded yet.");
+        return Ok(());
+    }
+
+    let fmt = |v: Option<f32>| v.map(|x| format!("{:.1}", x)).unwrap_or_else(|| "-".to_string());
+    let flag = |ok: bool| if ok { "OK" } else { "FAIL" };
+
+    println!(
+        "{:<5} {:<20} {:<12} {:>8} {:>8} {:>7} {:>6} {:<8} {:<8} {:<8} {:<6}",
+        "ID", "Run At", "By", "Out °C", "In °C", "Hum %", "CO", "Outdoor", "Indoor", "Devices", "Result"
+    );
+    println!("{}", "-".repeat(104));
+    for run in &runs {
+        let r = &run.result;
+        println!(
+            "{:<5} {:<20} {:<12} {:>8} {:>8} {:>7} {:>6} {:<8} {:<8} {:<8} {:<6}",
+            run.id,
+            run.run_at,
+            run.actor_username,
+            r.outdoor_temp_c.map(|x| format!("{:.1}", x)).unwrap_or_else(|| "-".to_string()),
+            fmt(r.indoor_temp_c),
+            fmt(r.indoor_humidity),
+            fmt(r.indoor_co_ppm),
+            flag(r.outdoor_ok),
+            flag(r.indoor_ok),
+            flag(r.devices_ok),
+            if run.passed { "PASS" } else { "FAIL" },
+        );
+    }
     Ok(())
 }
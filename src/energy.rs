@@ -16,6 +16,76 @@ pub struct EnergyUsage {
 
 pub struct EnergyTracker;
 
+// ======================================================
+//                 ENERGY COST FORMATTING
+// ======================================================
+
+// Currencies the cost display knows how to write; USD unless the user picks another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Currency {
+    #[default]
+    Usd,
+    Eur,
+    Gbp,
+    Jpy,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 4] = [Currency::Usd, Currency::Eur, Currency::Gbp, Currency::Jpy];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    // ISO code, any case
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    // (symbol, symbol goes first, decimals, thousands separator, decimal separator)
+    fn style(self) -> (&'static str, bool, usize, char, char) {
+        match self {
+            Currency::Usd => ("$", true, 2, ',', '.'),
+            Currency::Eur => ("€", false, 2, '.', ','),
+            Currency::Gbp => ("£", true, 2, ',', '.'),
+            Currency::Jpy => ("¥", true, 0, ',', '.'),
+        }
+    }
+
+    /* An amount in this currency's usual form, e.g. USD "$1,234.56",
+       EUR "1.234,56 €", JPY "¥1,235" (for 1234.56). Negative amounts lead with '-'. */
+    pub fn format(self, amount: f64) -> String {
+        let (symbol, prefix, decimals, thousands, decimal) = self.style();
+        let fixed = format!("{:.*}", decimals, amount.abs());
+        let (whole, frac) = fixed.split_once('.').unwrap_or((&fixed, ""));
+
+        let mut grouped = String::new();
+        for (i, ch) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push(thousands);
+            }
+            grouped.push(ch);
+        }
+        if !frac.is_empty() {
+            grouped.push(decimal);
+            grouped.push_str(frac);
+        }
+
+        // -0.00 after rounding is still zero
+        let sign = if amount < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+        if prefix {
+            format!("{}{}{}", sign, symbol, grouped)
+        } else {
+            format!("{}{} {}", sign, grouped, symbol)
+        }
+    }
+}
+
 impl EnergyTracker {
     // Generate mock energy data for the past N days (DST-safe)
     pub fn generate_mock_data(days: i64, _homeowner_username: &str) -> Vec<EnergyUsage> {
@@ -136,6 +206,11 @@ impl EnergyTracker {
 
     // Print formatted energy usage report
     pub fn display_energy_report(data: &[EnergyUsage]) {
+        Self::display_energy_report_with_cost(data, None);
+    }
+
+    // Same report plus estimated cost lines when a (rate per kWh, currency) is configured
+    pub fn display_energy_report_with_cost(data: &[EnergyUsage], cost: Option<(f64, Currency)>) {
         if data.is_empty() {
             println!("No energy usage data available.");
             return;
@@ -154,6 +229,10 @@ impl EnergyTracker {
         println!(" Summary:");
         println!("   • Total Energy Used: {:.2} kWh", total_energy);
         println!("   • Average Daily: {:.2} kWh", avg_daily);
+        if let Some((rate, currency)) = cost {
+            println!("   • Estimated Cost: {} ({} per kWh)", currency.format(total_energy * rate), currency.format(rate));
+            println!("   • Average Daily Cost: {}", currency.format(avg_daily * rate));
+        }
         println!("   • Efficiency Rating: {}", efficiency);
         println!("   • Period: {} days", daily_usage.len());
         println!();
@@ -283,7 +362,9 @@ pub fn view_energy_usage(conn: &Connection, username: &str) -> Result<()> {
         }
     };
 
-    EnergyTracker::display_energy_report(&energy_data);
+    let cost = crate::db::energy_rate(conn, username)?
+        .map(|rate| (rate, crate::db::energy_currency(conn, username).unwrap_or_default()));
+    EnergyTracker::display_energy_report_with_cost(&energy_data, cost);
     
     Ok(())
 }
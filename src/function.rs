@@ -1,4 +1,106 @@
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use rusqlite::Connection;
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::io::{self, Write};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Idle time before a role menu gives up waiting and logs the user out
+pub const MENU_TIMEOUT_SECS: u64 = 300;
+// How long before the auto-logout the countdown warning appears
+pub const INACTIVITY_WARNING_SECS: u64 = 30;
+
+// Shipped per-role idle limit; accounts with more reach get logged out sooner
+pub fn default_inactivity_timeout_secs(role: &str) -> u64 {
+    match role {
+        "admin" => 120,
+        "technician" => 180,
+        "guest" => 240,
+        _ => MENU_TIMEOUT_SECS,
+    }
+}
+
+fn inactivity_timeout_key(role: &str) -> String {
+    format!("inactivity_timeout_secs:{}", role)
+}
+
+// Idle limit for the role as the admin configured it (system_settings), else the shipped default
+pub fn inactivity_timeout_secs(conn: &Connection, role: &str) -> Result<u64> {
+    Ok(crate::db::get_setting(conn, &inactivity_timeout_key(role))?
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_else(|| default_inactivity_timeout_secs(role)))
+}
+
+// The limit has to outlast the countdown warning, or the warning would never show
+pub fn set_inactivity_timeout_secs(conn: &Connection, admin_username: &str, role: &str, secs: u64) -> Result<()> {
+    if secs <= INACTIVITY_WARNING_SECS {
+        return Err(anyhow!("Idle limit must be more than {} seconds.", INACTIVITY_WARNING_SECS));
+    }
+    let old = inactivity_timeout_secs(conn, role)?;
+    crate::db::set_setting(conn, &inactivity_timeout_key(role), &secs.to_string(), admin_username)?;
+    crate::logger::log_event(conn, admin_username, None, "SETTINGS_CHANGED", Some(&format!(
+        "Idle limit for {} changed: {}s -> {}s", role, old, secs
+    )))?;
+    Ok(())
+}
+
+// Time left before auto-logout, counted from the last input
+pub fn inactivity_remaining(last_activity: Instant, now: Instant, timeout: Duration) -> Duration {
+    timeout.saturating_sub(now.saturating_duration_since(last_activity))
+}
+
+lazy_static! {
+    // Reader thread left blocked on stdin by a timed-out prompt; its line goes to the next prompt
+    static ref PENDING_INPUT: Mutex<Option<mpsc::Receiver<Option<String>>>> = Mutex::new(None);
+}
+
+thread_local! {
+    // Lines fed to prompts instead of stdin; once drained, prompts see EOF
+    static SCRIPTED_INPUT: RefCell<Option<VecDeque<String>>> = const { RefCell::new(None) };
+}
+
+// Runs `f` with prompts answered from `lines` on this thread, followed by EOF.
+pub fn with_scripted_input<T>(lines: &[&str], f: impl FnOnce() -> T) -> T {
+    SCRIPTED_INPUT.with(|s| *s.borrow_mut() = Some(lines.iter().map(|l| l.to_string()).collect()));
+    let out = f();
+    SCRIPTED_INPUT.with(|s| *s.borrow_mut() = None);
+    out
+}
+
+// Some(line or None-for-EOF) while a script is active, None otherwise
+fn next_scripted_line() -> Option<Option<String>> {
+    SCRIPTED_INPUT.with(|s| s.borrow_mut().as_mut().map(|q| q.pop_front()))
+}
+
+fn read_stdin_line() -> Option<String> {
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) => None, // EOF
+        Ok(_) => Some(input.trim().to_string()),
+        Err(e) => {
+            eprintln!("Error reading input: {e}");
+            None
+        }
+    }
+}
+
+fn spawn_reader<F>(read: F) -> mpsc::Receiver<Option<String>>
+where
+    F: FnOnce() -> Option<String> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(read());
+    });
+    rx
+}
+
+fn take_pending() -> Option<mpsc::Receiver<Option<String>>> {
+    PENDING_INPUT.lock().ok().and_then(|mut p| p.take())
+}
 
 // ==============================================
 //            Prompt user for input
@@ -9,14 +111,131 @@ pub fn prompt_input() -> Option<String> {
         return None;
     }
 
-    let mut input = String::new();
-    match io::stdin().read_line(&mut input) {
-        Ok(0) => None, // EOF
-        Ok(_) => Some(input.trim().to_string()),
-        Err(e) => {
-            eprintln!("Error reading input: {e}");
+    if let Some(line) = next_scripted_line() {
+        return line;
+    }
+
+    // A reader from an earlier timed-out prompt already owns the next line
+    if let Some(rx) = take_pending() {
+        return rx.recv().ok().flatten();
+    }
+
+    read_stdin_line()
+}
+
+// ==============================================
+//       Read a line with an idle timeout
+// ==============================================
+// Runs `read` on a helper thread and returns None if nothing arrives within `timeout`.
+pub fn recv_line_with_timeout<F>(read: F, timeout: Duration) -> Option<String>
+where
+    F: FnOnce() -> Option<String> + Send + 'static,
+{
+    await_line(spawn_reader(read), timeout)
+}
+
+fn await_line(rx: mpsc::Receiver<Option<String>>, timeout: Duration) -> Option<String> {
+    match rx.recv_timeout(timeout) {
+        Ok(line) => line,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            // keep the blocked reader so its line is not lost
+            if let Ok(mut p) = PENDING_INPUT.lock() {
+                *p = Some(rx);
+            }
             None
         }
+        Err(mpsc::RecvTimeoutError::Disconnected) => None,
+    }
+}
+
+// Like prompt_input, but returns None after `timeout` with no input.
+// Use input_timed_out() to tell a timeout apart from EOF.
+pub fn prompt_input_timeout(timeout: Duration) -> Option<String> {
+    if io::stdout().flush().is_err() {
+        eprintln!("Error flushing stdout.");
+        return None;
+    }
+
+    if let Some(line) = next_scripted_line() {
+        return line;
+    }
+
+    match take_pending() {
+        Some(rx) => await_line(rx, timeout),
+        None => recv_line_with_timeout(read_stdin_line, timeout),
+    }
+}
+
+// True when the last timed prompt expired rather than hitting EOF
+pub fn input_timed_out() -> bool {
+    PENDING_INPUT.lock().map(|p| p.is_some()).unwrap_or(false)
+}
+
+
+// ==============================================
+//     Role menu prompt with inactivity logout
+// ==============================================
+// Waits for a menu choice, warning shortly before the role's idle limit runs out.
+// None means EOF or the limit elapsed; input_timed_out() tells them apart.
+pub fn prompt_menu_choice(conn: &Connection, role: &str) -> Option<String> {
+    let secs = inactivity_timeout_secs(conn, role).unwrap_or_else(|_| default_inactivity_timeout_secs(role));
+    let timeout = Duration::from_secs(secs);
+    let warning = Duration::from_secs(INACTIVITY_WARNING_SECS).min(timeout);
+    let last_activity = Instant::now();
+
+    if let Some(line) = prompt_input_timeout(timeout - warning) {
+        return Some(line);
+    }
+    if !input_timed_out() {
+        return None;
+    }
+
+    let remaining = inactivity_remaining(last_activity, Instant::now(), timeout);
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    println!("\n⏳ Logging out in {}s due to inactivity. Press ENTER to stay signed in.", secs);
+    prompt_input_timeout(remaining)
+}
+
+
+// ==============================================
+//        Numbered list selection (1..=max)
+// ==============================================
+/* The 0-based list index for a number typed at a "[1-N]" prompt. Only plain
+   digits are accepted, so signs, decimals and numbers too big for usize are
+   rejected the same way as 0 or anything past max. */
+pub fn parse_menu_index(input: &str, max: usize) -> Option<usize> {
+    let s = input.trim();
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let n: usize = s.parse().ok()?;
+    (1..=max).contains(&n).then(|| n - 1)
+}
+
+
+// ==============================================
+//          Yes/no confirmation prompt
+// ==============================================
+// y/yes/n/no in any case; an empty answer takes the default. None for anything else.
+pub fn parse_confirmation(input: &str, default: bool) -> Option<bool> {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "" => Some(default),
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// Asks until it gets a recognizable answer; EOF counts as "no"
+pub fn confirm(prompt: &str, default: bool) -> bool {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        print!("{} {}: ", prompt, hint);
+        let Some(answer) = prompt_input() else { return false };
+        match parse_confirmation(&answer, default) {
+            Some(choice) => return choice,
+            None => println!("Please answer y or n."),
+        }
     }
 }
 
@@ -26,9 +245,6 @@ pub fn prompt_input() -> Option<String> {
 // ==============================================
 pub fn wait_for_enter() {
     print!("Press ENTER to continue...");
-    let _ = io::stdout().flush();
-    let mut buf = String::new();
-    let _ = io::stdin().read_line(&mut buf);
+    let _ = prompt_input();
     println!();
 }
-
@@ -1,5 +1,4 @@
 use anyhow::{Result};
-use rpassword::read_password; // hidden password entry for CLI
 use std::io::{self, Write}; // reading inputs and printing prompts
 use zeroize::Zeroizing; // used for sensitive data are wiped from the memory after use
 use rusqlite::{params, Connection, OptionalExtension}; // handle for executing SQL queries
@@ -8,8 +7,28 @@ use crate::logger;
 use crate::db;
 use crate::auth;
 use crate::ui;
+use crate::function::{confirm, parse_menu_index, prompt_input, wait_for_enter};
 
 
+// Owning homeowner that blocks this guest's login, when strict owner checks are on
+pub fn guest_blocked_by_owner(conn: &Connection, guest_username: &str) -> Result<Option<String>> {
+    if !db::strict_guest_owner_check(conn)? {
+        return Ok(None);
+    }
+    db::inactive_owner_of_guest(conn, guest_username)
+}
+
+// The PIN was right, so this is logged as LOGIN_REFUSED; it never counts toward a lockout
+pub fn log_login_refused_by_owner(conn: &Connection, guest_username: &str, owner: &str) -> Result<()> {
+    logger::log_event(
+        conn,
+        guest_username,
+        Some(owner),
+        "LOGIN_REFUSED",
+        Some(&format!("Guest login refused: homeowner '{}' is disabled", owner)),
+    )
+}
+
 // Guest login using PIN authentication
 pub fn guest_login_user(conn: &mut Connection) -> Result<Option<String>> {
     // Single in-process session guard
@@ -33,8 +52,7 @@ pub fn guest_login_user(conn: &mut Connection) -> Result<Option<String>> {
     // Prompt for username
     print!("Guest username: ");
     io::stdout().flush().ok();
-    let mut username = String::new();
-    io::stdin().read_line(&mut username)?;
+    let Some(username) = prompt_input() else { return Ok(None); };
     let username = username.trim().to_string();
 
     if username.is_empty() {
@@ -55,7 +73,7 @@ pub fn guest_login_user(conn: &mut Connection) -> Result<Option<String>> {
     // Ask for guest PIN (hidden input)
     print!("Enter PIN: ");
     io::stdout().flush().ok();
-    let pin_in = Zeroizing::new(read_password()?);
+    let pin_in = auth::read_secret()?;
     let pin = pin_in.trim_end_matches(['\r', '\n']); // &str view; buffer wiped on drop
 
     // Fetch stored hash for this guest user
@@ -100,6 +118,13 @@ pub fn guest_login_user(conn: &mut Connection) -> Result<Option<String>> {
         return Ok(None);
     }
 
+    // Strict mode: a disabled homeowner takes their guests offline too
+    if let Some(owner) = guest_blocked_by_owner(conn, &username)? {
+        println!("Guest access is suspended because the homeowner account is disabled.");
+        log_login_refused_by_owner(conn, &username, &owner)?;
+        return Ok(None);
+    }
+
     // cleanup of expired sessions
     let _ = conn.execute(
         "DELETE FROM session_state WHERE session_expires <= datetime('now')",
@@ -134,6 +159,7 @@ pub fn guest_login_user(conn: &mut Connection) -> Result<Option<String>> {
     // Success: record login + create new session (stores only hash; returns plaintext token)
     logger::record_login_attempt(conn, &username, true)?;
     let _session_token_plain = db::update_session(conn, Some(&username))?; // DO NOT persist
+    db::record_guest_login_notice(conn, &username)?;
 
     // Reflect the session in-process so logout_user can find it (CLI guard)
     {
@@ -186,7 +212,7 @@ pub fn enable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
                       FROM technician_jobs
                      WHERE technician_username = ?1 COLLATE NOCASE
                        AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-                       AND datetime(updated_at, printf('+%d minutes', access_minutes)) > datetime('now')
+                       AND grant_expires > datetime('now')
                      ORDER BY updated_at DESC
                      LIMIT 1
                     "#,
@@ -279,11 +305,10 @@ pub fn enable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
     // Choose guest
     print!("\nEnter the number of the guest to enable: ");
     io::stdout().flush().ok();
-    let mut choice = String::new();
-    io::stdin().read_line(&mut choice).ok();
-    let choice = choice.trim().parse::<usize>().ok();
+    let Some(choice) = prompt_input() else { return Ok(()); };
+    let choice = parse_menu_index(&choice, guests.len());
 
-    let (guest_username, active) = match choice.and_then(|n| guests.get(n - 1)) {
+    let (guest_username, active) = match choice.and_then(|i| guests.get(i)) {
         Some((uname, active, _, _)) => (uname.clone(), *active),
         None => {
             println!("Invalid selection.");
@@ -298,11 +323,7 @@ pub fn enable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
 
 
     // Enable guest confirmation to prevent accidental actions
-    print!("Confirm enabling guest '{}'? (yes/no): ", guest_username);
-    io::stdout().flush().ok();
-    let mut confirm = String::new();
-    io::stdin().read_line(&mut confirm).ok();
-    if confirm.trim().to_lowercase() != "yes" { //if user didn't type yes, cancel
+    if !confirm(&format!("Confirm enabling guest '{}'?", guest_username), false) {
         println!("Action cancelled.");
         return Ok(());
     }
@@ -311,7 +332,7 @@ pub fn enable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
     let tx = conn.transaction()?;
     let affected = tx.execute(
         "UPDATE users
-         SET is_active = 1, updated_at = datetime('now')
+         SET is_active = 1, bulk_disabled = 0, updated_at = datetime('now')
          WHERE username = ?1
            AND homeowner_id = ?2",
         params![guest_username, homeowner_id],
@@ -368,7 +389,7 @@ pub fn disable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
                       FROM technician_jobs
                      WHERE technician_username = ?1 COLLATE NOCASE
                        AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-                       AND datetime(updated_at, printf('+%d minutes', access_minutes)) > datetime('now')
+                       AND grant_expires > datetime('now')
                      ORDER BY updated_at DESC
                      LIMIT 1
                     "#,
@@ -458,11 +479,10 @@ pub fn disable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
     // Select which guest to disable
     print!("\nEnter the number of the guest to disable: ");
     io::stdout().flush().ok();
-    let mut choice = String::new();
-    io::stdin().read_line(&mut choice).ok();
-    let choice = choice.trim().parse::<usize>().ok();
+    let Some(choice) = prompt_input() else { return Ok(()); };
+    let choice = parse_menu_index(&choice, guests.len());
 
-    let (guest_username, active) = match choice.and_then(|n| guests.get(n - 1)) {
+    let (guest_username, active) = match choice.and_then(|i| guests.get(i)) {
         Some((uname, active, _, _)) => (uname.clone(), *active),
         None => {
             println!("Invalid selection.");
@@ -479,7 +499,7 @@ pub fn disable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
     println!("\nPlease verify your identity to disable '{}':", guest_username);
     print!("Enter your password: ");
     io::stdout().flush().ok();
-    let pw_in = Zeroizing::new(read_password()?);
+    let pw_in = auth::read_secret()?;
     let password = pw_in.trim_end_matches(['\r', '\n']);
 
     let stored_hash_opt: Option<String> = conn
@@ -506,7 +526,7 @@ pub fn disable_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
     // Disable the guest
     let affected = conn.execute(
         "UPDATE users
-         SET is_active = 0, updated_at = datetime('now')
+         SET is_active = 0, bulk_disabled = 0, updated_at = datetime('now')
          WHERE username = ?1 AND homeowner_id = ?2",
         params![guest_username, homeowner_id],
     )?;
@@ -568,7 +588,7 @@ pub fn delete_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
                       FROM technician_jobs
                      WHERE technician_username = ?1 COLLATE NOCASE
                        AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-                       AND datetime(updated_at, printf('+%d minutes', access_minutes)) > datetime('now')
+                       AND grant_expires > datetime('now')
                      ORDER BY updated_at DESC
                      LIMIT 1
                     "#,
@@ -648,11 +668,10 @@ pub fn delete_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
     // Select which guest to delete
     print!("\nEnter the number of the guest to delete: ");
     io::stdout().flush().ok();
-    let mut choice = String::new();
-    io::stdin().read_line(&mut choice).ok();
-    let choice = choice.trim().parse::<usize>().ok();
+    let Some(choice) = prompt_input() else { return Ok(()); };
+    let choice = parse_menu_index(&choice, guests.len());
 
-    let (guest_username, _active) = match choice.and_then(|n| guests.get(n - 1)) {
+    let (guest_username, _active) = match choice.and_then(|i| guests.get(i)) {
         Some((uname, active, _, _)) => (uname.clone(), *active),
         None => {
             println!("Invalid selection.");
@@ -667,7 +686,7 @@ pub fn delete_guest(conn: &mut Connection, acting_username: &str) -> Result<()>
     );
     print!("Enter your password: ");
     io::stdout().flush().ok();
-    let pw_in = Zeroizing::new(read_password()?);
+    let pw_in = auth::read_secret()?;
     let password = pw_in.trim_end_matches(['\r', '\n']);
 
     // Fetch and verify password for the ACTING user
@@ -762,7 +781,7 @@ pub fn reset_guest_pin(conn: &mut Connection, acting_username: &str) -> Result<(
                       FROM technician_jobs
                      WHERE technician_username = ?1 COLLATE NOCASE
                        AND status IN ('ACCESS_GRANTED','TECH_ACCESS')
-                       AND datetime(updated_at, printf('+%d minutes', access_minutes)) > datetime('now')
+                       AND grant_expires > datetime('now')
                      ORDER BY updated_at DESC
                      LIMIT 1
                     "#,
@@ -854,11 +873,10 @@ pub fn reset_guest_pin(conn: &mut Connection, acting_username: &str) -> Result<(
     // Pick guest
     print!("\nEnter the number of the guest to reset PIN: ");
     io::stdout().flush().ok();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let choice = input.trim().parse::<usize>().ok();
+    let Some(input) = prompt_input() else { return Ok(()); };
+    let choice = parse_menu_index(&input, guests.len());
 
-    let (guest_username, active) = match choice.and_then(|n| guests.get(n - 1)) {
+    let (guest_username, active) = match choice.and_then(|i| guests.get(i)) {
         Some((uname, active, _, _)) => (uname.clone(), *active),
         None => {
             println!("Invalid selection.");
@@ -878,7 +896,7 @@ pub fn reset_guest_pin(conn: &mut Connection, acting_username: &str) -> Result<(
     );
     print!("Enter your password: ");
     io::stdout().flush().ok();
-    let pw_in = Zeroizing::new(read_password()?);
+    let pw_in = auth::read_secret()?;
     let password = pw_in.trim_end_matches(['\r', '\n']);
 
     let stored_hash_opt: Option<String> = conn
@@ -905,7 +923,7 @@ pub fn reset_guest_pin(conn: &mut Connection, acting_username: &str) -> Result<(
     // Prompt for new PIN
     print!("\nEnter new PIN for '{}': ", guest_username);
     io::stdout().flush().ok();
-    let new_pin_in: Zeroizing<String> = Zeroizing::new(read_password()?);
+    let new_pin_in: Zeroizing<String> = auth::read_secret()?;
     let new_pin_trimmed: &str = new_pin_in.trim_end_matches(['\r', '\n']);
 
     if new_pin_trimmed.len() < 6 {
@@ -967,7 +985,7 @@ pub fn manage_guests_menu(conn: &mut Connection, acting_username: &str, acting_r
         print!("Enter your technician password: ");
         io::stdout().flush().ok();
 
-        let pw_in: Zeroizing<String> = Zeroizing::new(read_password()?);
+        let pw_in: Zeroizing<String> = auth::read_secret()?;
         let pw_trimmed: &str = pw_in.trim_end_matches(['\r', '\n']);
 
         let stored_hash_opt: Option<String> = conn
@@ -999,12 +1017,10 @@ pub fn manage_guests_menu(conn: &mut Connection, acting_username: &str, acting_r
 
         ui::manage_guest_menu();
         
-        let mut choice = String::new();
-        let n = std::io::stdin().read_line(&mut choice)?;
-        if n == 0 {
+        let Some(choice) = prompt_input() else {
             println!("Input closed. Returning to Menu...");
             break;
-        }
+        };
 
         match choice.trim() {
             "1" => {auth::register_user(conn, Some((acting_username, acting_role)))?},
@@ -1022,12 +1038,9 @@ pub fn manage_guests_menu(conn: &mut Connection, acting_username: &str, acting_r
                 print!("Select an option [1-2]: ");
                 io::stdout().flush().ok();
 
-                let mut sub_choice = String::new();
-                let m = std::io::stdin().read_line(&mut sub_choice).unwrap_or(0);
-                if m == 0 {
-                    println!("Input closed. Returning...");
-                } else {
-                    match sub_choice.trim() {
+                match prompt_input() {
+                    None => println!("Input closed. Returning..."),
+                    Some(sub_choice) => match sub_choice.trim() {
                         "1" => {
                             if let Err(e) = enable_guest(conn, acting_username) {
                                 println!("Error: {}", e);
@@ -1052,15 +1065,131 @@ pub fn manage_guests_menu(conn: &mut Connection, acting_username: &str, acting_r
                 println!("Returning to Menu...");
                 break;
             }
-            _ => println!("Invalid choice, please enter 1–4."),
+            "T" | "t" if acting_role == "homeowner" => {
+                println!("\n======= Guest Temperature Limits =======");
+                if let Err(e) = set_guest_temp_bounds_flow(conn, homeowner_username) {
+                    println!("Error: {}", e);
+                }
+            }
+            "L" | "l" if acting_role == "homeowner" => {
+                let locked = !db::guest_hvac_locked(conn, homeowner_username)?;
+                match db::set_guest_hvac_locked(conn, homeowner_username, locked) {
+                    Ok(()) if locked => println!("🔒 Guests can no longer change the HVAC. Their attempts are logged."),
+                    Ok(()) => println!("🔓 Guests can change the HVAC again."),
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            "N" | "n" if acting_role == "homeowner" => {
+                let enabled = !db::guest_login_notices_enabled(conn, homeowner_username)?;
+                match db::set_guest_login_notices(conn, homeowner_username, enabled) {
+                    Ok(()) if enabled => println!("🔔 You will be told when your guests log in."),
+                    Ok(()) => println!("🔕 Guest login notices are off."),
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            _ => println!("Invalid choice, please enter 1–6."),
         }
 
-        print!("\nPress ENTER to continue...");
-        io::stdout().flush().ok();
-        let mut dummy = String::new();
-        let _ = std::io::stdin().read_line(&mut dummy);
         println!();
+        wait_for_enter();
     }
 
     Ok(())
 }
+
+// Homeowner caps the target temperature their guests may choose; blank keeps, '-' removes
+fn set_guest_temp_bounds_flow(conn: &Connection, homeowner_username: &str) -> Result<()> {
+    match db::guest_temp_bounds(conn, homeowner_username)? {
+        Some((lo, hi)) => println!("Guests may currently set {:.1}–{:.1}°C.", lo, hi),
+        None => println!("Guests currently have no extra temperature limits."),
+    }
+    print!("Enter min,max in °C (e.g. 20,24), '-' to remove, blank to keep: ");
+    io::stdout().flush().ok();
+    let Some(input) = prompt_input() else { return Ok(()); };
+    let bounds = match input.trim() {
+        "" => return Ok(()),
+        "-" => None,
+        v => {
+            let parsed = v
+                .split_once(',')
+                .and_then(|(lo, hi)| Some((lo.trim().parse::<f32>().ok()?, hi.trim().parse::<f32>().ok()?)));
+            let Some(bounds) = parsed else {
+                println!("Please enter two numbers separated by a comma.");
+                return Ok(());
+            };
+            Some(bounds)
+        }
+    };
+    db::set_guest_temp_bounds(conn, homeowner_username, bounds)?;
+    match bounds {
+        Some((lo, hi)) => println!("✅ Guests can now set {:.1}–{:.1}°C.", lo, hi),
+        None => println!("✅ Guest temperature limits removed."),
+    }
+    Ok(())
+}
+
+// ======================================================
+//                 GUEST NOTES FOR HOMEOWNER
+// ======================================================
+
+// Guest leaves a short note that only their own homeowner can read.
+pub fn leave_note_for_homeowner(conn: &Connection, guest_username: &str) -> Result<()> {
+    println!("\n======= Leave a Note for Homeowner =======");
+    println!("Write a short note (max {} characters), or press Enter to cancel.", db::MAX_GUEST_NOTE_LEN);
+    print!("Note: ");
+    io::stdout().flush().ok();
+
+    let Some(input) = prompt_input() else {
+        println!("Input closed. Returning...");
+        return Ok(());
+    };
+    if input.trim().is_empty() {
+        println!("No note entered. Returning to menu.");
+        return Ok(());
+    }
+
+    match db::submit_guest_note(conn, guest_username, &input) {
+        Ok(_) => println!("✅ Your note has been sent to the homeowner."),
+        Err(e) => println!("❌ Could not send note: {}", e),
+    }
+    Ok(())
+}
+
+// Homeowner reads unread guest notes and can mark them as read.
+pub fn view_guest_notes(conn: &Connection, homeowner_username: &str) -> Result<()> {
+    let notes = db::list_unread_guest_notes(conn, homeowner_username)?;
+
+    let logins = db::unread_guest_login_notices(conn, homeowner_username)?;
+    if let Some((last_id, _, _)) = logins.last() {
+        println!("\n======= Guest Logins =======");
+        for (_, at, guest) in &logins {
+            println!("[{}] {} logged in", at, guest);
+        }
+        db::mark_guest_login_notices_read(conn, homeowner_username, *last_id)?;
+    }
+
+    let blocked = db::blocked_guest_hvac_attempts(conn, homeowner_username, 10)?;
+    if !blocked.is_empty() {
+        println!("\n======= Blocked Guest HVAC Attempts =======");
+        for (at, guest, desc) in &blocked {
+            println!("[{}] {} — {}", at, guest, desc);
+        }
+    }
+
+    println!("\n======= Guest Notes =======");
+    if notes.is_empty() {
+        println!("No unread notes from your guests.");
+        return Ok(());
+    }
+
+    for n in &notes {
+        println!("#{} [{}] {} — {}", n.id, db::display_time(&n.created_at), n.guest_username, n.note);
+    }
+
+    println!();
+    if confirm(&format!("Mark these {} note(s) as read?", notes.len()), false) {
+        let cleared = db::mark_guest_notes_read(conn, homeowner_username)?;
+        println!("{} note(s) marked as read.", cleared);
+    }
+    Ok(())
+}
@@ -2,11 +2,8 @@ use rusqlite::Connection;
 use chrono::Local;
 use crate::logger;
 use crate::senser;
-
-// Convert Celsius to Fahrenheit
-fn celsius_to_fahrenheit(celsius: f32) -> f32 {
-    (celsius * 9.0 / 5.0) + 32.0
-}
+use crate::units::{self, celsius_to_fahrenheit, TempUnit};
+pub use crate::db::{DisplayMode, Presence};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HVACMode {
@@ -22,6 +19,12 @@ pub struct HVACSystem {
     pub target_temperature: f32,
     pub light_status: String,
     pub current_profile: Option<String>,
+    // Who changes made through this handle are logged under; "system" for automatic changes
+    pub actor: String,
+    // Auto's comfort band at home (°C either side of target), from system settings
+    pub comfort_band: f32,
+    // (min, max) °C a guest may set, from their homeowner's settings; None means unrestricted
+    pub guest_bounds: Option<(f32, f32)>,
 }
 
 // Temperature limits constants
@@ -37,6 +40,103 @@ pub const COOLING_MAX: f32 = 22.0;
 pub const AUTO_MIN: f32 = 18.0;
 pub const AUTO_MAX: f32 = 28.0;
 
+// How far Auto lets the room drift from target before heating or cooling.
+// HOME_DEADBAND_C is the default comfort band; Away never holds tighter than AWAY_DEADBAND_C.
+pub const HOME_DEADBAND_C: f32 = 0.5;
+pub const AWAY_DEADBAND_C: f32 = 2.0;
+
+pub fn auto_deadband(presence: Presence) -> f32 {
+    deadband_for(HOME_DEADBAND_C, presence)
+}
+
+// Deadband for a configured comfort band
+pub fn deadband_for(comfort_band: f32, presence: Presence) -> f32 {
+    match presence {
+        Presence::Home => comfort_band,
+        Presence::Away => comfort_band.max(AWAY_DEADBAND_C),
+    }
+}
+
+// Safety interlock: heater and AC are never driven together. A contradictory
+// request drives neither rather than guessing which one was meant.
+pub fn interlock(heat: bool, cool: bool) -> (bool, bool) {
+    if heat && cool { (false, false) } else { (heat, cool) }
+}
+
+// Auto's (heat, cool) demand: act only once the reading leaves target ± band
+pub fn auto_demand(current_c: f32, target_c: f32, band: f32) -> (bool, bool) {
+    interlock(current_c < target_c - band, current_c > target_c + band)
+}
+
+// What Auto is doing at a reading; Holding is inside the band with heater and AC both off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoAction {
+    Heating,
+    Cooling,
+    Holding,
+}
+
+impl AutoAction {
+    pub fn from_demand((heat, cool): (bool, bool)) -> Self {
+        match (heat, cool) {
+            (true, false) => AutoAction::Heating,
+            (false, true) => AutoAction::Cooling,
+            _ => AutoAction::Holding,
+        }
+    }
+}
+
+pub const AUTO_HOLDING_STATUS: &str = "Holding target (within the comfort band)";
+
+// Turning the system Off below this outdoor temperature risks frozen pipes
+pub const DEFAULT_FREEZE_THRESHOLD_C: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OffSwitchCheck {
+    Clear,           // outdoor reading at or above the threshold
+    FreezeRisk(f32), // outdoor °C below the threshold: warn and confirm
+    NoWeather,       // no outdoor reading to judge by: plain confirmation
+}
+
+pub fn off_switch_check(outdoor_c: Option<f32>, threshold_c: f32) -> OffSwitchCheck {
+    match outdoor_c {
+        Some(t) if t < threshold_c => OffSwitchCheck::FreezeRisk(t),
+        Some(_) => OffSwitchCheck::Clear,
+        None => OffSwitchCheck::NoWeather,
+    }
+}
+
+// Humidity may drift this far (percentage points) from target before we suggest anything
+pub const HUMIDITY_BAND_PCT: f32 = 5.0;
+
+// Advisory only: there is no humidifier to drive, so this just says what would help
+pub fn humidity_suggestion(reading_pct: f32, target_pct: f32) -> Option<String> {
+    if reading_pct > target_pct + HUMIDITY_BAND_PCT {
+        Some(format!(
+            "Too humid: {:.0}% vs target {:.0}% — consider running a dehumidifier.",
+            reading_pct, target_pct
+        ))
+    } else if reading_pct < target_pct - HUMIDITY_BAND_PCT {
+        Some(format!(
+            "Too dry: {:.0}% vs target {:.0}% — consider running a humidifier.",
+            reading_pct, target_pct
+        ))
+    } else {
+        None
+    }
+}
+
+// System humidity setpoint, as last set by an applied profile
+pub fn current_target_humidity(conn: &Connection) -> Option<f32> {
+    crate::db::hvac_target_humidity(conn).ok()?
+}
+
+// (target, suggestion) for the active profile, if it has a humidity target
+pub fn current_humidity_status(conn: &Connection, reading_pct: f32) -> Option<(f32, Option<String>)> {
+    let target = current_target_humidity(conn)?;
+    Some((target, humidity_suggestion(reading_pct, target)))
+}
+
 impl HVACMode {
     // Get the temperature range for a specific mode
     pub fn temperature_range(&self) -> (f32, f32) {
@@ -53,6 +153,24 @@ impl HVACMode {
         let (min, max) = self.temperature_range();
         temp >= min && temp <= max
     }
+
+    // Pull a temperature into this mode's valid range
+    pub fn clamp_temperature(&self, temp: f32) -> f32 {
+        let (min, max) = self.temperature_range();
+        temp.clamp(min, max)
+    }
+
+    // Parse the mode names stored in profiles / hvac_state
+    pub fn from_name(name: &str) -> Option<HVACMode> {
+        match name {
+            "Off" => Some(HVACMode::Off),
+            "Heating" => Some(HVACMode::Heating),
+            "Cooling" => Some(HVACMode::Cooling),
+            "FanOnly" => Some(HVACMode::FanOnly),
+            "Auto" => Some(HVACMode::Auto),
+            _ => None,
+        }
+    }
 }
 
 impl HVACSystem {
@@ -73,6 +191,9 @@ impl HVACSystem {
                     target_temperature: temp,
                     light_status: light,
                     current_profile: profile,
+                    actor: "system".to_string(),
+                    comfort_band: crate::db::comfort_band(conn).unwrap_or(HOME_DEADBAND_C),
+                    guest_bounds: None,
                 }
             }
             Err(_) => {
@@ -82,11 +203,24 @@ impl HVACSystem {
                     target_temperature: 22.0,
                     light_status: "OFF".to_string(),
                     current_profile: None,
+                    actor: "system".to_string(),
+                    comfort_band: HOME_DEADBAND_C,
+                    guest_bounds: None,
                 }
             }
         }
     }
     
+    // Same as new, but changes are logged under the signed-in user instead of "system",
+    // and a guest picks up their homeowner's temperature limits
+    pub fn for_user(conn: &Connection, username: &str) -> Self {
+        Self {
+            actor: username.to_string(),
+            guest_bounds: crate::db::guest_temp_bounds_for(conn, username).ok().flatten(),
+            ..Self::new(conn)
+        }
+    }
+
     pub fn set_mode(&mut self, conn: &Connection, mode: HVACMode) {
         self.mode = mode;
         
@@ -102,7 +236,7 @@ impl HVACSystem {
         
         let _ = logger::log_event(
             conn,
-            "system",
+            &self.actor,
             None,
             "HVAC",
             Some(&format!("Mode set to {:?}", mode)),
@@ -124,14 +258,49 @@ impl HVACSystem {
         
         let _ = logger::log_event(
             conn,
-            "system",
+            &self.actor,
             None,
             "HVAC",
             Some(&format!("Light status set to {}", light_status)),
         );
     }
 
+    // A profile's humidity target (or None) becomes the setpoint, unless that is turned off
+    pub fn apply_profile_humidity(&self, conn: &Connection, target: Option<f32>) {
+        if crate::db::profile_sets_humidity(conn).unwrap_or(true) {
+            let _ = crate::db::set_hvac_target_humidity(conn, target);
+        }
+    }
+
+    // Mode and light off in one step, then the all-off event and a first indoor snapshot
+    pub fn emergency_all_off(&mut self, conn: &Connection) {
+        self.set_mode(conn, HVACMode::Off);
+        self.set_light_status(conn, "OFF");
+        let indoor = senser::get_indoor_temperature().ok();
+        let _ = crate::db::record_emergency_all_off(conn, &self.actor, indoor);
+    }
+
+    // Guests stay inside the limits their homeowner set; a clamp is announced and logged
+    pub fn clamp_for_guest(&self, conn: &Connection, temperature: f32) -> f32 {
+        match self.guest_bounds {
+            Some((lo, hi)) if !(lo..=hi).contains(&temperature) => {
+                let clamped = temperature.clamp(lo, hi);
+                println!("⚠️  Guests can set {:.1}–{:.1}°C here; using {:.1}°C instead of {:.1}°C.", lo, hi, clamped, temperature);
+                let _ = logger::log_event(
+                    conn,
+                    &self.actor,
+                    None,
+                    "HVAC",
+                    Some(&format!("Guest temperature {:.1}°C clamped to {:.1}°C (limits {:.1}–{:.1}°C)", temperature, clamped, lo, hi)),
+                );
+                clamped
+            }
+            _ => temperature,
+        }
+    }
+
     pub fn set_target_temperature(&mut self, conn: &Connection, temperature: f32) {
+        let temperature = self.clamp_for_guest(conn, temperature);
         // Validate temperature limits
         if temperature < MIN_TEMPERATURE {
             println!("❌ Temperature too low! Minimum allowed: {:.1}°C", MIN_TEMPERATURE);
@@ -139,7 +308,7 @@ impl HVACSystem {
             self.target_temperature = MIN_TEMPERATURE;
             let _ = logger::log_event(
                 conn,
-                "system",
+                &self.actor,
                 None,
                 "HVAC",
                 Some(&format!("Temperature below limit ({:.1}°C), set to minimum {:.1}°C", temperature, MIN_TEMPERATURE)),
@@ -150,7 +319,7 @@ impl HVACSystem {
             self.target_temperature = MAX_TEMPERATURE;
             let _ = logger::log_event(
                 conn,
-                "system",
+                &self.actor,
                 None,
                 "HVAC",
                 Some(&format!("Temperature above limit ({:.1}°C), set to maximum {:.1}°C", temperature, MAX_TEMPERATURE)),
@@ -159,7 +328,7 @@ impl HVACSystem {
             self.target_temperature = temperature;
             let _ = logger::log_event(
                 conn,
-                "system",
+                &self.actor,
                 None,
                 "HVAC",
                 Some(&format!("Target temperature set to {:.1}°C", temperature)),
@@ -188,9 +357,30 @@ impl HVACSystem {
         let _ = crate::db::save_hvac_state(conn, mode_str, self.target_temperature, &self.light_status, self.current_profile.as_deref());
     }
 
+    // (heat, cool) demand at this indoor reading; Auto holds off inside the presence deadband
+    pub fn decide(&self, current_temp: f32, presence: Presence) -> (bool, bool) {
+        let band = deadband_for(self.comfort_band, presence);
+        let (heat, cool) = match self.mode {
+            HVACMode::Heating => (current_temp < self.target_temperature, false),
+            HVACMode::Cooling => (false, current_temp > self.target_temperature),
+            HVACMode::Auto => auto_demand(current_temp, self.target_temperature, band),
+            HVACMode::FanOnly | HVACMode::Off => (false, false),
+        };
+        interlock(heat, cool)
+    }
+
     pub fn update(&self, conn: &Connection) {
+        self.update_with_display(conn, DisplayMode::Verbose);
+    }
+
+    // Same as update(), rendered in the caller's preferred display mode
+    pub fn update_with_display(&self, conn: &Connection, display: DisplayMode) {
+        let humidity = senser::get_indoor_humidity().ok();
         let current_temp = match senser::get_indoor_temperature() {
-            Ok(temp) => temp,
+            Ok(temp) => {
+                let _ = crate::db::record_sensor_reading(conn, temp, humidity);
+                temp
+            }
             Err(_) => {
                 println!("⚠️  Sensor error, defaulting to 22.0°C.");
                 22.0
@@ -200,226 +390,77 @@ impl HVACSystem {
         let now = Local::now();
         let time_str = now.format("%b %d, %Y %I:%M %p %Z").to_string();
 
-        let current_temp_f = celsius_to_fahrenheit(current_temp);
-        let target_temp_f = celsius_to_fahrenheit(self.target_temperature);
+        let presence = crate::db::get_presence(conn).unwrap_or_default();
+        let (heat_demand, cool_demand) = self.decide(current_temp, presence);
 
-        println!("🌈✨=============================================✨🌈");
-        match self.mode {
-            HVACMode::Heating if current_temp < self.target_temperature => {
-                println!("🔥  HVAC Status: HEATING");
-                println!();
-                println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                println!();
-                println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
-                println!();
-                println!("⚙️  Mode: Heating");
-                println!();
-                if let Some(profile) = &self.current_profile {
-                    println!("�  Last applied Profile: {}", profile);
-                    println!();
-                }
-                println!("�🔥  Heater: ON");
-                println!();
-                println!("❄️  AC: OFF");
-                println!();
-                println!("💡  Light: {}", self.light_status);
-                println!();
-                println!("📊  Status: Warming up your space!");
-                println!();
-                println!("🕒  Time: {}", time_str);
-                let _ = logger::log_event(conn, "system", None, "HVAC", Some("Heating activated"));
+        // (icon, state, mode label, target shown, heater, ac, fan, status, log message)
+        let (icon, state, mode_label, show_target, heater_on, ac_on, fan, status, log_msg) = match self.mode {
+            HVACMode::Heating if heat_demand => {
+                ("🔥", "HEATING", "Heating", true, true, false, None, "Warming up your space!", Some("Heating activated"))
             }
             HVACMode::Heating => {
-                println!("🔥  HVAC Status: HEATING");
-                println!();
-                println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                println!();
-                println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
-                println!();
-                println!("⚙️  Mode: Heating");
-                println!();
-                if let Some(profile) = &self.current_profile {
-                    println!("�  Last applied Profile: {}", profile);
-                    println!();
-                }
-                println!("�🔥  Heater: ON");
-                println!();
-                println!("❄️  AC: OFF");
-                println!();
-                println!("💡  Light: {}", self.light_status);
-                println!();
-                println!("📊  Status: Temperature reached!");
-                println!();
-                println!("🕒  Time: {}", time_str);
+                ("🔥", "HEATING", "Heating", true, true, false, None, "Temperature reached!", None)
             }
-            HVACMode::Cooling if current_temp > self.target_temperature => {
-                println!("❄️  HVAC Status: COOLING");
-                println!();
-                println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                println!();
-                println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
-                println!();
-                println!("⚙️  Mode: Cooling");
-                println!();
-                if let Some(profile) = &self.current_profile {
-                    println!("�  Profile: {}", profile);
-                    println!();
-                }
-                println!("�🔥  Heater: OFF");
-                println!();
-                println!("❄️  AC: ON");
-                println!();
-                println!("💡  Light: {}", self.light_status);
-                println!();
-                println!("📊  Status: AC cooling down your space!");
-                println!();
-                println!("🕒  Time: {}", time_str);
-                let _ = logger::log_event(conn, "system", None, "HVAC", Some("Cooling activated"));
+            HVACMode::Cooling if cool_demand => {
+                ("❄️", "COOLING", "Cooling", true, false, true, None, "AC cooling down your space!", Some("Cooling activated"))
             }
             HVACMode::Cooling => {
-                println!("❄️  HVAC Status: COOLING");
-                println!();
-                println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                println!();
-                println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
-                println!();
-                println!("⚙️  Mode: Cooling");
-                println!();
-                if let Some(profile) = &self.current_profile {
-                    println!("📋  Last applied Profile: {}", profile);
-                    println!();
-                }
-                println!("🔥  Heater: OFF");
-                println!();
-                println!("❄️  AC: ON");
-                println!();
-                println!("💡  Light: {}", self.light_status);
-                println!();
-                println!("📊  Status: Temperature reached!");
-                println!();
-                println!("🕒  Time: {}", time_str);
+                ("❄️", "COOLING", "Cooling", true, false, true, None, "Temperature reached!", None)
             }
             HVACMode::FanOnly => {
-                println!("💨  HVAC Status: FAN ONLY");
-                println!();
-                println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                println!();
-                println!("⚙️  Mode: Fan Only");
-                println!();
-                if let Some(profile) = &self.current_profile {
-                    println!("�  Last applied Profile: {}", profile);
-                    println!();
-                }
-                println!("�🔥  Heater: OFF");
-                println!();
-                println!("❄️  AC: OFF");
-                println!();
-                println!("💡  Light: {}", self.light_status);
-                println!();
-                println!("💨  Fan: ON");
-                println!();
-                println!("📊  Status: Circulating fresh air!");
-                println!();
-                println!("🕒  Time: {}", time_str);
-                let _ = logger::log_event(conn, "system", None, "HVAC", Some("Fan mode active"));
+                ("💨", "FAN ONLY", "Fan Only", false, false, false, Some("ON"), "Circulating fresh air!", Some("Fan mode active"))
             }
-            HVACMode::Auto => {
-                if current_temp < self.target_temperature - 0.5 {
-                    println!("🤖  HVAC Status: AUTO MODE");
-                    println!();
-                    println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                    println!();
-                    println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
-                    println!();
-                    println!("⚙️  Mode: Auto");
-                    println!();
-                    if let Some(profile) = &self.current_profile {
-                        println!("�  Last applied Profile: {}", profile);
-                        println!();
-                    }
-                    println!("�🔥  Heater: ON");
-                    println!();
-                    println!("❄️  AC: OFF");
-                    println!();
-                    println!("💡  Light: {}", self.light_status);
-                    println!();
-                    println!("📊  Status: Heating to reach target");
-                    println!();
-                    println!("🕒  Time: {}", time_str);
-                    let _ = logger::log_event(conn, "system", None, "HVAC", Some("Auto heating started"));
-                } else if current_temp > self.target_temperature + 0.5 {
-                    println!("🤖  HVAC Status: AUTO MODE");
-                    println!();
-                    println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                    println!();
-                    println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
-                    println!();
-                    println!("⚙️  Mode: Auto");
-                    println!();
-                    if let Some(profile) = &self.current_profile {
-                        println!("�  Last applied Profile: {}", profile);
-                        println!();
-                    }
-                    println!("�🔥  Heater: OFF");
-                    println!();
-                    println!("❄️  AC: ON");
-                    println!();
-                    println!("💡  Light: {}", self.light_status);
-                    println!();
-                    println!("📊  Status: Cooling to reach target");
-                    println!();
-                    println!("🕒  Time: {}", time_str);
-                    let _ = logger::log_event(conn, "system", None, "HVAC", Some("Auto cooling started"));
-                } else {
-                    println!("🤖  HVAC Status: AUTO MODE");
-                    println!();
-                    println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                    println!();
-                    println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
-                    println!();
-                    println!("⚙️  Mode: Auto");
-                    println!();
-                    if let Some(profile) = &self.current_profile {
-                        println!("📋  Last applied Profile: {}", profile);
-                        println!();
-                    }
-                    println!("🔥  Heater: OFF");
-                    println!();
-                    println!("❄️  AC: OFF");
-                    println!();
-                    println!("💡  Light: {}", self.light_status);
-                    println!();
-                    println!("📊  Status: Maintaining comfort (Perfect temp!)");
-                    println!();
-                    println!("🕒  Time: {}", time_str);
+            HVACMode::Auto => match AutoAction::from_demand((heat_demand, cool_demand)) {
+                AutoAction::Heating => {
+                    ("🤖", "AUTO MODE", "Auto", true, true, false, None, "Heating to reach target", Some("Auto heating started"))
                 }
-            }
-            HVACMode::Off => {
-                println!("⭕  HVAC Status: OFF");
-                println!();
-                println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
-                println!();
-                println!("⚙️  Mode: Off");
-                println!();
-                if let Some(profile) = &self.current_profile {
-                    println!("�  Last applied Profile: {}", profile);
-                    println!();
+                AutoAction::Cooling => {
+                    ("🤖", "AUTO MODE", "Auto", true, false, true, None, "Cooling to reach target", Some("Auto cooling started"))
+                }
+                // Idle inside the band: nothing runs, same as the apply-time view
+                AutoAction::Holding => {
+                    ("🤖", "AUTO MODE", "Auto", true, false, false, None, AUTO_HOLDING_STATUS, None)
                 }
-                println!("�🔥  Heater: OFF");
-                println!();
-                println!("❄️  AC: OFF");
-                println!();
-                println!("💡  Light: {}", self.light_status);
-                println!();
-                println!("💨  Fan: OFF");
-                println!();
-                println!("📊  Status: No climate control");
-                println!();
-                println!("🕒  Time: {}", time_str);
-                let _ = logger::log_event(conn, "system", None, "HVAC", Some("System off"));
+            },
+            HVACMode::Off => {
+                ("⭕", "OFF", "Off", false, false, false, Some("OFF"), "No climate control", Some("System off"))
+            }
+        };
+
+        // A running fan boost is shown in place of the mode's own fan state
+        let boost = crate::db::active_fan_override(conn).ok().flatten().map(|b| {
+            format!("{} (boost until {})", b.fan_speed, crate::db::display_time(&b.expires_at))
+        });
+        let fan = boost.as_deref().or(fan);
+
+        let view = StatusView {
+            icon,
+            state,
+            mode_label,
+            current_c: current_temp,
+            target_c: if show_target { Some(self.target_temperature) } else { None },
+            profile: self.current_profile.as_deref(),
+            heater_on,
+            ac_on,
+            light: &self.light_status,
+            fan,
+            status,
+            time: &time_str,
+        };
+        println!("{}", render_hvac_status(&view, display));
+
+        if let Some(humidity) = humidity
+            && let Some((target, suggestion)) = current_humidity_status(conn, humidity)
+        {
+            println!("💧  Humidity: {:.0}% (target {:.0}%)", humidity, target);
+            if let Some(tip) = suggestion {
+                println!("💡  {}", tip);
             }
         }
-        println!("🌈✨=============================================✨🌈");
+
+        if let Some(msg) = log_msg {
+            let _ = logger::log_event(conn, "system", None, "HVAC", Some(msg));
+        }
     }
 
     pub fn diagnostics(&self, conn: &Connection) {
@@ -428,8 +469,6 @@ impl HVACSystem {
             Err(_) => 22.0,
         };
         
-        let current_temp_f = celsius_to_fahrenheit(current_temp);
-        let target_temp_f = celsius_to_fahrenheit(self.target_temperature);
         let now = Local::now();
         let time_str = now.format("%b %d, %Y %I:%M %p %Z").to_string();
         
@@ -438,9 +477,9 @@ impl HVACSystem {
         println!();
         println!("⚙️  Mode: {:?}", self.mode);
         println!();
-        println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", self.target_temperature, target_temp_f);
+        println!("🎯  Target Temperature: {}", units::format_temp_dual(self.target_temperature, TempUnit::Celsius));
         println!();
-        println!("🌡️  Current Temperature: {:.1}°C / {:.1}°F", current_temp, current_temp_f);
+        println!("🌡️  Current Temperature: {}", units::format_temp_dual(current_temp, TempUnit::Celsius));
         println!();
         
         let (min_temp, max_temp) = self.mode.temperature_range();
@@ -454,3 +493,87 @@ impl HVACSystem {
         let _ = logger::log_event(conn, "system", None, "HVAC", Some("Diagnostics executed"));
     }
 }
+
+// ======================================================
+//                  HVAC STATUS RENDERING
+// ======================================================
+
+// Everything needed to print one HVAC status snapshot
+pub struct StatusView<'a> {
+    pub icon: &'a str,
+    pub state: &'a str,
+    pub mode_label: &'a str,
+    pub current_c: f32,
+    pub target_c: Option<f32>,
+    pub profile: Option<&'a str>,
+    pub heater_on: bool,
+    pub ac_on: bool,
+    pub light: &'a str,
+    pub fan: Option<&'a str>,
+    pub status: &'a str,
+    pub time: &'a str,
+}
+
+// Verbose keeps the original banner layout; compact fits on three lines
+pub fn render_hvac_status(view: &StatusView, display: DisplayMode) -> String {
+    let on_off = |b: bool| if b { "ON" } else { "OFF" };
+    let current = units::format_temp_dual(view.current_c, TempUnit::Celsius);
+
+    match display {
+        DisplayMode::Compact => {
+            let mut line1 = format!(
+                "{} {} | Now {}",
+                view.icon, view.state, current
+            );
+            if let Some(t) = view.target_c {
+                line1.push_str(&format!(" | Target {}", units::format_temp_dual(t, TempUnit::Celsius)));
+            }
+            if let Some(p) = view.profile {
+                line1.push_str(&format!(" | Profile: {}", p));
+            }
+            let mut line2 = format!(
+                "🔥 Heater: {} | ❄️ AC: {} | 💡 Light: {}",
+                on_off(view.heater_on), on_off(view.ac_on), view.light
+            );
+            if let Some(f) = view.fan {
+                line2.push_str(&format!(" | 💨 Fan: {}", f));
+            }
+            let line3 = format!("📊 {} | 🕒 {}", view.status, view.time);
+            format!("{}\n{}\n{}", line1, line2, line3)
+        }
+        DisplayMode::Verbose => {
+            let mut lines: Vec<String> = vec![
+                "🌈✨=============================================✨🌈".to_string(),
+                format!("{}  HVAC Status: {}", view.icon, view.state),
+                String::new(),
+                format!("🌡️  Current Temperature: {}", current),
+                String::new(),
+            ];
+            if let Some(t) = view.target_c {
+                lines.push(format!("🎯  Target Temperature: {}", units::format_temp_dual(t, TempUnit::Celsius)));
+                lines.push(String::new());
+            }
+            lines.push(format!("⚙️  Mode: {}", view.mode_label));
+            lines.push(String::new());
+            if let Some(p) = view.profile {
+                lines.push(format!("📋  Last applied Profile: {}", p));
+                lines.push(String::new());
+            }
+            lines.push(format!("🔥  Heater: {}", on_off(view.heater_on)));
+            lines.push(String::new());
+            lines.push(format!("❄️  AC: {}", on_off(view.ac_on)));
+            lines.push(String::new());
+            lines.push(format!("💡  Light: {}", view.light));
+            lines.push(String::new());
+            if let Some(f) = view.fan {
+                lines.push(format!("💨  Fan: {}", f));
+                lines.push(String::new());
+            }
+            lines.push(format!("📊  Status: {}", view.status));
+            lines.push(String::new());
+            lines.push(format!("🕒  Time: {}", view.time));
+            lines.push("🌈✨=============================================✨🌈".to_string());
+            lines.join("\n")
+        }
+    }
+}
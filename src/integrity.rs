@@ -0,0 +1,98 @@
+// ======================================================
+//                  SOURCE INTEGRITY
+// ======================================================
+// INTEGRITY.sha256 lists "<sha256 hex><two spaces><path>" per line. Startup
+// verifies it; --gen-integrity rebuilds it without any external script.
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "INTEGRITY.sha256";
+pub const IGNORE_FILE: &str = ".integrityignore";
+
+// File types covered by the manifest, besides the ignore list itself
+const MANIFEST_EXTENSIONS: &[&str] = &["rs", "toml", "md"];
+
+fn sha256_hex(path: &Path) -> Result<String> {
+    let data = fs::read(path).with_context(|| format!("Read fail: {}", path.display()))?;
+    Ok(hex::encode(Sha256::digest(&data)))
+}
+
+// Paths are written with forward slashes so a manifest made on Windows verifies elsewhere
+fn manifest_name(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+pub fn generate_integrity_manifest(paths: &[PathBuf], out: &Path) -> Result<()> {
+    let mut content = String::new();
+    for path in paths {
+        content.push_str(&format!("{}  {}\n", sha256_hex(path)?, manifest_name(path)));
+    }
+    fs::write(out, content).with_context(|| format!("Write fail: {}", out.display()))?;
+    Ok(())
+}
+
+// (ok, bad) counts; a listed file that is missing counts as bad
+pub fn verify_integrity(manifest: &Path) -> Result<(usize, usize)> {
+    let content = fs::read_to_string(manifest)
+        .with_context(|| format!("Read {} fail", manifest.display()))?;
+    let mut ok = 0usize;
+    let mut bad = 0usize;
+
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() { continue; }
+        let (expected_hash, file) = match line.split_once("  ") {
+            Some(t) => t,
+            None => bail!("{} line {} error format: {}", manifest.display(), idx + 1, line),
+        };
+        match sha256_hex(Path::new(file)) {
+            Ok(got) if got == expected_hash => ok += 1,
+            _ => bad += 1,
+        }
+    }
+    Ok((ok, bad))
+}
+
+// Files under `root` that belong in the manifest, relative to `root` and sorted.
+// Each line of the ignore file is a regex matched against the relative path.
+pub fn project_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let ignore: Vec<Regex> = match fs::read_to_string(root.join(IGNORE_FILE)) {
+        Ok(raw) => raw
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| Regex::new(l).with_context(|| format!("Bad pattern in {IGNORE_FILE}: {l}")))
+            .collect::<Result<_>>()?,
+        Err(_) => Vec::new(),
+    };
+
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(root.join(&dir))? {
+            let entry = entry?;
+            let rel = dir.join(entry.file_name());
+            let is_dir = entry.file_type()?.is_dir();
+            // Directories are matched with a trailing slash, so "^target/" skips target/
+            let name = manifest_name(&rel) + if is_dir { "/" } else { "" };
+            if ignore.iter().any(|re| re.is_match(&name)) {
+                continue;
+            }
+            if is_dir {
+                if !name.starts_with('.') {
+                    pending.push(rel);
+                }
+            } else if name == IGNORE_FILE
+                || rel.extension().and_then(|e| e.to_str()).is_some_and(|e| MANIFEST_EXTENSIONS.contains(&e))
+            {
+                files.push(rel);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
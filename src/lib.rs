@@ -12,3 +12,8 @@ pub mod ui;
 pub mod weather;
 pub mod energy;
 pub mod diagnostic;
+pub mod units;
+pub mod sweeper;
+pub mod integrity;
+pub mod menu_config;
+pub mod clock;
@@ -1,20 +1,125 @@
-use anyhow::{anyhow, Context, Result};
-use chrono::{DateTime, Duration, TimeZone, Utc};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, SecondsFormat, TimeZone, Utc};
 use chrono_tz::America::New_York;
 use rand::Rng;
 use rusqlite::{params, params_from_iter, Connection, OptionalExtension, ToSql};
-use std::{fs::OpenOptions, io::{self, Write}, thread, time::Duration as StdDuration};
+use lazy_static::lazy_static;
+use std::{fs::OpenOptions, io::{self, Write}, path::{Path, PathBuf}, thread, time::Duration as StdDuration};
+use std::sync::{atomic::{AtomicBool, Ordering}, Mutex};
+
+use crate::auth;
+use crate::clock::{Clock, RealClock};
+use crate::function::prompt_input;
 
 // ------------------ PARAMETERS ------------------
-pub const MAX_ATTEMPTS: i64 = 3;          // Max failed attempts before lockout
-const LOCKOUT_SECONDS_BASE: i64 = 30;   // Initial lockout (30s)
-const MAX_LOCKOUT_SECONDS: i64 = 300;   // Max lockout cap (5 minutes)
+const SESSION_MAX_ATTEMPTS: i64 = 3;  // Failed attempts before the session locks
 const SESSION_LOCK_SECONDS: i64 = 60; 
+
+// How many failures an account gets and how long the lockouts last
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    pub max_attempts: i64,        // Max failed attempts before lockout
+    pub base_lockout_secs: i64,   // Initial lockout, doubled on each repeat
+    pub max_lockout_secs: i64,    // Lockout cap
+}
+
+// Password accounts
+pub const STANDARD_LOCKOUT: LockoutPolicy = LockoutPolicy {
+    max_attempts: 5,
+    base_lockout_secs: 30,
+    max_lockout_secs: 300,
+};
+
+// Guests sign in with short numeric PINs, so they get fewer tries and longer lockouts
+pub const GUEST_PIN_LOCKOUT: LockoutPolicy = LockoutPolicy {
+    max_attempts: 3,
+    base_lockout_secs: 120,
+    max_lockout_secs: 1800,
+};
+
+pub const MAX_ATTEMPTS: i64 = STANDARD_LOCKOUT.max_attempts;
+
+// Policy for the account being logged into; unknown usernames get the standard one
+pub fn lockout_policy_for(conn: &Connection, username: &str) -> Result<LockoutPolicy> {
+    let role: Option<String> = conn
+        .query_row(
+            "SELECT user_status FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(match role.as_deref() {
+        Some("guest") => GUEST_PIN_LOCKOUT,
+        _ => load_lockout_policy(conn)?,
+    })
+}
+
+// Password-account policy as the admin configured it (system_settings), else STANDARD_LOCKOUT.
+// The guest PIN policy stays fixed.
+const POLICY_MAX_ATTEMPTS_KEY: &str = "lockout_max_attempts";
+const POLICY_BASE_SECS_KEY: &str = "lockout_base_secs";
+const POLICY_MAX_SECS_KEY: &str = "lockout_max_secs";
+
+pub fn load_lockout_policy(conn: &Connection) -> Result<LockoutPolicy> {
+    let read = |key: &str, default: i64| -> Result<i64> {
+        Ok(crate::db::get_setting(conn, key)?
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(default))
+    };
+    Ok(LockoutPolicy {
+        max_attempts: read(POLICY_MAX_ATTEMPTS_KEY, STANDARD_LOCKOUT.max_attempts)?,
+        base_lockout_secs: read(POLICY_BASE_SECS_KEY, STANDARD_LOCKOUT.base_lockout_secs)?,
+        max_lockout_secs: read(POLICY_MAX_SECS_KEY, STANDARD_LOCKOUT.max_lockout_secs)?,
+    })
+}
+
+pub fn validate_lockout_policy(policy: &LockoutPolicy) -> Result<()> {
+    if policy.max_attempts <= 0 || policy.base_lockout_secs <= 0 || policy.max_lockout_secs <= 0 {
+        return Err(anyhow!("Lockout policy values must be positive."));
+    }
+    if policy.max_lockout_secs < policy.base_lockout_secs {
+        return Err(anyhow!("Lockout cap must be at least the base lockout."));
+    }
+    Ok(())
+}
+
+pub fn save_lockout_policy(conn: &Connection, admin_username: &str, policy: &LockoutPolicy) -> Result<()> {
+    validate_lockout_policy(policy)?;
+    let old = load_lockout_policy(conn)?;
+    crate::db::set_setting(conn, POLICY_MAX_ATTEMPTS_KEY, &policy.max_attempts.to_string(), admin_username)?;
+    crate::db::set_setting(conn, POLICY_BASE_SECS_KEY, &policy.base_lockout_secs.to_string(), admin_username)?;
+    crate::db::set_setting(conn, POLICY_MAX_SECS_KEY, &policy.max_lockout_secs.to_string(), admin_username)?;
+    log_event(conn, admin_username, None, "SETTINGS_CHANGED", Some(&format!(
+        "Lockout policy changed: attempts {} -> {}, base {}s -> {}s, cap {}s -> {}s",
+        old.max_attempts, policy.max_attempts,
+        old.base_lockout_secs, policy.base_lockout_secs,
+        old.max_lockout_secs, policy.max_lockout_secs
+    )))?;
+    Ok(())
+}
+
 // Current timestamp in Eastern Time (EST/EDT)
 pub fn now_est() -> DateTime<chrono_tz::Tz> {
     New_York.from_utc_datetime(&Utc::now().naive_utc())
 }
 
+// Every security_log timestamp is UTC RFC3339 with a Z suffix, e.g. "2025-03-01T14:05:00Z",
+// so plain string order is time order
+pub fn format_log_timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+// Canonical form of a stored timestamp: RFC3339 with any offset, or SQLite's UTC "YYYY-MM-DD HH:MM:SS"
+pub fn canonical_log_timestamp(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(format_log_timestamp(dt.with_timezone(&Utc)));
+    }
+    NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|naive| format_log_timestamp(naive.and_utc()))
+}
+
 // Small random delay to prevent timing attacks
 pub fn fake_verification_delay() {
     let delay_ms: u64 = rand::rng().random_range(100..=250);
@@ -23,9 +128,72 @@ pub fn fake_verification_delay() {
 
 
 // ------------------ LOGGING ------------------
-// Log event to both DB and file
+pub const DEFAULT_SECURITY_LOG_PATH: &str = "security.log";
+
+lazy_static! {
+    // Plain-text mirror of security_log; the database stays the source of truth
+    static ref SECURITY_LOG_PATH: Mutex<PathBuf> = Mutex::new(PathBuf::from(DEFAULT_SECURITY_LOG_PATH));
+}
+static FILE_LOG_WARNED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_security_log_path<P: Into<PathBuf>>(path: P) {
+    if let Ok(mut current) = SECURITY_LOG_PATH.lock() {
+        *current = path.into();
+    }
+}
+
+// Where log_event mirrors events to right now
+pub fn security_log_path() -> PathBuf {
+    SECURITY_LOG_PATH
+        .lock()
+        .map(|p| p.clone())
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_SECURITY_LOG_PATH))
+}
+
+fn append_to_security_log(path: &Path, line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+// Log event to the DB (required) and the log file (best effort)
 pub fn log_event(conn: &Connection, actor_username: &str, target_username: Option<&str>, event_type: &str, description: Option<&str>) -> Result<()> {
-    let timestamp = now_est().to_rfc3339();
+    log_event_at(conn, actor_username, target_username, event_type, description, Utc::now())
+}
+
+// Same, mirrored to `log_path` instead of the configured file
+pub fn log_event_to(
+    conn: &Connection,
+    log_path: &Path,
+    actor_username: &str,
+    target_username: Option<&str>,
+    event_type: &str,
+    description: Option<&str>,
+) -> Result<()> {
+    record_event(conn, log_path, actor_username, target_username, event_type, description, Utc::now())
+}
+
+// Same, stamped with the given time (clock-driven callers)
+fn log_event_at(
+    conn: &Connection,
+    actor_username: &str,
+    target_username: Option<&str>,
+    event_type: &str,
+    description: Option<&str>,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    record_event(conn, &security_log_path(), actor_username, target_username, event_type, description, at)
+}
+
+fn record_event(
+    conn: &Connection,
+    log_path: &Path,
+    actor_username: &str,
+    target_username: Option<&str>,
+    event_type: &str,
+    description: Option<&str>,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    let timestamp = format_log_timestamp(at);
 
     conn.execute(
         "INSERT INTO security_log (actor_username, target_username, event_type, description, timestamp)
@@ -33,17 +201,16 @@ pub fn log_event(conn: &Connection, actor_username: &str, target_username: Optio
         params![actor_username, target_username.unwrap_or(actor_username), event_type, description.unwrap_or(""), timestamp],
     )?;
 
-    // Log to file
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("security.log")
-        .context("Failed to open security.log")?;
-    writeln!(
-        file,
+    // Log to file; a read-only or missing location must not undo the DB write above
+    let line = format!(
         "{} | actor={} | target={} | event={} | desc={}",
         timestamp, actor_username, target_username.unwrap_or("-"), event_type, description.unwrap_or("-")
-    )?;
+    );
+    if let Err(e) = append_to_security_log(log_path, &line)
+        && !FILE_LOG_WARNED.swap(true, Ordering::Relaxed)
+    {
+        eprintln!("⚠️  Could not write the security log file ({}). Events are still recorded in the database.", e);
+    }
     Ok(())
 }
 
@@ -51,6 +218,10 @@ pub fn log_event(conn: &Connection, actor_username: &str, target_username: Optio
 
 // Check if user is currently locked out
 pub fn check_lockout(conn: &Connection, username: &str) -> Result<bool> {
+    check_lockout_with_clock(conn, username, &RealClock)
+}
+
+pub fn check_lockout_with_clock(conn: &Connection, username: &str, clock: &dyn Clock) -> Result<bool> {
     if let Some(locked_until_str) = conn
         .query_row(
             "SELECT locked_until FROM lockouts WHERE username = ?1 COLLATE NOCASE",
@@ -59,7 +230,7 @@ pub fn check_lockout(conn: &Connection, username: &str) -> Result<bool> {
         )
         .optional()?
     {
-        let now = now_est();
+        let now = clock.now().with_timezone(&New_York);
         let locked_until =
             DateTime::parse_from_rfc3339(&locked_until_str)?.with_timezone(&New_York);
 
@@ -81,15 +252,20 @@ pub fn check_lockout(conn: &Connection, username: &str) -> Result<bool> {
 
 // Record success/failure and apply lockouts automatically
 pub fn record_login_attempt(conn: &Connection, actor_username: &str, success: bool) -> Result<()> {
+    record_login_attempt_with_clock(conn, actor_username, success, &RealClock)
+}
+
+pub fn record_login_attempt_with_clock(conn: &Connection, actor_username: &str, success: bool, clock: &dyn Clock) -> Result<()> {
+    let now = clock.now();
     if success {
-        log_event(conn, actor_username, Some(actor_username), "SUCCESS_LOGIN", None)?;
+        log_event_at(conn, actor_username, Some(actor_username), "SUCCESS_LOGIN", None, now)?;
 
          // Update last_login_time and updated_at
         conn.execute(
             "UPDATE users 
-             SET last_login_time = datetime('now'), updated_at = datetime('now')
+             SET last_login_time = ?2, updated_at = ?2
              WHERE username = ?1 COLLATE NOCASE",
-            params![actor_username],
+            params![actor_username, clock.sqlite_now()],
         )?;
 
         // Clear any lockout state for this user
@@ -98,26 +274,27 @@ pub fn record_login_attempt(conn: &Connection, actor_username: &str, success: bo
     }
 
     // Failed attempt
-    log_event(conn, actor_username, Some(actor_username), "FAILURE_LOGIN", None)?;
+    log_event_at(conn, actor_username, Some(actor_username), "FAILURE_LOGIN", None, now)?;
 
    let recent_failures: i64 = conn.query_row(
         r#"
         SELECT COUNT(*) FROM security_log
         WHERE actor_username = ?1
           AND event_type = 'FAILURE_LOGIN'
-          AND timestamp > IFNULL((
-                SELECT MAX(timestamp)
+          AND datetime(timestamp) > IFNULL((
+                SELECT MAX(datetime(timestamp))
                 FROM security_log
                 WHERE actor_username = ?1 AND event_type = 'SUCCESS_LOGIN'
-          ), '1970-01-01T00:00:00Z')
-          AND timestamp > datetime('now', '-5 minutes')
+          ), '1970-01-01 00:00:00')
+          AND datetime(timestamp) > datetime(?2, '-5 minutes')
         "#,
-        params![actor_username],
+        params![actor_username, clock.sqlite_now()],
         |r| r.get(0),
     )?;
 
 
-    if recent_failures >= MAX_ATTEMPTS as i64 {
+    let policy = lockout_policy_for(conn, actor_username)?;
+    if recent_failures >= policy.max_attempts {
         // Get previous lockout count (if exists)
         let prev_count: Option<i64> = conn
             .query_row(
@@ -129,8 +306,8 @@ pub fn record_login_attempt(conn: &Connection, actor_username: &str, success: bo
 
         let next_count = prev_count.map_or(1, |c| (c + 1).min(10));
         let lockout_secs =
-            (LOCKOUT_SECONDS_BASE * (2_i64.pow(next_count as u32 - 1))).min(MAX_LOCKOUT_SECONDS);
-        let locked_until = (now_est() + Duration::seconds(lockout_secs)).to_rfc3339();
+            (policy.base_lockout_secs * (2_i64.pow(next_count as u32 - 1))).min(policy.max_lockout_secs);
+        let locked_until = (now.with_timezone(&New_York) + Duration::seconds(lockout_secs)).to_rfc3339();
 
         // Store new lockout
         conn.execute(
@@ -139,8 +316,8 @@ pub fn record_login_attempt(conn: &Connection, actor_username: &str, success: bo
             params![actor_username, locked_until, next_count],
         )?;
 
-        log_event(conn, actor_username, Some(actor_username), "LOCKOUT", 
-        Some("Account locked due to repeated failed attempts."))?;
+        log_event_at(conn, actor_username, Some(actor_username), "LOCKOUT",
+        Some("Account locked due to repeated failed attempts."), now)?;
         println!(
             "'{}' locked for {} seconds (until {}).",
             actor_username, lockout_secs, locked_until
@@ -177,6 +354,10 @@ pub fn increment_session_fail(conn: &Connection, username: Option<&str>) -> Resu
 
 
 pub fn session_lockout_check(conn: &Connection, username: Option<&str>) -> Result<bool> {
+    session_lockout_check_with_clock(conn, username, &RealClock)
+}
+
+pub fn session_lockout_check_with_clock(conn: &Connection, username: Option<&str>, clock: &dyn Clock) -> Result<bool> {
 
     let (where_clause, owned_params): (&str, Vec<Box<dyn ToSql>>) = match username {
         Some(u) => (
@@ -200,7 +381,11 @@ pub fn session_lockout_check(conn: &Connection, username: Option<&str>) -> Resul
             .optional()?;
 
     if let Some((fails, locked, until)) = row {
-        let now = chrono::Utc::now();
+        let now = clock.now();
+        // The session gate never trips later, or lifts sooner, than the account policy
+        let policy = load_lockout_policy(conn)?;
+        let max_attempts = SESSION_MAX_ATTEMPTS.min(policy.max_attempts);
+        let lock_seconds = SESSION_LOCK_SECONDS.max(policy.base_lockout_secs);
 
         //Currently locked
         if locked == 1 {
@@ -224,15 +409,15 @@ pub fn session_lockout_check(conn: &Connection, username: Option<&str>) -> Resul
         }
 
         // Too many failed attempts
-        if fails >= MAX_ATTEMPTS {
-            let until = (now + chrono::Duration::seconds(SESSION_LOCK_SECONDS)).to_rfc3339();
+        if fails >= max_attempts {
+            let until = (now + chrono::Duration::seconds(lock_seconds)).to_rfc3339();
             conn.execute(
                 "UPDATE session_state SET is_locked = 1, locked_until = ?1 WHERE username IS NULL",
                 params![until],
             )?;
             println!(
                 "Too many failed attempts. Session locked for {} seconds.",
-                SESSION_LOCK_SECONDS
+                lock_seconds
             );
             log_event(conn, username.unwrap_or("<anonymous>"), username, "SESSION_LOCKOUT", Some(&format!("Session locked due to multiple fail attempts.")))?;
             return Ok(true);
@@ -333,6 +518,53 @@ pub fn clear_lockout(conn: &Connection, current_admin: &str, username: Option<&s
     Ok(())
 }
 
+// Bulk clear of every lockout in one transaction, logged as a single LOCKOUT_CLEARED event
+pub fn clear_all_lockouts(conn: &mut Connection, admin_username: &str) -> Result<usize> {
+    let role: Option<String> = conn
+        .query_row(
+            "SELECT user_status FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![admin_username],
+            |r| r.get(0),
+        )
+        .optional()?;
+    if role.as_deref() != Some("admin") {
+        return Err(anyhow!("Access denied: only admin can clear lockouts."));
+    }
+
+    let tx = conn.transaction().context("Failed to start transaction")?;
+    let cleared = tx.execute("DELETE FROM lockouts", []).context("Failed to delete lockout entries")?;
+    log_event(&tx, admin_username, None, "LOCKOUT_CLEARED", Some(&format!("Admin cleared all lockouts ({} accounts)", cleared)))?;
+    tx.commit()?;
+    Ok(cleared)
+}
+
+// Every event_type the security_log CHECK constraint accepts
+pub const SECURITY_EVENT_TYPES: &[&str] = &[
+    "ACCOUNT_CREATED", "SUCCESS_LOGIN", "FAILURE_LOGIN", "LOGOUT", "LOCKOUT", "SESSION_LOCKOUT", "LOCKOUT_CLEARED",
+    "ACCOUNT_DELETED", "ACCOUNT_DISABLED", "ACCOUNT_ENABLED", "ADMIN_LOGIN", "PASSWORD_CHANGE", "HVAC",
+    "ACCESS_GRANTED", "ACCESS_EXPIRED", "TECH_ACCESS", "SETTINGS_CHANGED", "GUEST_LOGIN", "LOGIN_REFUSED",
+];
+
+// Username typed at the log filter, held to the account-name format (anonymized names included)
+pub fn username_filter(input: &str) -> Result<String> {
+    let name = input.trim();
+    if !auth::username_format_is_valid(name) {
+        bail!("'{}' is not a valid username filter.", name);
+    }
+    Ok(name.to_string())
+}
+
+/* Event type typed at the log filter, matched case-insensitively against the
+   known types; an unknown type is rejected with the list of valid ones. */
+pub fn event_type_filter(input: &str) -> Result<&'static str> {
+    let event = input.trim();
+    SECURITY_EVENT_TYPES
+        .iter()
+        .find(|t| t.eq_ignore_ascii_case(event))
+        .copied()
+        .ok_or_else(|| anyhow!("Unknown event type '{}'. Valid types: {}", event, SECURITY_EVENT_TYPES.join(", ")))
+}
+
 pub fn view_security_log(conn: &Connection, _admin_username: &str, current_role: &str) -> Result<()> {
     // Ensure admin privileges
     if current_role != "admin" {
@@ -361,13 +593,13 @@ pub fn view_security_log(conn: &Connection, _admin_username: &str, current_role:
     println!("  [2] Filter by username");
     println!("  [3] Filter by event type");
     println!("  [4] Show recent N entries");
+    println!("  [5] Failed login report");
     println!("  [0] Cancel");
 
     print!("Select an option: ");
     io::stdout().flush().ok();
 
-    let mut choice = String::new();
-    io::stdin().read_line(&mut choice).ok();
+    let Some(choice) = prompt_input() else { return Ok(()); };
     let choice = choice.trim();
 
     // Start query
@@ -387,30 +619,40 @@ pub fn view_security_log(conn: &Connection, _admin_username: &str, current_role:
         "2" => {
             print!("Enter username to filter by (actor or target): ");
             io::stdout().flush().ok();
-            let mut name = String::new();
-            io::stdin().read_line(&mut name)?;
-            let name = name.trim().to_string();
-            query.push_str(" WHERE actor_username = ?1 OR target_username = ?1 COLLATE NOCASE");
+            let Some(name) = prompt_input() else { return Ok(()); };
+            let name = match username_filter(&name) {
+                Ok(n) => n,
+                Err(e) => { println!("{}", e); return Ok(()); }
+            };
+            query.push_str(" WHERE actor_username = ?1 COLLATE NOCASE OR target_username = ?1 COLLATE NOCASE");
             params_vec.push(name);
         }
         "3" => {
             print!("Enter event type (SUCCESS_LOGIN, FAILURE_LOGIN, LOCKOUT, etc.): ");
             io::stdout().flush().ok();
-            let mut event = String::new();
-            io::stdin().read_line(&mut event)?;
-            let event = event.trim().to_uppercase();
+            let Some(event) = prompt_input() else { return Ok(()); };
+            let event = match event_type_filter(&event) {
+                Ok(e) => e,
+                Err(e) => { println!("{}", e); return Ok(()); }
+            };
             query.push_str(" WHERE event_type = ?1");
-            params_vec.push(event);
+            params_vec.push(event.to_string());
         }
         "4" => {
             print!("Enter number of recent entries to view: ");
             io::stdout().flush().ok();
-            let mut limit = String::new();
-            io::stdin().read_line(&mut limit)?;
+            let Some(limit) = prompt_input() else { return Ok(()); };
             let limit = limit.trim().parse::<i64>().unwrap_or(20);
             query.push_str(" ORDER BY id DESC LIMIT ?1");
             limit_val = Some(limit);
         }
+        "5" => {
+            print!("Report window in hours [24]: ");
+            io::stdout().flush().ok();
+            let Some(hours) = prompt_input() else { return Ok(()); };
+            let hours = hours.trim().parse::<i64>().ok().filter(|h| *h > 0).unwrap_or(24);
+            return print_failed_login_report(conn, hours);
+        }
         "0" => {
             println!("Cancelled viewing logs.");
             return Ok(());
@@ -475,9 +717,216 @@ pub fn view_security_log(conn: &Connection, _admin_username: &str, current_role:
     Ok(())
 }
 
+// The user's own recent SUCCESS_LOGIN / FAILURE_LOGIN events, newest first, as (Eastern time, event)
+pub fn own_login_history(conn: &Connection, username: &str, limit: usize) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT datetime(timestamp), event_type FROM security_log
+          WHERE actor_username = ?1 COLLATE NOCASE
+            AND event_type IN ('SUCCESS_LOGIN', 'FAILURE_LOGIN')
+          ORDER BY datetime(timestamp) DESC, id DESC
+          LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![username, limit as i64], |r| {
+        let ts: Option<String> = r.get(0)?;
+        Ok((ts.map(|t| crate::db::display_time(&t)).unwrap_or_default(), r.get::<_, String>(1)?))
+    })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
 
+// ------------------ FAILED LOGIN REPORT ------------------
+// FAILURE_LOGIN counts per actor over the last `hours`, most failures first
+pub fn failed_login_report(conn: &Connection, hours: i64) -> Result<Vec<(String, i64)>> {
+    // timestamps are stored as RFC3339 with an offset; datetime() normalizes them to UTC
+    let mut stmt = conn.prepare(
+        "SELECT actor_username, COUNT(*) AS failures
+           FROM security_log
+          WHERE event_type = 'FAILURE_LOGIN'
+            AND datetime(timestamp) > datetime('now', printf('-%d hours', ?1))
+          GROUP BY actor_username COLLATE NOCASE
+          ORDER BY failures DESC, actor_username ASC",
+    )?;
+    let rows = stmt
+        .query_map(params![hours], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
 
+fn print_failed_login_report(conn: &Connection, hours: i64) -> Result<()> {
+    let report = failed_login_report(conn, hours)?;
 
+    println!("\n===== Failed Logins (last {} h) =====", hours);
+    if report.is_empty() {
+        println!("(No failed logins in this window.)");
+        return Ok(());
+    }
 
+    println!("{:<25} {:>8}", "Account", "Failures");
+    println!("{}", "-".repeat(45));
+    let threshold = load_lockout_policy(conn)?.max_attempts;
+    for (actor, count) in &report {
+        // more failures than the lockout threshold suggests brute forcing
+        let flag = if *count > threshold { "  ⚠️  above lockout threshold" } else { "" };
+        println!("{:<25} {:>8}{}", actor, count, flag);
+    }
+    println!("{}", "-".repeat(45));
+    Ok(())
+}
+
+// ======================= ACTIVITY FEED ====================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedSource {
+    Security,
+    Hvac,
+}
+
+impl FeedSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FeedSource::Security => "SECURITY",
+            FeedSource::Hvac => "HVAC",
+        }
+    }
+}
 
+// One row of either log, normalized to a common shape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedItem {
+    pub timestamp: String, // Eastern, "YYYY-MM-DD HH:MM:SS TZ"
+    pub source: FeedSource,
+    pub actor: String,
+    pub action: String,
+    pub details: String,
+}
 
+/* Newest-first merge of security_log and hvac_activity_log.
+   Both store UTC RFC3339, but older rows may still be in SQLite's UTC form,
+   so both go through datetime() to sort on the same clock. */
+pub fn combined_activity_feed(conn: &Connection, limit: usize) -> Result<Vec<FeedItem>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT source, ts, actor, action, details FROM (
+            SELECT 'SECURITY' AS source, datetime(timestamp) AS ts, id,
+                   actor_username AS actor, event_type AS action,
+                   CASE WHEN target_username <> actor_username
+                        THEN 'target=' || target_username || ' ' || IFNULL(description, '')
+                        ELSE IFNULL(description, '') END AS details
+              FROM security_log
+            UNION ALL
+            SELECT 'HVAC', datetime(timestamp), id,
+                   username || ' (' || user_role || ')', action_type,
+                   IFNULL(NULLIF(description, ''), IFNULL(old_value, '') || ' → ' || IFNULL(new_value, ''))
+              FROM hvac_activity_log
+        )
+        ORDER BY ts DESC, id DESC
+        LIMIT ?1
+        "#,
+    )?;
+    let rows = stmt.query_map(params![limit as i64], feed_item)?;
+
+    let mut feed = Vec::new();
+    for item in rows {
+        feed.push(item?);
+    }
+    Ok(feed)
+}
+
+// (source, ts, actor, action, details) columns -> FeedItem, time shown in Eastern
+fn feed_item(r: &rusqlite::Row) -> rusqlite::Result<FeedItem> {
+    let source: String = r.get(0)?;
+    let ts: Option<String> = r.get(1)?;
+    Ok(FeedItem {
+        timestamp: ts.map(|t| crate::db::display_time(&t)).unwrap_or_default(),
+        source: if source == "HVAC" { FeedSource::Hvac } else { FeedSource::Security },
+        actor: r.get(2)?,
+        action: r.get(3)?,
+        details: r.get::<_, String>(4)?.trim().to_string(),
+    })
+}
+
+// security_log "HVAC" descriptions (LIKE patterns) that only repeat an hvac_activity_log row
+const HVAC_ACTIVITY_DUPLICATES: &[&str] = &[
+    "Profile '%' applied with mode %",
+    "Custom profile '%' applied with mode %",
+    "Profile '%' created by %",
+    "Profile '%' edited by %",
+    "Profile '%' deleted by %",
+    "Mode set to %",
+    "Target temperature set to %",
+];
+
+/* What happened to the home from `since` on, oldest first: every HVAC change,
+   plus security events aimed at the homeowner by someone else (grants,
+   technician access, blocked guest attempts) or done by one of their guests.
+   Security "HVAC" rows that repeat an hvac_activity_log row are left out. */
+pub fn activity_since(conn: &Connection, homeowner_username: &str, since: DateTime<Utc>) -> Result<Vec<FeedItem>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT source, ts, actor, action, details FROM (
+            SELECT 'SECURITY' AS source, datetime(s.timestamp) AS ts, s.id,
+                   s.actor_username AS actor, s.event_type AS action,
+                   CASE WHEN s.target_username <> s.actor_username
+                        THEN 'target=' || s.target_username || ' ' || IFNULL(s.description, '')
+                        ELSE IFNULL(s.description, '') END AS details
+              FROM security_log s
+             WHERE NOT (s.event_type = 'HVAC'
+                        AND EXISTS (SELECT 1 FROM json_each(?3) d WHERE s.description LIKE d.value))
+               AND ((s.target_username = ?1 COLLATE NOCASE AND s.actor_username <> ?1 COLLATE NOCASE)
+                    OR EXISTS (SELECT 1 FROM users g JOIN users h ON h.id = g.homeowner_id
+                                WHERE g.username = s.actor_username COLLATE NOCASE
+                                  AND h.username = ?1 COLLATE NOCASE))
+            UNION ALL
+            SELECT 'HVAC', datetime(timestamp), id,
+                   username || ' (' || user_role || ')', action_type,
+                   IFNULL(NULLIF(description, ''), IFNULL(old_value, '') || ' → ' || IFNULL(new_value, ''))
+              FROM hvac_activity_log
+        )
+        WHERE ts >= ?2
+        ORDER BY ts, id
+        "#,
+    )?;
+    let since = since.format("%Y-%m-%d %H:%M:%S").to_string();
+    let duplicates = serde_json::to_string(HVAC_ACTIVITY_DUPLICATES)?;
+    let rows = stmt.query_map(params![homeowner_username, since, duplicates], feed_item)?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+const FEED_PAGE_SIZE: usize = 15;
+
+// Admin view of the merged feed, one page at a time
+pub fn view_activity_feed(conn: &Connection, current_role: &str) -> Result<()> {
+    if current_role != "admin" {
+        println!("Access denied: Only administrators can view the activity feed.");
+        return Ok(());
+    }
+
+    let feed = combined_activity_feed(conn, 300)?;
+    if feed.is_empty() {
+        println!("(No activity recorded yet.)");
+        return Ok(());
+    }
+
+    let pages = feed.len().div_ceil(FEED_PAGE_SIZE);
+    for (page, chunk) in feed.chunks(FEED_PAGE_SIZE).enumerate() {
+        println!("\n===== Recent Activity (page {}/{}) =====", page + 1, pages);
+        println!("{:<24} {:<9} {:<22} {:<20} Details", "Time", "Source", "Actor", "Action");
+        println!("{}", "-".repeat(100));
+        for item in chunk {
+            println!(
+                "{:<24} {:<9} {:<22} {:<20} {}",
+                item.timestamp, item.source.as_str(), item.actor, item.action, item.details
+            );
+        }
+        if page + 1 == pages {
+            break;
+        }
+        print!("\n[Enter] next page, [Q] quit: ");
+        io::stdout().flush().ok();
+        match prompt_input() {
+            Some(a) if a.eq_ignore_ascii_case("q") => break,
+            Some(_) => {}
+            None => break,
+        }
+    }
+    Ok(())
+}
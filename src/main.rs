@@ -1,65 +1,132 @@
-mod auth; mod db; mod function; mod guest; mod hvac; mod logger;
-mod menu; mod ui;  mod profile; mod senser; mod technician; mod weather; mod energy;
-mod diagnostic;
+use smart_thermostat::{auth, db, function, guest, integrity, menu, senser, sweeper, ui};
 
 use anyhow::{bail, Context, Result};
-use sha2::{Digest, Sha256};
-use std::{fs, path::Path};
+use std::path::Path;
 
 fn main() -> Result<()> {
+    // Script-friendly one-shot output, printed before anything else touches stdout
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let db_path = db::configure_data_dir()?;
+    if args.iter().any(|a| a == "--status-line") {
+        return print_status_line(&db_path);
+    }
+    if args.iter().any(|a| a == "--report") {
+        return print_report(&db_path);
+    }
+    if args.iter().any(|a| a == "--seed-demo") {
+        return run_seed_demo(&db_path, args.iter().any(|a| a == "--force"));
+    }
+    if args.iter().any(|a| a == "--migrate-status") {
+        return print_migrate_status(&db_path);
+    }
+    if args.iter().any(|a| a == "--migrate") {
+        return run_migrate(&db_path);
+    }
+
+    if args.iter().any(|a| a == "--gen-integrity") {
+        return run_gen_integrity();
+    }
+    if args.iter().any(|a| a == "--verify-integrity") {
+        return check_integrity();
+    }
+
     let integrity_check = true;
     if integrity_check {
-        // 1) Check the hash list file is exist
-        let manifest = "INTEGRITY.sha256";
-        if !Path::new(manifest).exists() {
-            bail!("Can't find {manifest}, please generate the hash list first(scripts/gen_integrity.ps1)");
-        }
+        check_integrity()?;
+    }
 
-        // 2) Verify each line: format of each line "<hash><two spaces><filename>"
-        let content = fs::read_to_string(manifest).context("Read INTEGRITY.sha256 fail")?;
-        let mut ok = 0usize;
-        let mut bad = 0usize;
-
-        for (idx, line) in content.lines().enumerate() {
-            let line = line.trim();
-            if line.is_empty() { continue; }
-            let (expected_hash, file) = match line.split_once("  ") {
-                Some(t) => t,
-                None => bail!("INTEGRITY.sha256 line {} error format: {}", idx + 1, line),
-            };
-
-            let data = fs::read(file).with_context(|| format!("Read fail: {file}"))?;
-            let got = hex::encode(Sha256::digest(&data));
-            if got == expected_hash {
-                // println!("✅ OK  {file}"); // for debug: check integrity
-                ok += 1;
-            } else {
-                // eprintln!("❌ MISMATCH  {file}"); // for debug: check integrity
-                bad += 1;
-            }
-        }
+    // After passing the check, run the system
+    run_app(&db_path)
+}
 
-        println!("---\nPASS: {ok}, FAIL: {bad}");
-        if bad > 0 {
-            bail!("Fail to check the integrity of the source code, stop excuting");
-        }
-        
-        }
+// Verify INTEGRITY.sha256: format of each line "<hash><two spaces><filename>"
+fn check_integrity() -> Result<()> {
+    let manifest = Path::new(integrity::MANIFEST_FILE);
+    if !manifest.exists() {
+        bail!("Can't find {}, please generate the hash list first (--gen-integrity)", integrity::MANIFEST_FILE);
+    }
+    let (ok, bad) = integrity::verify_integrity(manifest)?;
+    println!("---\nPASS: {ok}, FAIL: {bad}");
+    if bad > 0 {
+        bail!("Fail to check the integrity of the source code, stop excuting");
+    }
+    Ok(())
+}
+
+// Rebuild INTEGRITY.sha256 from the files in the current directory
+fn run_gen_integrity() -> Result<()> {
+    let files = integrity::project_files(Path::new("."))?;
+    integrity::generate_integrity_manifest(&files, Path::new(integrity::MANIFEST_FILE))?;
+    println!("Wrote {} entries to {}", files.len(), integrity::MANIFEST_FILE);
+    Ok(())
+}
+
+fn print_status_line(db_path: &Path) -> Result<()> {
+    let conn = db::get_connection(db_path).context("Failed to open system database")?;
+    let indoor = senser::get_indoor_temperature().ok();
+    println!("{}", db::status_line(&conn, indoor)?);
+    Ok(())
+}
 
-    // 3) After passing the check, run the system
-    run_app()
+// Reads only; the database is never initialized or migrated here
+fn print_report(db_path: &Path) -> Result<()> {
+    let conn = db::open_read_only(db_path).context("Failed to open system database")?;
+    print!("{}", db::system_report(&conn)?);
+    Ok(())
+}
+
+fn run_seed_demo(db_path: &Path, force: bool) -> Result<()> {
+    let mut conn = db::get_connection(db_path).context("Failed to open system database")?;
+    db::seed_demo_data(&mut conn, force)?;
+    println!("Demo data added. Accounts: demo_admin, demo_home, demo_tech (password {}),", db::DEMO_PASSWORD);
+    println!("guests demo_guest1 and demo_guest2 (PIN {}).", db::DEMO_GUEST_PIN);
+    println!("demo_admin must choose a new password at first login.");
+    Ok(())
+}
+
+// Applies pending migrations without starting the menu
+fn run_migrate(db_path: &Path) -> Result<()> {
+    let conn = db::open_without_migrating(db_path)?;
+    let (before, after) = db::migrate_database(&conn)?;
+    if before == after {
+        println!("Schema already at version {after}, nothing to do.");
+    } else {
+        println!("Schema migrated: version {before} -> {after}");
+    }
+    Ok(())
+}
 
-    
+// Reports versions only; the database is not modified
+fn print_migrate_status(db_path: &Path) -> Result<()> {
+    let conn = db::open_without_migrating(db_path)?;
+    println!("Current schema version: {}", db::schema_version(&conn)?);
+    println!("Latest schema version:  {}", db::latest_schema_version());
+    let pending = db::pending_migrations(&conn)?;
+    if pending.is_empty() {
+        println!("No pending migrations.");
+    }
+    for (version, desc) in pending {
+        println!("  pending {version}: {desc}");
+    }
+    Ok(())
 }
 
-fn run_app() -> Result<()> {
+fn run_app(db_path: &Path) -> Result<()> {
     // Initialize unified system database (users + logs + lockouts)
-    let db_path = "system.db";
     let mut conn = db::get_connection(db_path).expect("Failed to initialize system database.");
 
+    // Periodic cleanup runs on its own connection; the handle stops and joins it on exit
+    let sweeper = match sweeper::configured_interval() {
+        Some(interval) => Some(sweeper::Sweeper::start(
+            std::sync::Arc::new(std::sync::Mutex::new(db::get_connection(db_path)?)),
+            interval,
+        )),
+        None => None,
+    };
+
     let _anon_token = db::update_session(&conn, None)?;
     // Show front page UI
-    ui::front_page_ui();
+    ui::front_page_ui(&conn);
 
     // Main program loop
     loop {
@@ -75,11 +142,11 @@ fn run_app() -> Result<()> {
                             menu::main_menu(&mut conn, &username, &role)?;
                         }
                         Ok(None) => {
-                            ui::front_page_ui();
+                            ui::front_page_ui(&conn);
                         }
                         Err(e) => {
                             eprintln!("Login error: {e}");
-                            ui::front_page_ui();
+                            ui::front_page_ui(&conn);
                         }
                     }
                 }
@@ -93,11 +160,11 @@ fn run_app() -> Result<()> {
                             menu::main_menu(&mut conn, &username, "guest")?;
                         }
                         Ok(None) => {
-                            ui::front_page_ui();
+                            ui::front_page_ui(&conn);
                         }
                         Err(e) => {
                             eprintln!("Guest login error: {e}");
-                            ui::front_page_ui();
+                            ui::front_page_ui(&conn);
                         }
                     }
                 }
@@ -106,7 +173,7 @@ fn run_app() -> Result<()> {
                 "3" => {
                     ui::about_ui();
                     function::wait_for_enter();
-                    ui::front_page_ui();
+                    ui::front_page_ui(&conn);
                 }
 
                 // === [4] EXIT ===
@@ -116,7 +183,16 @@ fn run_app() -> Result<()> {
                     break;
                 }
 
-                _ => println!("Invalid choice. Please enter 1–4.\n"),
+                // === [5] LOCKOUT RECOVERY ===
+                "5" => {
+                    if let Err(e) = auth::recover_locked_account(&conn) {
+                        eprintln!("Recovery error: {e}");
+                    }
+                    function::wait_for_enter();
+                    ui::front_page_ui(&conn);
+                }
+
+                _ => println!("Invalid choice. Please enter 1–5.\n"),
             },
             None => {
                 println!("End of input detected. Exiting...");
@@ -125,5 +201,8 @@ fn run_app() -> Result<()> {
         }
     }
 
+    if let Some(sweeper) = sweeper {
+        sweeper.stop();
+    }
     Ok(())
 }
@@ -1,36 +1,23 @@
 use rusqlite::{Connection, params};
 use anyhow::Result;
 use std::io::{self, Write};
-use rpassword;
 
 use crate::{auth, db, guest, hvac, logger, senser, technician, ui, weather, diagnostic};
 use crate::energy;
-use crate::function::{prompt_input, wait_for_enter};
+use crate::function::{confirm, parse_menu_index, prompt_input, prompt_menu_choice, input_timed_out, default_inactivity_timeout_secs, inactivity_timeout_secs, wait_for_enter};
 
 use crate::profile::{HVACProfile, apply_profile};
 use crate::hvac::{HVACSystem, HVACMode};
+use crate::units::TempUnit;
+use crate::menu_config::{self, menu_action, MenuItem};
 use chrono::Local;
 
-// ===============================================================
-//                    VACATION MODE CHECK
-// ===============================================================
-// Check if vacation mode is currently active
-fn is_vacation_mode_active(conn: &Connection) -> Result<bool> {
-    if let Ok(Some(vacation_profile)) = db::get_profile_row(conn, "Vacation") {
-        // Vacation mode is active if both start and end dates are set
-        Ok(vacation_profile.vacation_start_date.is_some() && 
-           vacation_profile.vacation_end_date.is_some())
-    } else {
-        Ok(false)
-    }
-}
-
 // ===============================================================
 //                         PROFILE SELECTION MENU
 // ===============================================================
 fn profile_selection_menu(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
     // Load all profiles from database
-    let profiles = db::list_profile_rows(conn)?;
+    let mut profiles = db::list_profile_rows(conn)?;
     
     if profiles.is_empty() {
         println!("❌ No profiles available.");
@@ -39,8 +26,20 @@ fn profile_selection_menu(conn: &mut Connection, username: &str, user_role: &str
     }
     
     ui::profile_selection_ui(&profiles);
+    let mut choice = prompt_input();
+
+    // [M] narrows the list to one mode, then asks again
+    if choice.as_deref().is_some_and(|c| c.trim().eq_ignore_ascii_case("m")) {
+        let Some(filtered) = prompt_profiles_by_mode(conn)? else {
+            wait_for_enter();
+            return Ok(());
+        };
+        profiles = filtered;
+        ui::profile_selection_ui(&profiles);
+        choice = prompt_input();
+    }
     
-    match prompt_input() {
+    match choice {
         Some(choice) => {
             if choice.trim() == "0" {
                 println!("Profile selection cancelled.");
@@ -48,219 +47,299 @@ fn profile_selection_menu(conn: &mut Connection, username: &str, user_role: &str
             }
             
             // Parse the selection
-            let selection: usize = match choice.trim().parse() {
-                Ok(num) if num > 0 && num <= profiles.len() => num,
-                _ => {
-                    println!("Invalid option.");
-                    return Ok(());
-                }
-            };
-            
-            // Get the selected profile
-            let selected_profile = &profiles[selection - 1];
-            let profile_name = &selected_profile.name;
-            
-            // Check if it's a default profile or custom
-            let profile_opt = match profile_name.as_str() {
-                "Day" => Some(HVACProfile::Day),
-                "Night" => Some(HVACProfile::Night),
-                "Sleep" => Some(HVACProfile::Sleep),
-                "Party" => Some(HVACProfile::Party),
-                "Vacation" => Some(HVACProfile::Vacation),
-                "Away" => Some(HVACProfile::Away),
-                _ => None, // Custom profile
+            let Some(selection) = parse_menu_index(&choice, profiles.len()) else {
+                println!("Invalid option.");
+                return Ok(());
             };
             
-            // For custom profiles, handle them separately
-            if profile_opt.is_none() {
-                // This is a custom profile - apply it directly from database
-                apply_custom_profile(conn, username, user_role, selected_profile)?;
-                println!("\n✓ Profile '{}' applied successfully!", profile_name);
+            apply_selected_profile(conn, username, user_role, &profiles[selection])?;
+        }
+        None => {
+            println!("No input detected.");
+        }
+    }
+    Ok(())
+}
+
+// Asks for a mode and returns its profiles; None (after saying why) for a bad mode or no match
+fn prompt_profiles_by_mode(conn: &Connection) -> Result<Option<Vec<db::ProfileRow>>> {
+    print!("Mode (Heating, Cooling, Auto, FanOnly, Off): ");
+    io::stdout().flush()?;
+    let Some(mode) = prompt_input() else { return Ok(None); };
+    match db::list_profiles_by_mode(conn, &mode) {
+        Ok(rows) if rows.is_empty() => {
+            println!("No {} profiles.", db::profile_mode_name(&mode)?);
+            Ok(None)
+        }
+        Ok(rows) => Ok(Some(rows)),
+        Err(e) => {
+            println!("❌ {}", e);
+            Ok(None)
+        }
+    }
+}
+
+// Apply one profile row, with the vacation-mode checks every apply goes through
+fn apply_selected_profile(conn: &mut Connection, username: &str, user_role: &str, selected_profile: &db::ProfileRow) -> Result<()> {
+    let attempted = format!("profile '{}'", selected_profile.name);
+    if !hvac_change_allowed(conn, username, &attempted, Some(selected_profile.target_temp))? {
+        wait_for_enter();
+        return Ok(());
+    }
+    let profile_name = &selected_profile.name;
+    
+    // Check if it's a default profile or custom
+    let profile_opt = match profile_name.as_str() {
+        "Day" => Some(HVACProfile::Day),
+        "Night" => Some(HVACProfile::Night),
+        "Sleep" => Some(HVACProfile::Sleep),
+        "Party" => Some(HVACProfile::Party),
+        "Vacation" => Some(HVACProfile::Vacation),
+        "Away" => Some(HVACProfile::Away),
+        _ => None, // Custom profile
+    };
+    
+    // For custom profiles, handle them separately
+    if profile_opt.is_none() {
+        // This is a custom profile - apply it directly from database
+        apply_custom_profile(conn, username, user_role, selected_profile)?;
+        println!("\n✓ Profile '{}' applied successfully!", profile_name);
+        wait_for_enter();
+        return Ok(());
+    }
+    
+    let profile = profile_opt.unwrap();
+
+    // Special handling for Vacation profile
+    if matches!(profile, HVACProfile::Vacation) {
+        // Only homeowner can enable vacation mode
+        if user_role != "homeowner" {
+            println!("❌ Access denied: Only homeowners can enable/disable vacation mode for security reasons.");
+            wait_for_enter();
+            return Ok(());
+        }
+        
+        // Check if vacation mode is currently active
+        let current_vacation = db::get_profile_row(conn, "Vacation")?;
+        let is_vacation_active = current_vacation
+            .as_ref()
+            .and_then(|p| p.vacation_start_date.as_ref())
+            .is_some();
+        
+        // Require password verification for enabling/disabling vacation mode
+        println!("\n🔐 Security Check: Please re-enter your password to modify vacation mode");
+        print!("Password: ");
+        io::stdout().flush()?;
+        
+        let password = match auth::read_secret() {
+            Ok(pwd) => pwd,
+            Err(_) => {
+                println!("❌ Error reading password. Vacation mode change cancelled.");
                 wait_for_enter();
                 return Ok(());
             }
-            
-            let profile = profile_opt.unwrap();
-
-            // Special handling for Vacation profile
-            if matches!(profile, HVACProfile::Vacation) {
-                // Only homeowner can enable vacation mode
-                if user_role != "homeowner" {
-                    println!("❌ Access denied: Only homeowners can enable/disable vacation mode for security reasons.");
-                    wait_for_enter();
-                    return Ok(());
+        };
+        
+        // Get stored password hash
+        let stored_hash: String = conn.query_row(
+            "SELECT hashed_password FROM users WHERE username = ?1",
+            params![username],
+            |row| row.get(0),
+        )?;
+        
+        if !auth::verify_password(&password, &stored_hash)? {
+            println!("❌ Incorrect password. Vacation mode change cancelled.");
+            wait_for_enter();
+            return Ok(());
+        }
+        
+        if is_vacation_active {
+            // Turning OFF vacation mode
+            println!("\n🏖️  Vacation mode is currently ACTIVE");
+            if confirm("Do you want to turn OFF vacation mode?", false) {
+                db::clear_vacation_dates(conn)?;
+                println!("✓ Vacation mode has been turned OFF.");
+                match db::enable_all_guests(conn, username) {
+                    Ok(0) => {}
+                    Ok(n) => println!("✓ Re-enabled {} guest account(s) disabled for vacation.", n),
+                    Err(e) => println!("❌ Failed to re-enable guests: {}", e),
                 }
-                
-                // Check if vacation mode is currently active
-                let current_vacation = db::get_profile_row(conn, "Vacation")?;
-                let is_vacation_active = current_vacation
-                    .as_ref()
-                    .and_then(|p| p.vacation_start_date.as_ref())
-                    .is_some();
-                
-                // Require password verification for enabling/disabling vacation mode
-                println!("\n🔐 Security Check: Please re-enter your password to modify vacation mode");
-                print!("Password: ");
-                io::stdout().flush()?;
-                
-                let password = match rpassword::read_password() {
-                    Ok(pwd) => pwd,
-                    Err(_) => {
-                        println!("❌ Error reading password. Vacation mode change cancelled.");
-                        wait_for_enter();
-                        return Ok(());
-                    }
-                };
-                
-                // Get stored password hash
-                let stored_hash: String = conn.query_row(
-                    "SELECT hashed_password FROM users WHERE username = ?1",
-                    params![username],
-                    |row| row.get(0),
-                )?;
-                
-                if !auth::verify_password(&password, &stored_hash)? {
-                    println!("❌ Incorrect password. Vacation mode change cancelled.");
+                wait_for_enter();
+                return Ok(());
+            } else {
+                println!("Vacation mode remains active.");
+                wait_for_enter();
+                return Ok(());
+            }
+        } else {
+            // Turning ON vacation mode - prompt for dates
+            println!("\n🏖️  Activating Vacation Mode");
+            println!("Please enter the vacation date range:");
+            
+            print!("Start date (mm-dd-yyyy): ");
+            io::stdout().flush()?;
+            let start_date = match prompt_input() {
+                Some(d) => d.trim().to_string(),
+                None => {
+                    println!("❌ Date required. Vacation mode cancelled.");
                     wait_for_enter();
                     return Ok(());
                 }
-                
-                if is_vacation_active {
-                    // Turning OFF vacation mode
-                    println!("\n🏖️  Vacation mode is currently ACTIVE");
-                    println!("Do you want to turn OFF vacation mode? (y/n): ");
-                    if let Some(confirm) = prompt_input() {
-                        if confirm.trim().eq_ignore_ascii_case("y") {
-                            db::clear_vacation_dates(conn)?;
-                            println!("✓ Vacation mode has been turned OFF.");
-                            wait_for_enter();
-                            return Ok(());
-                        } else {
-                            println!("Vacation mode remains active.");
-                            wait_for_enter();
-                            return Ok(());
-                        }
-                    }
-                } else {
-                    // Turning ON vacation mode - prompt for dates
-                    println!("\n🏖️  Activating Vacation Mode");
-                    println!("Please enter the vacation date range:");
-                    
-                    print!("Start date (mm-dd-yyyy): ");
-                    io::stdout().flush()?;
-                    let start_date = match prompt_input() {
-                        Some(d) => d.trim().to_string(),
-                        None => {
-                            println!("❌ Date required. Vacation mode cancelled.");
-                            wait_for_enter();
-                            return Ok(());
-                        }
-                    };
-                    
-                    print!("End date (mm-dd-yyyy): ");
-                    io::stdout().flush()?;
-                    let end_date = match prompt_input() {
-                        Some(d) => d.trim().to_string(),
-                        None => {
-                            println!("❌ Date required. Vacation mode cancelled.");
-                            wait_for_enter();
-                            return Ok(());
-                        }
-                    };
-                    
-                    // Validate date format (basic check)
-                    if !validate_date_format(&start_date) || !validate_date_format(&end_date) {
-                        println!("❌ Invalid date format. Please use mm-dd-yyyy format.");
-                        wait_for_enter();
-                        return Ok(());
-                    }
-                    
-                    // Save vacation dates
-                    db::set_vacation_dates(conn, &start_date, &end_date)?;
-                    
-                    let mut hvac = HVACSystem::new(conn);
-                    apply_profile(conn, &mut hvac, profile, username, user_role);
-                    println!("\n✓ Vacation mode activated from {} to {}!", start_date, end_date);
+            };
+            
+            print!("End date (mm-dd-yyyy): ");
+            io::stdout().flush()?;
+            let end_date = match prompt_input() {
+                Some(d) => d.trim().to_string(),
+                None => {
+                    println!("❌ Date required. Vacation mode cancelled.");
                     wait_for_enter();
                     return Ok(());
                 }
+            };
+            
+            // Validate date format (basic check)
+            if !validate_date_format(&start_date) || !validate_date_format(&end_date) {
+                println!("❌ Invalid date format. Please use mm-dd-yyyy format.");
+                wait_for_enter();
+                return Ok(());
             }
+            
+            // Save vacation dates
+            db::set_vacation_dates(conn, &start_date, &end_date)?;
+            
+            let mut hvac = HVACSystem::for_user(conn, username);
+            apply_profile(conn, &mut hvac, profile, username, user_role);
+            println!("\n✓ Vacation mode activated from {} to {}!", start_date, end_date);
 
-            // Check if vacation mode is currently active and switching to a different profile
-            if !matches!(profile, HVACProfile::Vacation) && is_vacation_mode_active(conn)? {
-                // Only homeowner can deactivate vacation mode
-                if user_role != "homeowner" {
-                    println!("❌ Access denied: Vacation mode is active. Only homeowners can change profiles.");
-                    wait_for_enter();
-                    return Ok(());
+            if confirm("Also disable all guest logins until vacation ends?", false) {
+                match db::disable_all_guests(conn, username) {
+                    Ok(n) => println!("✓ Disabled {} guest account(s).", n),
+                    Err(e) => println!("❌ Failed to disable guests: {}", e),
                 }
-                
-                println!("\n⚠️  VACATION MODE IS CURRENTLY ACTIVE");
-                println!("════════════════════════════════════════════════");
-                println!("You are attempting to switch to a different profile.");
-                println!("This will deactivate vacation mode and restore");
-                println!("guest and technician access to the system.");
-                println!("════════════════════════════════════════════════");
-                print!("\nDo you want to turn OFF vacation mode and switch to the new profile? (y/n): ");
-                io::stdout().flush()?;
-                
-                if let Some(confirm) = prompt_input() {
-                    if confirm.trim().eq_ignore_ascii_case("y") {
-                        // Require password verification
-                        println!("\n🔐 Security Check: Please re-enter your password to deactivate vacation mode");
-                        print!("Password: ");
-                        io::stdout().flush()?;
-                        
-                        let password = match rpassword::read_password() {
-                            Ok(pwd) => pwd,
-                            Err(_) => {
-                                println!("❌ Error reading password. Profile change cancelled.");
-                                wait_for_enter();
-                                return Ok(());
-                            }
-                        };
-                        
-                        // Get stored password hash
-                        let stored_hash: String = conn.query_row(
-                            "SELECT hashed_password FROM users WHERE username = ?1",
-                            params![username],
-                            |row| row.get(0),
-                        )?;
-                        
-                        if !auth::verify_password(&password, &stored_hash)? {
-                            println!("❌ Incorrect password. Profile change cancelled.");
-                            wait_for_enter();
-                            return Ok(());
-                        }
-                        
-                        // Clear vacation dates
-                        db::clear_vacation_dates(conn)?;
-                        println!("✓ Vacation mode has been deactivated.");
-                        println!("✓ Guest and technician access is now restored.");
-                    } else {
-                        println!("Profile change cancelled. Vacation mode remains active.");
-                        wait_for_enter();
-                        return Ok(());
-                    }
-                } else {
-                    println!("Profile change cancelled. Vacation mode remains active.");
+            }
+            wait_for_enter();
+            return Ok(());
+        }
+    }
+
+    // Check if vacation mode is currently active and switching to a different profile
+    if !matches!(profile, HVACProfile::Vacation) && db::vacation_mode_active(conn)? {
+        // Only homeowner can deactivate vacation mode
+        if user_role != "homeowner" {
+            println!("❌ Access denied: Vacation mode is active. Only homeowners can change profiles.");
+            wait_for_enter();
+            return Ok(());
+        }
+        
+        println!("\n⚠️  VACATION MODE IS CURRENTLY ACTIVE");
+        println!("════════════════════════════════════════════════");
+        println!("You are attempting to switch to a different profile.");
+        println!("This will deactivate vacation mode and restore");
+        println!("guest and technician access to the system.");
+        println!("════════════════════════════════════════════════");
+        println!();
+        if confirm("Do you want to turn OFF vacation mode and switch to the new profile?", false) {
+            // Require password verification
+            println!("\n🔐 Security Check: Please re-enter your password to deactivate vacation mode");
+            print!("Password: ");
+            io::stdout().flush()?;
+            
+            let password = match auth::read_secret() {
+                Ok(pwd) => pwd,
+                Err(_) => {
+                    println!("❌ Error reading password. Profile change cancelled.");
                     wait_for_enter();
                     return Ok(());
                 }
+            };
+            
+            // Get stored password hash
+            let stored_hash: String = conn.query_row(
+                "SELECT hashed_password FROM users WHERE username = ?1",
+                params![username],
+                |row| row.get(0),
+            )?;
+            
+            if !auth::verify_password(&password, &stored_hash)? {
+                println!("❌ Incorrect password. Profile change cancelled.");
+                wait_for_enter();
+                return Ok(());
             }
-
-            let mut hvac = HVACSystem::new(conn);
-            apply_profile(conn, &mut hvac, profile, username, user_role);
-            println!("\n✓ Profile applied successfully!");
+            
+            // Clear vacation dates
+            db::clear_vacation_dates(conn)?;
+            println!("✓ Vacation mode has been deactivated.");
+            println!("✓ Guest and technician access is now restored.");
+        } else {
+            println!("Profile change cancelled. Vacation mode remains active.");
             wait_for_enter();
+            return Ok(());
         }
-        None => {
-            println!("No input detected.");
-        }
+    }
+
+    let mut hvac = HVACSystem::for_user(conn, username);
+    apply_profile(conn, &mut hvac, profile, username, user_role);
+    println!("\n✓ Profile applied successfully!");
+    wait_for_enter();
+    Ok(())
+}
+
+// Indoor temperatures recorded since the latest emergency all-off
+fn show_cooldown_report(conn: &Connection) -> Result<()> {
+    let Some(since) = db::last_emergency_all_off(conn)? else {
+        println!("No emergency all-off has been recorded.");
+        return Ok(());
+    };
+    let report = db::cooldown_report(conn, &since)?;
+    println!("\n🧊 Cool-down since the all-off at {}", db::display_time(&since));
+    let Some(first) = report.first().map(|(_, t)| *t) else {
+        println!("(No indoor readings recorded yet.)");
+        return Ok(());
+    };
+    for (at, temp) in &report {
+        println!("  {}  {:.1}°C ({:+.1}°C)", db::display_time(at), temp, temp - first);
     }
     Ok(())
 }
 
+/* Every path that changes the HVAC asks here first. A guest whose homeowner
+   locked the HVAC is refused and the attempt (`attempted`, at `target_c`) is
+   recorded for the homeowner; everyone else is held to the change rate limit.
+   Prints why and returns false when the change must not go ahead. */
+fn hvac_change_allowed(conn: &Connection, username: &str, attempted: &str, target_c: Option<f32>) -> Result<bool> {
+    if db::guest_hvac_change_blocked(conn, username, attempted, target_c)? {
+        println!("🔒 Your homeowner has locked the HVAC. This attempt was recorded for them.");
+        return Ok(false);
+    }
+    let wait = db::next_allowed_change_in(conn, username)?;
+    if wait > 0 {
+        println!("⏳ Too many HVAC changes in a row. Please wait {}s before the next change.", wait);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/* Reapply the profile this user applied last, after a confirmation. The name
+   comes from the activity log, so the profile may have been deleted since. */
+fn apply_last_profile_menu(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
+    let Some(name) = db::last_applied_profile(conn, username)? else {
+        println!("You have not applied a profile yet. Use Choose Profile to pick one.");
+        wait_for_enter();
+        return Ok(());
+    };
+    let Some(row) = db::get_profile_row(conn, &name)? else {
+        println!("❌ Your last profile '{}' no longer exists. Please choose another profile.", name);
+        wait_for_enter();
+        return Ok(());
+    };
+    if !confirm(&format!("Apply your last profile '{}'?", name), true) {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    apply_selected_profile(conn, username, user_role, &row)
+}
+
 // Helper function to validate date format mm-dd-yyyy
 fn validate_date_format(date_str: &str) -> bool {
     let parts: Vec<&str> = date_str.split('-').collect();
@@ -286,7 +365,7 @@ fn validate_date_format(date_str: &str) -> bool {
 pub fn main_menu(conn: &mut Connection, username: &str, role: &str) -> Result<()> {
     // Check if vacation mode is active for guests and technicians
     if role == "guest" || role == "technician" {
-        if is_vacation_mode_active(conn)? {
+        if db::vacation_mode_active(conn)? {
             println!("\n🏖️ ═══════════════════════════════════════════════════════");
             println!("   VACATION MODE ACTIVE - ACCESS RESTRICTED");
             println!("   ═══════════════════════════════════════════════════════");
@@ -312,29 +391,50 @@ pub fn main_menu(conn: &mut Connection, username: &str, role: &str) -> Result<()
         }
     }
     
+    crate::units::set_temp_precision(db::get_temp_precision(conn, username).unwrap_or(crate::units::DEFAULT_TEMP_PRECISION));
+    crate::profile::reset_greeting_memory();
+    crate::profile::resume_from_idle_away(conn, username, role);
+
     loop {
+        if role != "admin" {
+            crate::profile::revert_expired_override(conn, username, role);
+        }
+        // The daily schedule is the homeowner's; guests and technicians never trigger a switch
+        if role == "homeowner" {
+            let mut hvac = HVACSystem::new(conn);
+            crate::profile::apply_scheduled_profile_if_due(conn, &mut hvac, username, role);
+        }
+        // Reloaded each pass so layout changes show up without logging out
+        let items = menu_config::load_menu(conn, role)?;
         match role {
             "homeowner" => {
-                ui::homeowner_ui();
-                if !homeowner_menu(conn, username, role)? {
+                show_shortcuts(conn, username);
+                let logins = db::unread_guest_login_notices(conn, username).map(|n| n.len()).unwrap_or(0);
+                if logins > 0 {
+                    println!("🔔 {} new guest login(s). See [C] View Guest Notes.", logins);
+                }
+                ui::homeowner_ui(&items);
+                if !homeowner_menu(conn, username, role, &items)? {
                     break;
                 }
             }
             "guest" => {
-                ui::guest_ui();
-                if !guest_menu(conn, username, role)? {
+                show_shortcuts(conn, username);
+                ui::guest_ui(&items);
+                if !guest_menu(conn, username, role, &items)? {
                     break;
                 }
             }
             "technician" => {
-                ui::technician_ui();
-                if !technician_menu(conn, username, role)? {
+                show_shortcuts(conn, username);
+                ui::technician_ui(&items);
+                if !technician_menu(conn, username, role, &items)? {
                     break;
                 }
             }
             "admin" => {
-                ui::admin_ui();
-                if !admin_menu(conn, username, role)? {
+                ui::admin_ui(&items);
+                if !admin_menu(conn, username, role, &items)? {
                     break;
                 }
             }
@@ -344,13 +444,220 @@ pub fn main_menu(conn: &mut Connection, username: &str, role: &str) -> Result<()
             }
         }
     }
+    crate::units::set_temp_precision(crate::units::DEFAULT_TEMP_PRECISION);
+    crate::profile::reset_greeting_memory();
+    Ok(())
+}
+
+// No input at a role menu: idle timeout logs out to the front page, EOF just exits.
+fn menu_input_closed(conn: &Connection, role: &str) -> Result<bool> {
+    if input_timed_out() {
+        let secs = inactivity_timeout_secs(conn, role).unwrap_or_else(|_| default_inactivity_timeout_secs(role));
+        println!("\n⏱️  No input for {} seconds. Logging out...", secs);
+        auth::logout_user(conn)?;
+        ui::front_page_ui(conn);
+    } else {
+        println!("End of input detected. Exiting...");
+    }
+    Ok(false)
+}
+
+// ===============================================================
+//                         MENU SHORTCUTS
+// ===============================================================
+const SHORTCUT_KEYS: [&str; 3] = ["X", "Y", "Z"];
+
+fn show_shortcuts(conn: &Connection, username: &str) {
+    let shortcuts = db::list_menu_shortcuts(conn, username).unwrap_or_default();
+    let items: Vec<(&str, &str)> = shortcuts
+        .iter()
+        .filter_map(|(slot, action)| {
+            let key = SHORTCUT_KEYS.get((*slot - 1) as usize)?;
+            Some((*key, db::shortcut_label(action)?))
+        })
+        .collect();
+    ui::shortcuts_ui(&items);
+}
+
+// Resolve a pressed shortcut key to the action pinned in that slot and run it
+fn run_shortcut(conn: &mut Connection, username: &str, role: &str, key: &str) -> Result<()> {
+    let Some(slot) = SHORTCUT_KEYS.iter().position(|k| *k == key).map(|i| i as i64 + 1) else {
+        println!("Unknown shortcut.");
+        return Ok(());
+    };
+    let action = db::list_menu_shortcuts(conn, username)?
+        .into_iter()
+        .find(|(s, _)| *s == slot)
+        .map(|(_, a)| a);
+    let Some(action) = action else {
+        println!("No shortcut pinned to [{}]. Use [S] to set one.", key);
+        return Ok(());
+    };
+    if !db::shortcut_allowed_for_role(&action, role) {
+        println!("This shortcut is not available for your role.");
+        return Ok(());
+    }
+    if !menu_config::shortcut_visible(&menu_config::load_menu(conn, role)?, &action) {
+        println!("This shortcut's menu option has been hidden by an administrator.");
+        return Ok(());
+    }
+
+    match action.as_str() {
+        "apply_day" | "apply_night" | "apply_sleep" => {
+            let profile = match action.as_str() {
+                "apply_day" => HVACProfile::Day,
+                "apply_night" => HVACProfile::Night,
+                _ => HVACProfile::Sleep,
+            };
+            if !hvac_change_allowed(conn, username, &format!("profile '{:?}'", profile), None)? {
+                return Ok(());
+            }
+            let mut hvac = HVACSystem::for_user(conn, username);
+            apply_profile(conn, &mut hvac, profile, username, role);
+        }
+        "view_weather" => {
+            println!("Retrieving outdoor weather status...");
+            if let Err(e) = weather::get_current_weather(conn, username) {
+                eprintln!("❌ Error: {:?}", e);
+            }
+        }
+        "indoor_sensing" => {
+            println!("🌡 Checking indoor temperature...");
+            if let Err(e) = senser::run_dashboard_inline(dashboard_thresholds(conn)) {
+                eprintln!("dashboard error: {e}");
+            }
+        }
+        "system_status" => show_system_status(conn, username, role)?,
+        "nudge_up" | "nudge_down" => {
+            let mut hvac = HVACSystem::for_user(conn, username);
+            if !matches!(hvac.mode, HVACMode::Heating | HVACMode::Cooling | HVACMode::Auto) {
+                println!("Temperature nudge is not available in {:?} mode.", hvac.mode);
+                return Ok(());
+            }
+            let old_temp = hvac.target_temperature;
+            let delta = if action == "nudge_up" { 1.0 } else { -1.0 };
+            let new_temp = old_temp + delta;
+            if !hvac.mode.is_valid_temperature_for_mode(new_temp) {
+                let (min_t, max_t) = hvac.mode.temperature_range();
+                println!("❌ {:.1}°C is outside the {:?} range ({:.0}-{:.0}°C).", new_temp, hvac.mode, min_t, max_t);
+                return Ok(());
+            }
+            if !hvac_change_allowed(conn, username, &format!("{:?} nudge", hvac.mode), Some(new_temp))? {
+                return Ok(());
+            }
+            hvac.set_target_temperature(conn, new_temp);
+            let new_temp = hvac.target_temperature;
+            let _ = db::log_temperature_changed(conn, username, role, old_temp, new_temp);
+            println!("✅ Target temperature {:.1}°C → {:.1}°C", old_temp, new_temp);
+        }
+        _ => println!("Unknown shortcut action '{}'.", action),
+    }
+    Ok(())
+}
+
+fn manage_shortcuts_menu(conn: &mut Connection, username: &str, role: &str) -> Result<()> {
+    loop {
+        println!("\n======= Menu Shortcuts =======");
+        let current = db::list_menu_shortcuts(conn, username)?;
+        for (i, key) in SHORTCUT_KEYS.iter().enumerate() {
+            let slot = i as i64 + 1;
+            let label = current
+                .iter()
+                .find(|(s, _)| *s == slot)
+                .and_then(|(_, a)| db::shortcut_label(a))
+                .unwrap_or("(empty)");
+            println!("Slot {} [{}]: {}", slot, key, label);
+        }
+        println!("\n[1-3] Set slot   [C] Clear a slot   [Q] Back");
+        print!("Select an option: ");
+
+        let Some(choice) = prompt_input() else { break };
+        match choice.trim().to_uppercase().as_str() {
+            "1" | "2" | "3" => {
+                let slot: i64 = choice.trim().parse().unwrap_or(1);
+                let available: Vec<&(&str, &str)> = db::SHORTCUT_ACTIONS
+                    .iter()
+                    .filter(|(a, _)| db::shortcut_allowed_for_role(a, role))
+                    .collect();
+                println!("\nAvailable actions:");
+                for (i, (_, label)) in available.iter().enumerate() {
+                    println!("[{}] {}", i + 1, label);
+                }
+                print!("Choose an action [1-{}]: ", available.len());
+                let Some(pick) = prompt_input() else { break };
+                match parse_menu_index(&pick, available.len()) {
+                    Some(i) => {
+                        let (action, label) = available[i];
+                        match db::set_menu_shortcut(conn, username, slot, action) {
+                            Ok(()) => println!("✅ Slot {} now runs: {}", slot, label),
+                            Err(e) => println!("❌ {}", e),
+                        }
+                    }
+                    _ => println!("❌ Invalid action selection."),
+                }
+            }
+            "C" => {
+                print!("Slot to clear [1-3]: ");
+                let Some(slot) = prompt_input() else { break };
+                match slot.trim().parse::<i64>() {
+                    Ok(n) if db::clear_menu_shortcut(conn, username, n)? => println!("Slot {} cleared.", n),
+                    _ => println!("Nothing to clear."),
+                }
+            }
+            "Q" => break,
+            _ => println!("Invalid choice, please try again."),
+        }
+    }
+    Ok(())
+}
+
+// Flip between the verbose and compact HVAC banners
+fn toggle_display_mode(conn: &Connection, username: &str) -> Result<()> {
+    let next = match db::get_display_mode(conn, username)? {
+        db::DisplayMode::Verbose => db::DisplayMode::Compact,
+        db::DisplayMode::Compact => db::DisplayMode::Verbose,
+    };
+    db::set_display_mode(conn, username, next)?;
+    println!("✅ Display mode set to {}.", next.as_str());
+    Ok(())
+}
+
+// Flip between metric (°C, km/h) and imperial (°F, mph)
+fn toggle_temp_unit(conn: &Connection, username: &str) -> Result<()> {
+    let next = match db::get_temp_unit(conn, username)? {
+        TempUnit::Celsius => TempUnit::Fahrenheit,
+        TempUnit::Fahrenheit => TempUnit::Celsius,
+    };
+    db::set_temp_unit(conn, username, next)?;
+    println!("✅ Units set to {}.", next.as_str());
+    Ok(())
+}
+
+// Flip between one decimal and whole degrees
+fn toggle_temp_precision(conn: &Connection, username: &str) -> Result<()> {
+    let next = if db::get_temp_precision(conn, username)? == 0 { 1 } else { 0 };
+    db::set_temp_precision(conn, username, next)?;
+    crate::units::set_temp_precision(next);
+    println!("✅ Temperatures now shown with {} decimal place(s), e.g. {}.", next, crate::units::format_temp(22.4, TempUnit::Celsius));
+    Ok(())
+}
+
+// Flip between greeting on every apply and only when the profile changes
+fn toggle_greet_once(conn: &Connection, username: &str) -> Result<()> {
+    let next = !db::get_greet_once(conn, username)?;
+    db::set_greet_once(conn, username, next)?;
+    if next {
+        println!("✅ Profile greetings now show once; re-applying the same profile skips them.");
+    } else {
+        println!("✅ Profile greetings now show on every apply.");
+    }
     Ok(())
 }
 
 // ===============================================================
 //                         HOMEOWNER MENU
 // ===============================================================
-fn homeowner_menu(conn: &mut Connection, username: &str, role: &str) -> Result<bool> {
+fn homeowner_menu(conn: &mut Connection, username: &str, role: &str, items: &[MenuItem]) -> Result<bool> {
     match db::get_user_id_and_role(conn, username)? {
     Some((id, role)) if role == "homeowner" => id,
     _ => {
@@ -358,64 +665,99 @@ fn homeowner_menu(conn: &mut Connection, username: &str, role: &str) -> Result<b
         return Ok(false);
         }
     };
-    match prompt_input() {
-        Some(choice) => match choice.trim() {
-            "1" => { 
+    match prompt_menu_choice(conn, role) {
+        Some(choice) => match menu_action(items, role, &choice) {
+            Some("apply_last_profile") => apply_last_profile_menu(conn, username, role)?,
+            Some("view_profile") => { 
                 db::show_own_profile(conn, username)?;
                 wait_for_enter();
             },
-            "2" => {guest::manage_guests_menu(conn, username, role, username)?;}
-            "3" => {
+            Some("manage_guests") => {guest::manage_guests_menu(conn, username, role, username)?;}
+            Some("indoor_sensing") => {
                 println!("🌡 Checking indoor temperature...");
-                if let Err(e) = senser::run_dashboard_inline(senser::Thresholds::default()) {
+                if let Err(e) = senser::run_dashboard_inline(dashboard_thresholds(conn)) {
                     eprintln!("dashboard error: {e}");
                 }
                 wait_for_enter();
             },
 
-            "4" => {
+            Some("outdoor_weather") => {
                 println!("Retrieving outdoor weather status...");
-                if let Err(e) = weather::get_current_weather(conn) {
+                if let Err(e) = weather::get_current_weather(conn, username) {
                     eprintln!("❌ Error: {:?}", e);
                 }
                 wait_for_enter();
             },
-            "5" => {hvac_control_menu(conn, username, role)?;},
-            "6" => {show_system_status(conn, username, role)?;},
-            "7" => {manage_profiles_menu(conn, username, role)?;},
-            "8" => {
+            Some("hvac_control") => {hvac_control_menu(conn, username, role)?;},
+            Some("system_status") => {show_system_status(conn, username, role)?;},
+            Some("profile_settings") => {manage_profiles_menu(conn, username, role)?;},
+            Some("energy_usage") => {
                 if let Err(e) = energy::view_energy_usage(conn, username) {
                         println!("Error generating energy report: {}", e);
                     }
-                    wait_for_enter();
+                    energy_cost_settings_flow(conn, username)?;
             },
-            "9" => { 
+            Some("energy_comparison") => { 
                 if let Err(e) = energy::compare_energy_usage(conn, username) {
                 println!("Error comparing energy usage: {}", e);
                 wait_for_enter(); }
             },
-            "A" => {
+            Some("request_technician") => {
                 technician::homeowner_request_tech(conn)?;
                 wait_for_enter();
             }
-            "B" => {
+            Some("away_report") => {
+                away_report_flow(conn, username)?;
+                wait_for_enter();
+            }
+            Some("active_grants") => {
                 println!("Your active technician access grants:");
                 db::list_active_grants(conn, username)?;
                 wait_for_enter();
             }
+            Some("guest_notes") => {
+                guest::view_guest_notes(conn, username)?;
+                wait_for_enter();
+            }
+            Some("export_jobs") => {
+                technician::export_jobs_menu(conn, username)?;
+                wait_for_enter();
+            }
+            Some("profile_effectiveness") => {
+                crate::profile::show_profile_effectiveness(conn, 30)?;
+                wait_for_enter();
+            }
 
-            "0" => {
+            Some("login_history") => {
+                show_login_history(conn, username)?;
+                wait_for_enter();
+            }
+            Some("my_permissions") => {
+                show_my_permissions(conn, username, role)?;
+                wait_for_enter();
+            }
+            Some("manage_shortcuts") => {manage_shortcuts_menu(conn, username, role)?;}
+            Some("display_mode") => {toggle_display_mode(conn, username)?;}
+            Some("temp_unit") => {toggle_temp_unit(conn, username)?;}
+            Some("temp_precision") => {toggle_temp_precision(conn, username)?;}
+            Some("greet_once") => {toggle_greet_once(conn, username)?;}
+            Some("weather_location") => {
+                set_weather_location_flow(conn, username)?;
+                wait_for_enter();
+            }
+            Some("shortcut") => {
+                run_shortcut(conn, username, role, &choice.trim().to_ascii_uppercase())?;
+                wait_for_enter();
+            }
+            Some("logout") => {
                 println!("Logging out...");
                 auth::logout_user(conn)?;
-                ui::front_page_ui();
+                ui::front_page_ui(conn);
                 return Ok(false);
             }
             _ => println!("Invalid choice, please try again.\n"),
         },
-        None => {
-            println!("End of input detected. Exiting...");
-            return Ok(false);
-        }
+        None => return menu_input_closed(conn, role),
     }
     Ok(true)
 }
@@ -423,44 +765,98 @@ fn homeowner_menu(conn: &mut Connection, username: &str, role: &str) -> Result<b
 // ===============================================================
 //                         ADMIN MENU
 // ===============================================================
-fn admin_menu(conn: &mut Connection, username: &str, role: &str) -> Result<bool> {
-    match prompt_input() {
-        Some(choice) => match choice.trim() {
-            "1" => { 
-                db::show_own_profile(conn, username)?;
+fn admin_menu(conn: &mut Connection, username: &str, role: &str, items: &[MenuItem]) -> Result<bool> {
+    match prompt_menu_choice(conn, role) {
+        Some(choice) => match menu_action(items, role, &choice) {
+            Some("view_profile") => {
+                print!("Username to view (Enter for your own): ");
+                io::stdout().flush().ok();
+                match prompt_input().as_deref().map(str::trim) {
+                    Some(target) if !target.is_empty() && !target.eq_ignore_ascii_case(username) => {
+                        db::show_user_profile(conn, username, role, target)?;
+                    }
+                    Some(_) => db::show_own_profile(conn, username)?,
+                    None => {}
+                }
                 wait_for_enter();
             },
-            "2" => {
+            Some("register_user") => {
                 println!("Registering a new user...");
                 auth::register_user(conn, Some((username, role)))?;
             }
-            "3" => {
-                println!("Viewing all users...");
-                db::view_all_users(conn, role)?;
+            Some("view_users") => {
+                print!("Search by username or role (Enter for all users): ");
+                io::stdout().flush().ok();
+                match prompt_input().as_deref().map(str::trim) {
+                    Some("") => {
+                        println!("Viewing all users...");
+                        db::view_all_users(conn, role)?;
+                    }
+                    Some(query) => db::view_user_search(conn, role, query)?,
+                    None => {}
+                }
                 wait_for_enter();
                 },
-            "4" => {
+            Some("manage_users") => {
                 println!("Managing users...");
                 db::manage_user_status(conn, username, role)?;
             },
             
-            "5" => {
+            Some("security_logs") => {
                 println!("Viewing security logs...");
                 logger::view_security_log(conn, username, role)?;
                 wait_for_enter();
             }
-            "6" => {
-                println!("Checking current lockouts...");
-                //show all locked accounts
-                logger::clear_lockout(conn, "admin", None)?;
+            Some("consistency_check") => {
+                println!("\n=== Database Consistency Check ===");
+                match db::logical_integrity_check(conn) {
+                    Ok(problems) if problems.is_empty() => println!("✅ No consistency problems found."),
+                    Ok(problems) => {
+                        println!("⚠️  {} problem(s) found:", problems.len());
+                        for p in &problems {
+                            println!("   • {}", p);
+                        }
+                    }
+                    Err(e) => println!("❌ Consistency check failed: {}", e),
+                }
+                wait_for_enter();
+            }
+            Some("logout_user") => {
+                print!("Username to log out of every session: ");
+                io::stdout().flush()?;
+                if let Some(target) = prompt_input() {
+                    let target = target.trim();
+                    if target.is_empty() {
+                        println!("No username entered. Returning to menu.");
+                    } else if confirm(&format!("End every session of '{}'?", target), false) {
+                        match db::logout_all_for_user(conn, username, target) {
+                            Ok(0) => println!("'{}' had no sessions (logged).", target),
+                            Ok(n) => println!("✅ Ended {} session(s) for '{}' (logged).", n, target),
+                            Err(e) => println!("❌ {}", e),
+                        }
+                    }
+                }
+                wait_for_enter();
+            }
+            Some("clear_lockouts") => {
+                println!("Checking current lockouts...");
+                //show all locked accounts
+                logger::clear_lockout(conn, "admin", None)?;
 
-                // ask admin if they want to clear a specific user
-                println!("\nEnter username to clear lockout (or press Enter to cancel): ");
+                // ask admin if they want to clear a specific user, or everyone
+                println!("\nEnter username to clear lockout, 'all' to clear every lockout (or press Enter to cancel): ");
                 let target = prompt_input();
 
                 if let Some(user_input) = target {
                     let trimmed = user_input.trim();
-                    if !trimmed.is_empty() {
+                    if trimmed.eq_ignore_ascii_case("all") {
+                        if confirm("Clear every active lockout?", false) {
+                            match logger::clear_all_lockouts(conn, username) {
+                                Ok(n) => println!("Cleared {} lockout(s) (logged).", n),
+                                Err(e) => println!("❌ {}", e),
+                            }
+                        }
+                    } else if !trimmed.is_empty() {
                         // Call again with Some(username)
                         logger::clear_lockout(conn, "admin", Some(trimmed))?;
                     } else {
@@ -470,18 +866,112 @@ fn admin_menu(conn: &mut Connection, username: &str, role: &str) -> Result<bool>
                     println!("No input detected. Returning to menu.");
                 }
             }
-            "0" => {
+            Some("reset_password") => {
+                println!("\nEnter username to reset password (or press Enter to cancel): ");
+                match prompt_input() {
+                    Some(user_input) if !user_input.trim().is_empty() => {
+                        db::admin_reset_password(conn, username, user_input.trim())?;
+                    }
+                    _ => println!("No username entered. Returning to menu."),
+                }
+                wait_for_enter();
+            }
+            Some("diagnostics_history") => {
+                diagnostic::view_diagnostics_history(conn, role, 20)?;
+                wait_for_enter();
+            }
+            Some("activity_feed") => {
+                logger::view_activity_feed(conn, role)?;
+                wait_for_enter();
+            }
+            Some("login_history") => {
+                show_login_history(conn, username)?;
+                wait_for_enter();
+            }
+            Some("my_permissions") => {
+                show_my_permissions(conn, username, role)?;
+                wait_for_enter();
+            }
+            Some("strict_guest_login") => {
+                let enabled = !db::strict_guest_owner_check(conn)?;
+                db::set_strict_guest_owner_check(conn, username, enabled)?;
+                if enabled {
+                    println!("✅ Strict guest login ON: guests of disabled homeowners cannot log in.");
+                } else {
+                    println!("✅ Strict guest login OFF: only the guest's own status is checked.");
+                }
+                wait_for_enter();
+            }
+            Some("away_on_last_logout") => {
+                let enabled = !db::away_on_last_logout(conn)?;
+                db::set_away_on_last_logout(conn, username, enabled)?;
+                if enabled {
+                    println!("✅ Away on last logout ON: the Away profile is applied when no one is signed in.");
+                } else {
+                    println!("✅ Away on last logout OFF: the current profile stays after everyone logs out.");
+                }
+                wait_for_enter();
+            }
+            Some("lockout_policy") => {
+                edit_lockout_policy_flow(conn, username)?;
+                wait_for_enter();
+            }
+            Some("freeze_guard") => {
+                set_freeze_threshold_flow(conn, username)?;
+                wait_for_enter();
+            }
+            Some("weather_location") => {
+                set_weather_location_flow(conn, username)?;
+                wait_for_enter();
+            }
+            Some("anonymize_user") => {
+                println!("\nEnter username to anonymize (or press Enter to cancel): ");
+                match prompt_input() {
+                    Some(user_input) if !user_input.trim().is_empty() => {
+                        let target = user_input.trim();
+                        println!("'{}' will be renamed to a pseudonym and disabled. Logs are kept.", target);
+                        if confirm("⚠️  Anonymize this account?", false) {
+                            let reason = db::prompt_admin_reason();
+                            match db::anonymize_user(conn, username, target, reason.as_deref()) {
+                                Ok(()) => println!("✅ '{}' has been anonymized.", target),
+                                Err(e) => println!("{}", e),
+                            }
+                        } else {
+                            println!("Anonymization cancelled.");
+                        }
+                    }
+                    _ => println!("No username entered. Returning to menu."),
+                }
+                wait_for_enter();
+            }
+            Some("sensor_thresholds") => {
+                edit_thresholds_flow(conn, username)?;
+                wait_for_enter();
+            }
+            Some("menu_layout") => {
+                edit_menu_layout_flow(conn, username)?;
+                wait_for_enter();
+            }
+            Some("reset_settings") => {
+                println!("This restores every setting (policies, comfort band, user preferences,");
+                println!("shortcuts, mode defaults, menu layouts) to factory defaults. Users, profiles and logs are kept.");
+                if confirm("⚠️  Reset all settings?", false) {
+                    db::reset_settings_to_defaults(conn, username)?;
+                    println!("✅ All settings restored to defaults (logged).");
+                } else {
+                    println!("Reset cancelled.");
+                }
+                wait_for_enter();
+            }
+            Some("logout") => {
                 println!("🔒 Logging out...");
                 auth::logout_user(conn)?;
-                ui::front_page_ui();
+                ui::front_page_ui(conn);
                 return Ok(false);
             }
             _ => println!("⚠️ Invalid choice, please try again.\n"),
         },
-        None => {
-            println!("End of input detected. Exiting...");
-            return Ok(false);
-        }
+        None => return menu_input_closed(conn, role),
     }
     Ok(true)
 }
@@ -489,7 +979,7 @@ fn admin_menu(conn: &mut Connection, username: &str, role: &str) -> Result<bool>
 // ===============================================================
 //                         TECHNICIAN MENU
 // ===============================================================
-fn technician_menu(conn: &mut Connection, username: &str, role: &str) -> Result<bool> {
+fn technician_menu(conn: &mut Connection, username: &str, role: &str, items: &[MenuItem]) -> Result<bool> {
     
  match db::get_user_id_and_role(conn, username)? {
         Some((id, role)) if role == "technician" => id,
@@ -500,24 +990,24 @@ fn technician_menu(conn: &mut Connection, username: &str, role: &str) -> Result<
     };
 
     
-    match prompt_input() {
-        Some(choice) => match choice.trim() {
-            "1" => { 
+    match prompt_menu_choice(conn, role) {
+        Some(choice) => match menu_action(items, role, &choice) {
+            Some("view_profile") => { 
                 db::show_own_profile(conn, username)?;
                 wait_for_enter();
             },
-            "2" => {
+            Some("view_jobs") => {
                 if let Err(e) = technician::tech_list_my_jobs(conn) {
                 eprintln!("Error: {e}");
             }
             wait_for_enter();}
 
-            "3" => {    
+            Some("access_job") => {    
             println!("Use a homeowner access grant…");
             if let Err(e) = technician::tech_access_job(conn) {
             eprintln!("Error: {e}");}
             },
-            "4" => {    
+            Some("manage_guests") => {    
                 println!("Enter homeowner username to manage their guests:");
             if let Some(h_in) = prompt_input() {
             let homeowner_username = h_in.trim();
@@ -538,213 +1028,914 @@ fn technician_menu(conn: &mut Connection, username: &str, role: &str) -> Result<
             }
             } else { println!("Cancelled."); }
         }
-            "5" => {
+            Some("run_diagnostics") => {
                 println!("Running diagnostics...");
-                let _ = diagnostic::run_diagnostics();
+                if let Err(e) = diagnostic::run_diagnostics(conn, username) {
+                    println!("Diagnostics error: {}", e);
+                }
                 wait_for_enter();
             },
-            "6" => {
+            Some("system_status") => {
                 show_system_status(conn, username, role)?;
             },
-            "7"  => {
+            Some("indoor_sensing") => {
                 println!("🌡 Checking indoor temperature...");
-                if let Err(e) = senser::run_dashboard_inline(senser::Thresholds::default()) {
+                if let Err(e) = senser::run_dashboard_inline(dashboard_thresholds(conn)) {
                     eprintln!("dashboard error: {e}");
                 }
                 wait_for_enter();
             },
-            "8" => {
+            Some("outdoor_weather") => {
                 println!("Outdoor weather data...");
-                if let Err(e) = weather::get_current_weather(conn) {
+                if let Err(e) = weather::get_current_weather(conn, username) {
                     eprintln!("❌ Error: {:?}", e);
                 }
-                wait_for_enter();
-            },
-            "9" => {
-                manage_profiles_menu(conn, username, role)?;
-            },
-            "0" => {
-                println!("Logging out...");
-                auth::logout_user(conn)?;
-                ui::front_page_ui();
-                return Ok(false);
+                wait_for_enter();
+            },
+            Some("profile_settings") => {
+                manage_profiles_menu(conn, username, role)?;
+            },
+            Some("diagnostics_history") => {
+                diagnostic::view_diagnostics_history(conn, role, 20)?;
+                wait_for_enter();
+            }
+            Some("export_jobs") => {
+                technician::export_jobs_menu(conn, username)?;
+                wait_for_enter();
+            }
+            Some("login_history") => {
+                show_login_history(conn, username)?;
+                wait_for_enter();
+            }
+            Some("my_permissions") => {
+                show_my_permissions(conn, username, role)?;
+                wait_for_enter();
+            }
+            Some("manage_shortcuts") => {manage_shortcuts_menu(conn, username, role)?;}
+            Some("display_mode") => {toggle_display_mode(conn, username)?;}
+            Some("temp_unit") => {toggle_temp_unit(conn, username)?;}
+            Some("temp_precision") => {toggle_temp_precision(conn, username)?;}
+            Some("greet_once") => {toggle_greet_once(conn, username)?;}
+            Some("shortcut") => {
+                run_shortcut(conn, username, role, &choice.trim().to_ascii_uppercase())?;
+                wait_for_enter();
+            }
+            Some("logout") => {
+                println!("Logging out...");
+                auth::logout_user(conn)?;
+                ui::front_page_ui(conn);
+                return Ok(false);
+            }
+            _ => println!("Invalid choice, please try again.\n"),
+        },
+        None => return menu_input_closed(conn, role),
+    }
+    Ok(true)
+}
+// ===============================================================
+//                         GUEST MENU
+// ===============================================================
+fn guest_menu(conn: &mut Connection, username: &str, role: &str, items: &[MenuItem]) -> Result<bool> {
+    match prompt_menu_choice(conn, role) {
+        Some(choice) => match menu_action(items, role, &choice) {
+            Some("apply_last_profile") => apply_last_profile_menu(conn, username, role)?,
+            Some("view_profile") => { 
+                db::show_own_profile(conn, username)?;
+                wait_for_enter();},
+            Some("indoor_sensing") => {
+                println!("🌡 Checking indoor temperature...");
+                if let Err(e) = senser::run_dashboard_inline(dashboard_thresholds(conn)) {
+                    eprintln!("dashboard error: {e}");
+                }
+                wait_for_enter();
+            },
+            Some("outdoor_weather") => {
+            println!("Retrieving outdoor weather statu...");
+                if let Err(e) = weather::get_current_weather(conn, username) {
+                    eprintln!("❌ Error: {:?}", e);
+                }
+                wait_for_enter();
+            },
+            Some("hvac_control") => hvac_control_menu(conn, username, role)?,
+            Some("choose_profile") => {
+                profile_selection_menu(conn, username, role)?;
+            },
+            Some("leave_note") => {
+                guest::leave_note_for_homeowner(conn, username)?;
+                wait_for_enter();
+            },
+            Some("login_history") => {
+                show_login_history(conn, username)?;
+                wait_for_enter();
+            }
+            Some("manage_shortcuts") => {manage_shortcuts_menu(conn, username, role)?;}
+            Some("display_mode") => {toggle_display_mode(conn, username)?;}
+            Some("temp_unit") => {toggle_temp_unit(conn, username)?;}
+            Some("temp_precision") => {toggle_temp_precision(conn, username)?;}
+            Some("greet_once") => {toggle_greet_once(conn, username)?;}
+            Some("shortcut") => {
+                run_shortcut(conn, username, role, &choice.trim().to_ascii_uppercase())?;
+                wait_for_enter();
+            }
+            Some("logout") => {
+                println!("🔒 Logging out...");
+                auth::logout_user(conn)?;
+                ui::front_page_ui(conn);
+                return Ok(false);
+            }
+            _ => println!("Invalid choice, please try again.\n"),
+        },
+        None => return menu_input_closed(conn, role),
+    }
+    Ok(true)
+}
+
+// ===============================================================
+//                         HVAC CONTROL MENU
+// ===============================================================
+fn hvac_control_menu(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
+    let mut hvac = hvac::HVACSystem::for_user(conn, username);
+    
+    loop {
+        ui::hvac_control_ui(user_role);
+
+        match prompt_input() {
+            Some(choice) => match choice.trim() {
+                "1" => {
+                    println!("\n🌡️  Select HVAC Mode:");
+                    println!("[1] 🔥 Heating  [2] ❄️  Cooling  [3] 🤖 Auto  [4] 💨 Fan Only  [5] ⭕ Off");
+                    if let Some(mode) = prompt_input() {
+                        let new_mode = match mode.trim() {
+                            "1" => hvac::HVACMode::Heating,
+                            "2" => hvac::HVACMode::Cooling,
+                            "3" => hvac::HVACMode::Auto,
+                            "4" => hvac::HVACMode::FanOnly,
+                            "5" => hvac::HVACMode::Off,
+                            _ => {
+                                println!("❌ Invalid mode selection");
+                                continue;
+                            }
+                        };
+                        
+                        if new_mode == hvac::HVACMode::Off && hvac.mode != hvac::HVACMode::Off
+                            && !confirm_switch_off(conn, username)?
+                        {
+                            println!("Mode unchanged.");
+                            continue;
+                        }
+
+                        let old_mode_str = format!("{:?}", hvac.mode);
+                        let old_temp = hvac.target_temperature;
+                        
+                        // Set temperature for modes that need it (not Off or FanOnly)
+                        if matches!(new_mode, hvac::HVACMode::Heating | hvac::HVACMode::Cooling | hvac::HVACMode::Auto) {
+                            let (min_temp, max_temp) = new_mode.temperature_range();
+                            let default_temp = db::mode_default_temperature(conn, new_mode)?;
+                            // Shown and typed in the user's unit; validated and stored in Celsius
+                            let unit = db::get_temp_unit(conn, username).unwrap_or_default();
+                            println!("\n🌡️  Enter target temperature for {:?} mode ({}):", new_mode,
+                                     crate::units::format_temp_range(min_temp, max_temp, unit));
+                            print!("Temperature [{}]: ", crate::units::format_temp(default_temp, unit));
+                            io::stdout().flush()?;
+                            
+                            if let Some(temp_str) = prompt_input() {
+                                // Enter accepts the pre-filled default for this mode
+                                let parsed = match temp_str.trim() {
+                                    "" => Some(default_temp),
+                                    t => crate::units::parse_temp_input(t, unit),
+                                };
+                                if let Some(temp) = parsed {
+                                    if new_mode.is_valid_temperature_for_mode(temp) {
+                                        if !hvac_change_allowed(conn, username, &format!("{:?}", new_mode), Some(temp))? {
+                                            continue;
+                                        }
+                                        hvac.set_mode(conn, new_mode);
+                                        hvac.set_target_temperature(conn, temp);
+                                        // What was applied, after any guest clamp
+                                        let temp = hvac.target_temperature;
+                                        // The per-mode default is house-wide, so guests never change it
+                                        if user_role != "guest" {
+                                            let _ = db::set_mode_default_temperature(conn, new_mode, temp);
+                                        }
+                                        
+                                        // Prompt for light status
+                                        println!("\n💡 Light/Lamp: [1] ON  [2] OFF");
+                                        print!("Choice: ");
+                                        io::stdout().flush()?;
+                                        if let Some(light_choice) = prompt_input() {
+                                            let light_status = match light_choice.trim() {
+                                                "1" => "ON",
+                                                "2" => "OFF",
+                                                _ => "OFF", // Default to OFF
+                                            };
+                                            hvac.set_light_status(conn, light_status);
+                                        }
+                                        
+                                        // Log both changes
+                                        let new_mode_str = format!("{:?}", new_mode);
+                                        let _ = db::log_mode_changed(conn, username, user_role, &old_mode_str, &new_mode_str);
+                                        let _ = db::log_temperature_changed(conn, username, user_role, old_temp, temp);
+                                        
+                                        println!("✅ Mode set to {:?} with target {}, Light: {}", new_mode,
+                                                 crate::units::format_temp(temp, unit), hvac.light_status);
+                                    } else {
+                                        println!("❌ Invalid temperature for {:?} mode! Must be within {}", 
+                                                 new_mode, crate::units::format_temp_range(min_temp, max_temp, unit));
+                                        continue;
+                                    }
+                                } else {
+                                    println!("❌ Invalid temperature value");
+                                    continue;
+                                }
+                            }
+                        } else {
+                            // Fan Only or Off - just set mode and light, no temperature needed
+                            if !hvac_change_allowed(conn, username, &format!("{:?}", new_mode), None)? {
+                                continue;
+                            }
+                            hvac.set_mode(conn, new_mode);
+                            
+                            // Prompt for light status
+                            println!("\n💡 Light/Lamp: [1] ON  [2] OFF");
+                            print!("Choice: ");
+                            io::stdout().flush()?;
+                            if let Some(light_choice) = prompt_input() {
+                                let light_status = match light_choice.trim() {
+                                    "1" => "ON",
+                                    "2" => "OFF",
+                                    _ => "OFF", // Default to OFF
+                                };
+                                hvac.set_light_status(conn, light_status);
+                            }
+                            
+                            let new_mode_str = format!("{:?}", new_mode);
+                            let _ = db::log_mode_changed(conn, username, user_role, &old_mode_str, &new_mode_str);
+                            println!("✅ Mode set to {:?}, Light: {}", new_mode, hvac.light_status);
+                        }
+                    }
+                }
+                "H" | "h" => {
+                    hold_temperature_flow(conn, &mut hvac, username, user_role)?;
+                    wait_for_enter();
+                }
+                "U" | "u" => {
+                    show_upcoming_schedule(conn, &mut hvac, username, user_role)?;
+                    wait_for_enter();
+                }
+                "P" | "p" => {
+                    if hvac_change_allowed(conn, username, "presence change", None)? {
+                        toggle_presence(conn, username)?;
+                    }
+                    wait_for_enter();
+                }
+                "B" | "b" if user_role != "guest" => {
+                    set_comfort_band_flow(conn, &mut hvac, username)?;
+                    wait_for_enter();
+                }
+                "F" | "f" => {
+                    fan_boost_flow(conn, username)?;
+                    wait_for_enter();
+                }
+                "E" | "e" if user_role != "guest" => {
+                    if confirm("⚠️  Switch the HVAC, light, holds and fan boosts off right now?", false) {
+                        hvac.emergency_all_off(conn);
+                        println!("🛑 Emergency all-off done. Indoor readings are being recorded for the cool-down report.");
+                    }
+                    wait_for_enter();
+                }
+                "C" | "c" if user_role != "guest" => {
+                    show_cooldown_report(conn)?;
+                    wait_for_enter();
+                }
+                "2" => {
+                    let display = db::get_display_mode(conn, username).unwrap_or_default();
+                    hvac.update_with_display(conn, display);
+                    wait_for_enter();
+                }
+                "3" => {
+                    // Option 3 behavior depends on user role
+                    if user_role == "homeowner" {
+                        // Homeowners: Choose Profile
+                        profile_selection_menu(conn, username, user_role)?;
+                        // Reload HVAC state from database after profile change
+                        hvac = hvac::HVACSystem::for_user(conn, username);
+                    } else if user_role == "guest" {
+                        // Guests: Return to Main Menu (they have Choose Profile in main menu)
+                        break;
+                    } else {
+                        // Technicians: Run Diagnostics
+                        hvac.diagnostics(conn);
+                        wait_for_enter();
+                    }
+                }
+                "4" => {
+                    // Option 4 is "Return to Main Menu" for homeowners and technicians
+                    break;
+                }
+                _ => println!("Invalid option. Please try again."),
+            },
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+// Admin view/edit of the password-account lockout policy
+fn edit_lockout_policy_flow(conn: &Connection, admin_username: &str) -> Result<()> {
+    let current = logger::load_lockout_policy(conn)?;
+    println!("\n===== Lockout Policy =====");
+    println!("Max failed attempts : {}", current.max_attempts);
+    println!("Base lockout (s)    : {}", current.base_lockout_secs);
+    println!("Lockout cap (s)     : {}", current.max_lockout_secs);
+    println!("(Guest PIN policy is fixed.) Leave a field blank to keep it.");
+
+    let ask = |label: &str, value: i64| -> Option<i64> {
+        print!("{} [{}]: ", label, value);
+        io::stdout().flush().ok();
+        let input = prompt_input()?;
+        match input.trim() {
+            "" => Some(value),
+            v => v.parse::<i64>().ok().or_else(|| { println!("❌ Not a number."); None }),
+        }
+    };
+    let Some(max_attempts) = ask("Max failed attempts", current.max_attempts) else { return Ok(()); };
+    let Some(base_lockout_secs) = ask("Base lockout (s)", current.base_lockout_secs) else { return Ok(()); };
+    let Some(max_lockout_secs) = ask("Lockout cap (s)", current.max_lockout_secs) else { return Ok(()); };
+
+    let policy = logger::LockoutPolicy { max_attempts, base_lockout_secs, max_lockout_secs };
+    if policy == current {
+        println!("No changes.");
+        return Ok(());
+    }
+    match logger::save_lockout_policy(conn, admin_username, &policy) {
+        Ok(()) => println!("✅ Lockout policy updated (logged)."),
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+// Admin: hide, show or move items of one role's main menu
+fn edit_menu_layout_flow(conn: &Connection, admin_username: &str) -> Result<()> {
+    print!("Role to edit [admin | homeowner | technician | guest]: ");
+    io::stdout().flush().ok();
+    let Some(role_in) = prompt_input() else { return Ok(()); };
+    let role = role_in.trim().to_lowercase();
+    if menu_config::default_menu(&role).is_empty() {
+        println!("❌ Unknown role '{}'.", role);
+        return Ok(());
+    }
+
+    loop {
+        println!("\n===== {} menu layout =====", role);
+        println!("Key    Option                       Pos    Shown");
+        for (item, position, hidden) in menu_config::menu_layout(conn, &role)? {
+            println!("{:<6} {:<28} {:<6} {}", format!("[{}]", item.key), item.label, position, if hidden { "no" } else { "yes" });
+        }
+        println!("\n[H] Hide/show an option  [M] Move an option  [R] Restore defaults  [0] Done");
+        print!("Choice: ");
+        io::stdout().flush().ok();
+        let Some(choice) = prompt_input() else { return Ok(()); };
+        let result = match choice.trim().to_uppercase().as_str() {
+            "H" => {
+                print!("Key of the option: ");
+                io::stdout().flush().ok();
+                let Some(key) = prompt_input() else { return Ok(()); };
+                let hidden = menu_config::menu_layout(conn, &role)?
+                    .iter()
+                    .any(|(item, _, hidden)| *hidden && item.key.eq_ignore_ascii_case(key.trim()));
+                menu_config::set_menu_item_hidden(conn, admin_username, &role, key.trim(), !hidden)
+            }
+            "M" => {
+                print!("Key of the option: ");
+                io::stdout().flush().ok();
+                let Some(key) = prompt_input() else { return Ok(()); };
+                print!("New position (lower prints first): ");
+                io::stdout().flush().ok();
+                let Some(pos) = prompt_input() else { return Ok(()); };
+                match pos.trim().parse::<i64>() {
+                    Ok(position) => menu_config::set_menu_item_position(conn, admin_username, &role, key.trim(), position),
+                    Err(_) => { println!("❌ Not a number."); continue; }
+                }
+            }
+            "R" => menu_config::reset_menu(conn, admin_username, &role).map(|_| ()),
+            "0" => return Ok(()),
+            _ => { println!("⚠️ Invalid choice."); continue; }
+        };
+        match result {
+            Ok(()) => println!("✅ Layout updated (logged)."),
+            Err(e) => println!("❌ {}", e),
+        }
+    }
+}
+
+// Off in freezing weather can burst pipes: warn and record an explicit override
+fn confirm_switch_off(conn: &Connection, username: &str) -> Result<bool> {
+    let outdoor_c = db::latest_weather(conn)?.and_then(|w| w.temperature_c).map(|t| t as f32);
+    let threshold = db::freeze_threshold(conn)?;
+    match hvac::off_switch_check(outdoor_c, threshold) {
+        hvac::OffSwitchCheck::Clear => Ok(true),
+        hvac::OffSwitchCheck::NoWeather => Ok(confirm("No outdoor reading available. Turn the system Off?", false)),
+        hvac::OffSwitchCheck::FreezeRisk(t) => {
+            println!("\n🥶 It is {:.1}°C outside (freeze guard below {:.1}°C).", t, threshold);
+            println!("   Turning the system Off now risks frozen pipes.");
+            if !confirm("Turn Off anyway?", false) {
+                return Ok(false);
+            }
+            logger::log_event(conn, username, None, "HVAC", Some(&format!(
+                "Freeze guard overridden: switched Off at {:.1}°C outdoors (threshold {:.1}°C)", t, threshold
+            )))?;
+            Ok(true)
+        }
+    }
+}
+
+// Admin setting for the freeze guard threshold
+fn set_freeze_threshold_flow(conn: &Connection, admin_username: &str) -> Result<()> {
+    println!("\nFreeze guard threshold: {:.1}°C", db::freeze_threshold(conn)?);
+    print!(
+        "New threshold in °C ({:.0} to {:.0}, blank to keep): ",
+        db::FREEZE_THRESHOLD_MIN, db::FREEZE_THRESHOLD_MAX
+    );
+    io::stdout().flush()?;
+    let Some(value) = prompt_input() else { return Ok(()); };
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+    match crate::units::parse_decimal(value) {
+        Some(t) => match db::set_freeze_threshold(conn, t, admin_username) {
+            Ok(()) => println!("✅ Switching Off below {:.1}°C outdoors now needs confirmation.", t),
+            Err(e) => println!("❌ {}", e),
+        },
+        None => println!("❌ Invalid temperature value"),
+    }
+    Ok(())
+}
+
+// Coordinates (and optionally a station ID) used for outdoor weather
+fn set_weather_location_flow(conn: &Connection, username: &str) -> Result<()> {
+    let current = db::weather_location(conn)?;
+    println!(
+        "\nWeather location: {:.4}, {:.4} (station {})",
+        current.latitude, current.longitude, current.station.as_deref().unwrap_or("nearest")
+    );
+
+    print!("Latitude (-90 to 90, blank to keep): ");
+    io::stdout().flush()?;
+    let Some(lat) = prompt_input() else { return Ok(()); };
+    print!("Longitude (-180 to 180, blank to keep): ");
+    io::stdout().flush()?;
+    let Some(lon) = prompt_input() else { return Ok(()); };
+    print!("Station ID (e.g. KNYC, '-' for nearest station, blank to keep): ");
+    io::stdout().flush()?;
+    let Some(station) = prompt_input() else { return Ok(()); };
+
+    let parse = |input: &str, current: f64| -> Option<f64> {
+        match input.trim() {
+            "" => Some(current),
+            v => v.parse::<f64>().ok(),
+        }
+    };
+    let (Some(latitude), Some(longitude)) = (parse(&lat, current.latitude), parse(&lon, current.longitude)) else {
+        println!("❌ Invalid coordinate value");
+        return Ok(());
+    };
+    let station = match station.trim() {
+        "" => current.station.clone(),
+        "-" => None,
+        s => Some(s.to_uppercase()),
+    };
+
+    let location = weather::WeatherLocation { latitude, longitude, station };
+    if location == current {
+        println!("No changes made.");
+        return Ok(());
+    }
+    match db::set_weather_location(conn, &location, username) {
+        Ok(()) => println!("✅ Weather location updated."),
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+// Live Home/Away switch, independent of whichever profile is applied
+fn toggle_presence(conn: &Connection, username: &str) -> Result<()> {
+    let next = match db::get_presence(conn)? {
+        db::Presence::Home => db::Presence::Away,
+        db::Presence::Away => db::Presence::Home,
+    };
+    db::set_presence(conn, username, next)?;
+    let deadband = crate::hvac::deadband_for(db::comfort_band(conn)?, next);
+    if next == db::Presence::Away {
+        logger::log_event(conn, username, None, "HVAC", Some(&format!("Away: Auto deadband widened to ±{:.1}°C", deadband)))?;
+    }
+    println!(
+        "✅ Presence set to {}. Auto mode now holds within ±{:.1}°C of target.",
+        next.as_str(),
+        deadband
+    );
+    Ok(())
+}
+
+// Wider band = fewer heat/cool cycles, narrower = closer to target
+fn set_comfort_band_flow(conn: &Connection, hvac: &mut HVACSystem, username: &str) -> Result<()> {
+    println!("\nCurrent comfort band: ±{:.1}°C", hvac.comfort_band);
+    print!(
+        "New band in °C ({:.1}-{:.1}, blank to keep): ",
+        db::COMFORT_BAND_MIN, db::COMFORT_BAND_MAX
+    );
+    io::stdout().flush()?;
+    let Some(value) = prompt_input() else { return Ok(()); };
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+    let Some(band) = crate::units::parse_decimal(value) else {
+        println!("❌ Invalid band value");
+        return Ok(());
+    };
+    match db::set_comfort_band(conn, band, username) {
+        Ok(()) => {
+            hvac.comfort_band = band;
+            println!("✅ Auto mode now holds within ±{:.1}°C of target.", band);
+        }
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+// Fan at a chosen speed for a while; the profile's speed applies again afterwards
+fn fan_boost_flow(conn: &Connection, username: &str) -> Result<()> {
+    if !hvac_change_allowed(conn, username, "fan boost", None)? {
+        return Ok(());
+    }
+    if let Some(active) = db::active_fan_override(conn)? {
+        println!(
+            "\n🌀 Fan boosted to {} until {}, set by {}.",
+            active.fan_speed, db::display_time(&active.expires_at), active.set_by
+        );
+        if confirm("End the boost now?", false) {
+            db::end_active_fan_override(conn)?;
+            let _ = logger::log_event(conn, username, None, "HVAC", Some("Fan boost ended early"));
+            println!("✅ Fan back to {}.", db::current_fan_speed(conn)?.as_deref().unwrap_or("its normal setting"));
+        }
+        return Ok(());
+    }
+
+    print!("\n🌀 Fan boost speed [1] Low  [2] Medium  [3] High [3]: ");
+    io::stdout().flush()?;
+    let Some(choice) = prompt_input() else { return Ok(()); };
+    let speed = match choice.trim() {
+        "1" => "Low",
+        "2" => "Medium",
+        "" | "3" => "High",
+        _ => {
+            println!("❌ Invalid fan speed selection");
+            return Ok(());
+        }
+    };
+
+    print!("Boost for how many minutes (1-{}) [15]: ", db::MAX_OVERRIDE_MINUTES);
+    io::stdout().flush()?;
+    let Some(mins_str) = prompt_input() else { return Ok(()); };
+    let minutes = match mins_str.trim() {
+        "" => 15,
+        m => m.parse::<i64>().unwrap_or(0),
+    };
+
+    match db::set_fan_override(conn, username, speed, minutes) {
+        Ok(_) => {
+            let _ = logger::log_event(conn, username, None, "HVAC", Some(&format!("Fan boost: {} for {} min", speed, minutes)));
+            println!("✅ Fan set to {} for {} minutes; the profile's fan speed returns afterwards.", speed, minutes);
+        }
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+// Next day of schedule switches, with the option to drop an active hold
+fn show_upcoming_schedule(conn: &mut Connection, hvac: &mut HVACSystem, username: &str, user_role: &str) -> Result<()> {
+    let upcoming = crate::profile::upcoming_schedule(conn, 24)?;
+    println!("\n📅 Coming up in the next 24 hours:");
+    for (what, when) in &upcoming {
+        println!("   {:<16}  {}", when, what);
+    }
+
+    if db::active_override(conn)?.is_some() {
+        println!();
+        if confirm("Cancel the hold and return to the schedule now?", false)
+            && hvac_change_allowed(conn, username, "end of hold", None)?
+        {
+            db::end_active_override(conn)?;
+            if let Some(profile) = crate::profile::scheduled_profile_to_apply(conn) {
+                apply_profile(conn, hvac, profile, username, user_role);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Recent logins for the signed-in user, with failed attempts called out
+fn show_login_history(conn: &Connection, username: &str) -> Result<()> {
+    let history = logger::own_login_history(conn, username, 20)?;
+    println!("\n===== My Login History =====");
+    if history.is_empty() {
+        println!("No logins recorded yet.");
+        return Ok(());
+    }
+    for (when, event) in history {
+        let label = if event == "FAILURE_LOGIN" { "❌ FAILED attempt" } else { "✅ Successful login" };
+        println!("{:<26} {}", when, label);
+    }
+    Ok(())
+}
+
+// "Was my thermostat changed while I was away?": defaults to the start of a recent vacation
+fn away_report_flow(conn: &Connection, username: &str) -> Result<()> {
+    let default = db::recent_vacation_start(conn)?;
+    let default_label = match default {
+        Some(t) => format!("vacation start, {}", db::display_time(&t.format("%Y-%m-%d %H:%M:%S").to_string())),
+        None => "the last 7 days".to_string(),
+    };
+    print!("Show changes since (mm-dd-yyyy, Enter = {}): ", default_label);
+    io::stdout().flush()?;
+    let Some(input) = prompt_input() else { return Ok(()); };
+    let input = input.trim();
+
+    let since = if input.is_empty() {
+        default.unwrap_or_else(|| chrono::Utc::now() - chrono::Duration::days(7))
+    } else {
+        let start = chrono::NaiveDate::parse_from_str(input, "%m-%d-%Y")
+            .ok()
+            .filter(|_| validate_date_format(input))
+            .and_then(|d| d.and_hms_opt(0, 0, 0)?.and_local_timezone(chrono_tz::America::New_York).earliest());
+        match start {
+            Some(t) => t.with_timezone(&chrono::Utc),
+            None => {
+                println!("❌ Invalid date. Please use mm-dd-yyyy.");
+                return Ok(());
+            }
+        }
+    };
+
+    let items = logger::activity_since(conn, username, since)?;
+    println!("\n===== While You Were Away =====");
+    if items.is_empty() {
+        println!("Nothing changed since then.");
+        return Ok(());
+    }
+    println!("{:<24} {:<9} {:<22} {:<20} Details", "Time", "Source", "Actor", "Action");
+    println!("{}", "-".repeat(100));
+    for item in items {
+        println!(
+            "{:<24} {:<9} {:<22} {:<20} {}",
+            item.timestamp, item.source.as_str(), item.actor, item.action, item.details
+        );
+    }
+    Ok(())
+}
+
+// Who this user can act on (technicians) or who can act on them (homeowners)
+fn show_my_permissions(conn: &Connection, username: &str, role: &str) -> Result<()> {
+    let names = db::my_permissions(conn, username)?;
+    println!("\n===== My Permissions =====");
+    match role {
+        "admin" => println!("You can act on all accounts."),
+        _ if names.is_empty() => println!("No live technician grants right now."),
+        "technician" => println!("You can currently act for: {}", names.join(", ")),
+        _ => println!("Technicians who can currently act for you: {}", names.join(", ")),
+    }
+    Ok(())
+}
+
+// Default warning levels plus the active profile's humidity target
+fn dashboard_thresholds(conn: &Connection) -> senser::Thresholds {
+    senser::Thresholds {
+        humidity_target: hvac::current_target_humidity(conn),
+        ..senser::load_thresholds(conn).unwrap_or_default()
+    }
+}
+
+// Admin edit of the dashboard thresholds; blank keeps a value
+fn edit_thresholds_flow(conn: &Connection, admin_username: &str) -> Result<()> {
+    let current = senser::load_thresholds(conn)?;
+    println!("\n===== Sensor Thresholds =====");
+    println!("Comfort band : {:.1}–{:.1}°C", current.temp_comfort_lo, current.temp_comfort_hi);
+    println!("Humidity band: {:.0}–{:.0}%", current.humidity_lo, current.humidity_hi);
+    println!("CO warning   : {:.0}ppm, danger {:.0}ppm", current.co_warn, current.co_danger);
+    println!("Leave a field blank to keep it.");
+
+    let ask = |label: &str, value: f32| -> Option<f32> {
+        print!("{} [{}]: ", label, value);
+        io::stdout().flush().ok();
+        let input = prompt_input()?;
+        match input.trim() {
+            "" => Some(value),
+            v => v.parse::<f32>().ok().or_else(|| { println!("❌ Not a number."); None }),
+        }
+    };
+    let Some(temp_comfort_lo) = ask("Comfort low (°C)", current.temp_comfort_lo) else { return Ok(()); };
+    let Some(temp_comfort_hi) = ask("Comfort high (°C)", current.temp_comfort_hi) else { return Ok(()); };
+    let Some(humidity_lo) = ask("Humidity low (%)", current.humidity_lo) else { return Ok(()); };
+    let Some(humidity_hi) = ask("Humidity high (%)", current.humidity_hi) else { return Ok(()); };
+    let Some(co_warn) = ask("CO warning (ppm)", current.co_warn) else { return Ok(()); };
+    let Some(co_danger) = ask("CO danger (ppm)", current.co_danger) else { return Ok(()); };
+
+    let updated = senser::Thresholds {
+        temp_comfort_lo, temp_comfort_hi, humidity_lo, humidity_hi, co_warn, co_danger,
+        humidity_target: None,
+    };
+    if updated == current {
+        println!("No changes.");
+        return Ok(());
+    }
+    match senser::save_thresholds(conn, &updated, admin_username) {
+        Ok(()) => println!("✅ Sensor thresholds updated (logged)."),
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+// Offered after the energy report: rate per kWh and the currency cost is shown in
+fn energy_cost_settings_flow(conn: &Connection, username: &str) -> Result<()> {
+    print!("\n[R] Set energy rate  [C] Set currency  [ENTER] Back: ");
+    io::stdout().flush()?;
+    let Some(choice) = prompt_input() else { return Ok(()); };
+    match choice.trim().to_ascii_uppercase().as_str() {
+        "R" => {
+            let currency = db::energy_currency(conn, username)?;
+            print!("Rate per kWh in {} (blank to clear): ", currency.code());
+            io::stdout().flush()?;
+            let Some(value) = prompt_input() else { return Ok(()); };
+            let rate = match value.trim() {
+                "" => None,
+                v => match crate::units::parse_decimal(v) {
+                    Some(r) => Some(f64::from(r)),
+                    None => {
+                        println!("❌ Invalid rate");
+                        return Ok(());
+                    }
+                },
+            };
+            match db::set_energy_rate(conn, username, rate) {
+                Ok(()) => match rate {
+                    Some(r) => println!("✅ Energy rate set to {} per kWh.", currency.format(r)),
+                    None => println!("✅ Energy rate cleared; cost estimates are hidden."),
+                },
+                Err(e) => println!("❌ {}", e),
+            }
+        }
+        "C" => {
+            let codes: Vec<&str> = energy::Currency::ALL.iter().map(|c| c.code()).collect();
+            print!("Currency ({}): ", codes.join(", "));
+            io::stdout().flush()?;
+            let Some(code) = prompt_input() else { return Ok(()); };
+            match energy::Currency::from_code(&code) {
+                Some(c) => {
+                    db::set_energy_currency(conn, username, c)?;
+                    println!("✅ Costs will be shown in {} (e.g. {}).", c.code(), c.format(1234.5));
+                }
+                None => println!("❌ Unknown currency '{}'", code.trim()),
             }
-            _ => println!("Invalid choice, please try again.\n"),
-        },
-        None => {
-            println!("End of input detected. Exiting...");
-            return Ok(false);
         }
+        _ => return Ok(()),
     }
-    Ok(true)
+    wait_for_enter();
+    Ok(())
 }
-// ===============================================================
-//                         GUEST MENU
-// ===============================================================
-fn guest_menu(conn: &mut Connection, username: &str, role: &str) -> Result<bool> {
-    match prompt_input() {
-        Some(choice) => match choice.trim() {
-            "1" => { 
-                db::show_own_profile(conn, username)?;
-                wait_for_enter();},
-            "2" => {
-                println!("🌡 Checking indoor temperature...");
-                if let Err(e) = senser::run_dashboard_inline(senser::Thresholds::default()) {
-                    eprintln!("dashboard error: {e}");
-                }
-                wait_for_enter();
-            },
-            "3" => {
-            println!("Retrieving outdoor weather statu...");
-                if let Err(e) = weather::get_current_weather(conn) {
-                    eprintln!("❌ Error: {:?}", e);
-                }
-                wait_for_enter();
-            },
-            "4" => hvac_control_menu(conn, username, role)?,
-            "5" => {
-                profile_selection_menu(conn, username, role)?;
-            },
-            "0" => {
-                println!("🔒 Logging out...");
-                auth::logout_user(conn)?;
-                ui::front_page_ui();
-                return Ok(false);
+
+// Per-profile humidity target; blank clears it
+fn set_target_humidity_flow(conn: &Connection) -> Result<()> {
+    print!("Profile name: ");
+    io::stdout().flush()?;
+    let Some(name) = prompt_input() else { return Ok(()); };
+    print!(
+        "Target humidity ({:.0}-{:.0}%, blank to clear): ",
+        db::TARGET_HUMIDITY_MIN, db::TARGET_HUMIDITY_MAX
+    );
+    io::stdout().flush()?;
+    let Some(value) = prompt_input() else { return Ok(()); };
+    let target = match value.trim().trim_end_matches('%') {
+        "" => None,
+        v => match v.parse::<f32>() {
+            Ok(t) => Some(t),
+            Err(_) => {
+                println!("❌ Invalid humidity value");
+                return Ok(());
             }
-            _ => println!("Invalid choice, please try again.\n"),
         },
-        None => {
-            println!("End of input detected. Exiting...");
-            return Ok(false);
+    };
+    match db::set_profile_target_humidity(conn, name.trim(), target) {
+        Ok(()) => match target {
+            Some(t) => println!("✅ '{}' now targets {:.0}% humidity.", name.trim(), t),
+            None => println!("✅ Humidity target cleared for '{}'.", name.trim()),
+        },
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+fn compare_profiles_flow(conn: &Connection) -> Result<()> {
+    print!("First profile: ");
+    io::stdout().flush()?;
+    let Some(a) = prompt_input() else { return Ok(()); };
+    print!("Second profile: ");
+    io::stdout().flush()?;
+    let Some(b) = prompt_input() else { return Ok(()); };
+    let (a, b) = (a.trim(), b.trim());
+    match db::compare_profiles(conn, a, b) {
+        Ok(rows) => {
+            println!("\n{:<10} {:<28} {:<28}", "Field", a, b);
+            println!("{}", "-".repeat(68));
+            for (field, a_value, b_value) in rows {
+                let marker = if a_value != b_value { " *" } else { "" };
+                println!("{:<10} {:<28} {:<28}{}", field, a_value, b_value, marker);
+            }
+            println!("(* = differs)");
         }
+        Err(e) => println!("❌ {}", e),
     }
-    Ok(true)
+    wait_for_enter();
+    Ok(())
 }
 
-// ===============================================================
-//                         HVAC CONTROL MENU
-// ===============================================================
-fn hvac_control_menu(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
-    let mut hvac = hvac::HVACSystem::new(conn);
-    
-    loop {
-        ui::hvac_control_ui(user_role);
+// Hold a target for a while; the schedule takes over again when it expires
+fn hold_temperature_flow(conn: &mut Connection, hvac: &mut HVACSystem, username: &str, user_role: &str) -> Result<()> {
+    if let Some(active) = db::active_override(conn)? {
+        println!(
+            "\n⏳ Holding {:.1}°C ({}) until {}, set by {}.",
+            active.target_temp, active.mode, db::display_time(&active.expires_at), active.set_by
+        );
+        if confirm("End this hold now and return to the schedule?", false)
+            && hvac_change_allowed(conn, username, "end of hold", None)?
+        {
+            db::end_active_override(conn)?;
+            if let Some(profile) = crate::profile::scheduled_profile_to_apply(conn) {
+                apply_profile(conn, hvac, profile, username, user_role);
+            }
+        }
+        return Ok(());
+    }
 
-        match prompt_input() {
-            Some(choice) => match choice.trim() {
-                "1" => {
-                    println!("\n🌡️  Select HVAC Mode:");
-                    println!("[1] 🔥 Heating  [2] ❄️  Cooling  [3] 🤖 Auto  [4] 💨 Fan Only  [5] ⭕ Off");
-                    if let Some(mode) = prompt_input() {
-                        let new_mode = match mode.trim() {
-                            "1" => hvac::HVACMode::Heating,
-                            "2" => hvac::HVACMode::Cooling,
-                            "3" => hvac::HVACMode::Auto,
-                            "4" => hvac::HVACMode::FanOnly,
-                            "5" => hvac::HVACMode::Off,
-                            _ => {
-                                println!("❌ Invalid mode selection");
-                                continue;
-                            }
-                        };
-                        
-                        let old_mode_str = format!("{:?}", hvac.mode);
-                        let old_temp = hvac.target_temperature;
-                        
-                        // Set temperature for modes that need it (not Off or FanOnly)
-                        if matches!(new_mode, hvac::HVACMode::Heating | hvac::HVACMode::Cooling | hvac::HVACMode::Auto) {
-                            let (min_temp, max_temp) = new_mode.temperature_range();
-                            println!("\n🌡️  Enter target temperature for {:?} mode ({:.0}-{:.0}°C):", new_mode, min_temp, max_temp);
-                            print!("Temperature: ");
-                            io::stdout().flush()?;
-                            
-                            if let Some(temp_str) = prompt_input() {
-                                if let Ok(temp) = temp_str.trim().parse::<f32>() {
-                                    if new_mode.is_valid_temperature_for_mode(temp) {
-                                        hvac.set_mode(conn, new_mode);
-                                        hvac.set_target_temperature(conn, temp);
-                                        
-                                        // Prompt for light status
-                                        println!("\n💡 Light/Lamp: [1] ON  [2] OFF");
-                                        print!("Choice: ");
-                                        io::stdout().flush()?;
-                                        if let Some(light_choice) = prompt_input() {
-                                            let light_status = match light_choice.trim() {
-                                                "1" => "ON",
-                                                "2" => "OFF",
-                                                _ => "OFF", // Default to OFF
-                                            };
-                                            hvac.set_light_status(conn, light_status);
-                                        }
-                                        
-                                        // Log both changes
-                                        let new_mode_str = format!("{:?}", new_mode);
-                                        let _ = db::log_mode_changed(conn, username, user_role, &old_mode_str, &new_mode_str);
-                                        let _ = db::log_temperature_changed(conn, username, user_role, old_temp, temp);
-                                        
-                                        println!("✅ Mode set to {:?} with target {:.1}°C, Light: {}", new_mode, temp, hvac.light_status);
-                                    } else {
-                                        println!("❌ Invalid temperature for {:?} mode! Must be between {:.0}°C and {:.0}°C", 
-                                                 new_mode, min_temp, max_temp);
-                                        continue;
-                                    }
-                                } else {
-                                    println!("❌ Invalid temperature value");
-                                    continue;
-                                }
-                            }
-                        } else {
-                            // Fan Only or Off - just set mode and light, no temperature needed
-                            hvac.set_mode(conn, new_mode);
-                            
-                            // Prompt for light status
-                            println!("\n💡 Light/Lamp: [1] ON  [2] OFF");
-                            print!("Choice: ");
-                            io::stdout().flush()?;
-                            if let Some(light_choice) = prompt_input() {
-                                let light_status = match light_choice.trim() {
-                                    "1" => "ON",
-                                    "2" => "OFF",
-                                    _ => "OFF", // Default to OFF
-                                };
-                                hvac.set_light_status(conn, light_status);
-                            }
-                            
-                            let new_mode_str = format!("{:?}", new_mode);
-                            let _ = db::log_mode_changed(conn, username, user_role, &old_mode_str, &new_mode_str);
-                            println!("✅ Mode set to {:?}, Light: {}", new_mode, hvac.light_status);
-                        }
-                    }
-                }
-                "2" => {
-                    hvac.update(conn);
-                    wait_for_enter();
-                }
-                "3" => {
-                    // Option 3 behavior depends on user role
-                    if user_role == "homeowner" {
-                        // Homeowners: Choose Profile
-                        profile_selection_menu(conn, username, user_role)?;
-                        // Reload HVAC state from database after profile change
-                        hvac = hvac::HVACSystem::new(conn);
-                    } else if user_role == "guest" {
-                        // Guests: Return to Main Menu (they have Choose Profile in main menu)
-                        break;
-                    } else {
-                        // Technicians: Run Diagnostics
-                        hvac.diagnostics(conn);
-                        wait_for_enter();
-                    }
-                }
-                "4" => {
-                    // Option 4 is "Return to Main Menu" for homeowners and technicians
-                    break;
-                }
-                _ => println!("Invalid option. Please try again."),
-            },
-            None => break,
+    println!("\n⏳ Temporary hold: [1] 🔥 Heating  [2] ❄️  Cooling  [3] 🤖 Auto");
+    print!("Mode: ");
+    io::stdout().flush()?;
+    let Some(choice) = prompt_input() else { return Ok(()); };
+    let mode = match choice.trim() {
+        "1" => HVACMode::Heating,
+        "2" => HVACMode::Cooling,
+        "3" => HVACMode::Auto,
+        _ => {
+            println!("❌ Invalid mode selection");
+            return Ok(());
         }
+    };
+
+    let (min_temp, max_temp) = mode.temperature_range();
+    let default_temp = db::mode_default_temperature(conn, mode)?;
+    print!("Temperature ({:.0}-{:.0}°C) [{:.1}]: ", min_temp, max_temp, default_temp);
+    io::stdout().flush()?;
+    let Some(temp_str) = prompt_input() else { return Ok(()); };
+    let temp = match temp_str.trim() {
+        "" => default_temp,
+        t => match crate::units::parse_decimal(t) {
+            Some(v) => v,
+            None => {
+                println!("❌ Invalid temperature value");
+                return Ok(());
+            }
+        },
+    };
+
+    print!("Hold for how many minutes (1-{}) [120]: ", db::MAX_OVERRIDE_MINUTES);
+    io::stdout().flush()?;
+    let Some(mins_str) = prompt_input() else { return Ok(()); };
+    let minutes = match mins_str.trim() {
+        "" => 120,
+        m => m.parse::<i64>().unwrap_or(0),
+    };
+
+    let mode_str = format!("{:?}", mode);
+    if !hvac_change_allowed(conn, username, &format!("{} hold", mode_str), Some(temp))? {
+        return Ok(());
     }
+    // The hold, its log entry and the message all use the target a guest is actually allowed
+    let temp = hvac.clamp_for_guest(conn, temp);
+    if let Err(e) = db::set_override(conn, username, &mode_str, temp, minutes) {
+        println!("❌ {}", e);
+        return Ok(());
+    }
+
+    let old_mode_str = format!("{:?}", hvac.mode);
+    let old_temp = hvac.target_temperature;
+    hvac.set_mode(conn, mode);
+    hvac.set_target_temperature(conn, temp);
+    hvac.current_profile = None;
+    hvac.save_state(conn);
+    let _ = db::log_mode_changed(conn, username, user_role, &old_mode_str, &mode_str);
+    let _ = db::log_temperature_changed(conn, username, user_role, old_temp, temp);
+
+    println!("✅ Holding {:.1}°C in {} mode for {} minutes, then back to the schedule.", temp, mode_str, minutes);
     Ok(())
 }
 
@@ -768,10 +1959,16 @@ fn show_system_status(conn: &mut Connection, username: &str, user_role: &str) ->
     println!("╠═══════════════════════════════════════════════════════╣");
     println!("║ Current Time: {:<39} ║", time_str);
     println!("║ Scheduled Profile Window: {:<27} ║", format!("{:?}", scheduled));
+    if let Some(reason) = crate::profile::schedule_suppression(conn) {
+        println!("║ {:<53} ║", reason);
+    }
     println!("╚═══════════════════════════════════════════════════════╝");
     
-    // Display HVAC activity log
-    db::view_hvac_activity_log(conn, username, user_role)?;
+    // Display HVAC activity log, optionally for one profile
+    print!("\nFilter activity by profile name (blank for all): ");
+    io::stdout().flush()?;
+    let filter = prompt_input().map(|f| f.trim().to_string()).filter(|f| !f.is_empty());
+    db::view_hvac_activity_log(conn, username, user_role, filter.as_deref())?;
     
     wait_for_enter();
     Ok(())
@@ -780,26 +1977,56 @@ fn show_system_status(conn: &mut Connection, username: &str, user_role: &str) ->
 // ===============================================================
 //                  ADMIN: MANAGE PROFILE SETTINGS
 // ===============================================================
+// Numbered profile list of the management menu, with type and usage tags
+fn print_profile_rows(conn: &Connection, profiles: &[db::ProfileRow]) {
+    let drifted = db::default_profile_drift(conn).unwrap_or_default();
+    for (idx, p) in profiles.iter().enumerate() {
+        let profile_type = if drifted.iter().any(|d| d.eq_ignore_ascii_case(&p.name)) {
+            "🔒 Default (modified)"
+        } else if db::is_default_profile(&p.name) {
+            "🔒 Default"
+        } else {
+            "✨ Custom"
+        };
+        let usage = crate::profile::profile_usage(conn, &p.name).map(|u| u.tags()).unwrap_or_default();
+        println!("[{}] {} {:<15} | {} | mode={:<8} | temp={:.1}°C | fan={:<6} | light={:<3} {}",
+            idx + 1, db::profile_icon(p), p.name, profile_type, p.mode, p.target_temp, p.fan_speed, p.light_status, usage);
+    }
+}
+
 fn manage_profiles_menu(conn: &mut Connection, admin_username: &str, current_role: &str) -> Result<()> {
     if current_role != "homeowner" && current_role != "admin" && current_role != "technician" { 
         println!("Access denied: Only homeowners, technicians, and admins can manage profiles."); 
         return Ok(()); 
     }
 
+    // Technicians need a live grant; edits are recorded under that homeowner
+    let homeowner_ctx = match db::profile_edit_context(conn, admin_username, current_role) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            println!("{}", e);
+            wait_for_enter();
+            return Ok(());
+        }
+    };
+    let ctx = homeowner_ctx.as_deref();
+
     loop {
+        if current_role == "technician" && db::profile_edit_context(conn, admin_username, current_role).is_err() {
+            println!("Grant expired or revoked. Leaving profile management.");
+            break;
+        }
+
         println!("\n═══════════════════════════════════════════════════");
         println!("          🔧 PROFILE MANAGEMENT MENU");
         println!("═══════════════════════════════════════════════════");
         let profiles = db::list_profile_rows(conn)?;
         println!("\n📋 Existing Profiles:");
-        for (idx, p) in profiles.iter().enumerate() {
-            let profile_type = if db::is_default_profile(&p.name) { "🔒 Default" } else { "✨ Custom" };
-            println!("[{}] {:<15} | {} | mode={:<8} | temp={:.1}°C | fan={:<6} | light={:<3}", 
-                idx + 1, p.name, profile_type, p.mode, p.target_temp, p.fan_speed, p.light_status);
-        }
+        print_profile_rows(conn, &profiles);
         println!("\n📝 Options:");
         println!("[C] Create New Profile    [E] Edit Profile       [D] Delete Profile");
-        println!("[R] Reset to Defaults     [Q] Back to Main Menu");
+        println!("[R] Reset to Defaults     [H] Target Humidity    [V] Compare Profiles");
+        println!("[M] Filter by Mode        [A] Humidity on Apply  [Q] Back to Main Menu");
         print!("\nSelect option: "); io::stdout().flush().ok();
         let choice = prompt_input();
         let Some(choice) = choice else { break };
@@ -809,49 +2036,64 @@ fn manage_profiles_menu(conn: &mut Connection, admin_username: &str, current_rol
             break; 
         } else if choice.eq_ignore_ascii_case("c") {
             // CREATE NEW PROFILE
-            create_new_profile_flow(conn, admin_username, current_role)?;
+            create_new_profile_flow(conn, admin_username, current_role, ctx)?;
         } else if choice.eq_ignore_ascii_case("d") {
             // DELETE PROFILE
-            delete_profile_flow(conn, admin_username, current_role)?;
+            delete_profile_flow(conn, admin_username, current_role, ctx)?;
         } else if choice.eq_ignore_ascii_case("e") {
             // EDIT PROFILE (with full control)
-            edit_profile_full_flow(conn, admin_username, current_role)?;
+            edit_profile_full_flow(conn, admin_username, current_role, ctx)?;
+        } else if choice.eq_ignore_ascii_case("h") {
+            set_target_humidity_flow(conn)?;
+        } else if choice.eq_ignore_ascii_case("a") {
+            let enabled = !db::profile_sets_humidity(conn)?;
+            db::set_profile_sets_humidity(conn, admin_username, enabled)?;
+            if enabled {
+                println!("✅ Applying a profile now also sets the humidity target.");
+            } else {
+                println!("✅ Applying a profile now leaves the humidity target alone.");
+            }
+        } else if choice.eq_ignore_ascii_case("v") {
+            compare_profiles_flow(conn)?;
+        } else if choice.eq_ignore_ascii_case("m") {
+            if let Some(filtered) = prompt_profiles_by_mode(conn)? {
+                println!();
+                print_profile_rows(conn, &filtered);
+            }
+            wait_for_enter();
         } else if choice.eq_ignore_ascii_case("r") {
             print!("Enter profile name to reset (or 'all'): "); io::stdout().flush().ok();
-            let target = match prompt_input() { Some(s) => s.trim().to_string(), None => continue };
+            let target = match prompt_input() { Some(s) => s.trim().to_string(), None => break };
             if target.eq_ignore_ascii_case("all") {
-                for nm in ["Day","Night","Sleep","Party","Vacation","Away"].iter() {
-                    db::reset_profile_to_default(conn, nm)?;
-                    let _ = db::log_profile_reset(conn, admin_username, current_role, nm);
+                match db::reset_all_defaults(conn, admin_username, current_role) {
+                    Ok(()) => println!("All profiles reset (logged)."),
+                    Err(e) => println!("❌ {:#}. No profiles were changed.", e),
                 }
-                println!("All profiles reset (logged).");
             } else {
                 db::reset_profile_to_default(conn, &target)?;
                 let _ = db::log_profile_reset(conn, admin_username, current_role, &target);
                 let msg = format!("Profile '{}' reset to defaults", target);
                 println!("{} (logged)", msg);
             }
-        } else if let Ok(idx) = choice.parse::<usize>() {
+        } else if let Some(idx) = parse_menu_index(choice, profiles.len()) {
             // Profile selection - only Edit option available (no Apply from Profile Settings)
-            if idx >= 1 && idx <= profiles.len() {
-                let selected_profile = &profiles[idx-1];
-                let name = selected_profile.name.clone();
-                
-                // Profile Settings menu: Only Edit option (Apply must be done through HVAC Control menu)
-                println!("Selected {}. Choose action: [E] Edit  [Back: Enter]", name);
-                println!("💡 Note: To apply profiles, use the HVAC Control menu.");
-                
-                if let Some(act) = prompt_input() {
-                    let t = act.trim().to_string();
-                    if t.eq_ignore_ascii_case("a") {
-                        println!("❌ Profiles cannot be applied from Profile Settings menu.");
-                        println!("   Please use HVAC Control menu to apply profiles (with proper security checks).");
-                        wait_for_enter();
-                    } else if t.eq_ignore_ascii_case("e") {
-                        // loop back into edit branch by simulating 'E'
-                        // Simpler: prompt again with E
-                        println!("Re-open menu and choose [E] to edit.");
-                    }
+            let selected_profile = &profiles[idx];
+            let name = selected_profile.name.clone();
+            
+            // Profile Settings menu: Only Edit option (Apply must be done through HVAC Control menu)
+            println!("Selected {}. Choose action: [E] Edit  [Back: Enter]", name);
+            println!("💡 Note: To apply profiles, use the HVAC Control menu.");
+            
+            if let Some(act) = prompt_input() {
+                let t = act.trim().to_string();
+                if t.eq_ignore_ascii_case("a") {
+                    println!("❌ Profiles cannot be applied from Profile Settings menu.");
+                    println!("   Please use HVAC Control menu to apply profiles (with proper security checks).");
+                    wait_for_enter();
+                } else if t.eq_ignore_ascii_case("e") {
+                    // loop back into edit branch by simulating 'E'
+                    // Simpler: prompt again with E
+                    println!("Re-open menu and choose [E] to edit.");
                 }
             }
         } else {
@@ -863,7 +2105,7 @@ fn manage_profiles_menu(conn: &mut Connection, admin_username: &str, current_rol
 }
 
 // Helper function to create a new profile (for homeowners and technicians)
-fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
+fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &str, homeowner_ctx: Option<&str>) -> Result<()> {
     use std::io::{self, Write};
 
     println!("\n========== CREATE NEW PROFILE ==========");
@@ -871,8 +2113,7 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     // 1. Get profile name
     print!("Enter profile name (3-20 characters, letters/numbers/spaces only): ");
     io::stdout().flush()?;
-    let mut name = String::new();
-    io::stdin().read_line(&mut name)?;
+    let Some(name) = prompt_input() else { return Ok(()); };
     let name = name.trim().to_string();
 
     // Validate profile name
@@ -890,8 +2131,7 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     println!("  5) Auto");
     print!("Choose mode (1-5): ");
     io::stdout().flush()?;
-    let mut mode_choice = String::new();
-    io::stdin().read_line(&mut mode_choice)?;
+    let Some(mode_choice) = prompt_input() else { return Ok(()); };
     let mode = match mode_choice.trim() {
         "1" => "Off",
         "2" => "Heating",
@@ -907,15 +2147,18 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     // 3. Get target temperature
     print!("Enter target temperature (16-40°C): ");
     io::stdout().flush()?;
-    let mut temp_str = String::new();
-    io::stdin().read_line(&mut temp_str)?;
-    let target_temp: f32 = match temp_str.trim().parse() {
-        Ok(t) if t >= 16.0 && t <= 40.0 => t,
+    let Some(temp_str) = prompt_input() else { return Ok(()); };
+    let target_temp: f32 = match crate::units::parse_decimal(&temp_str) {
+        Some(t) if (16.0..=40.0).contains(&t) => t,
         _ => {
             println!("❌ Invalid temperature. Must be between 16-40°C");
             return Ok(());
         }
     };
+    let Some(target_temp) = crate::profile::reconcile_profile_temperature(mode, target_temp, |q| confirm(q, false)) else {
+        println!("❌ Profile not created.");
+        return Ok(());
+    };
 
     // 4. Get heater status
     println!("\nHeater status:");
@@ -924,8 +2167,7 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     println!("  3) Auto");
     print!("Choose heater status (1-3): ");
     io::stdout().flush()?;
-    let mut heater_choice = String::new();
-    io::stdin().read_line(&mut heater_choice)?;
+    let Some(heater_choice) = prompt_input() else { return Ok(()); };
     let heater_status = match heater_choice.trim() {
         "1" => "On",
         "2" => "Off",
@@ -943,8 +2185,7 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     println!("  3) Auto");
     print!("Choose AC status (1-3): ");
     io::stdout().flush()?;
-    let mut ac_choice = String::new();
-    io::stdin().read_line(&mut ac_choice)?;
+    let Some(ac_choice) = prompt_input() else { return Ok(()); };
     let ac_status = match ac_choice.trim() {
         "1" => "On",
         "2" => "Off",
@@ -954,6 +2195,10 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
             return Ok(());
         }
     };
+    if let Err(e) = db::validate_device_interlock(heater_status, ac_status) {
+        println!("❌ {}", e);
+        return Ok(());
+    }
 
     // 6. Get light status
     println!("\nLight status:");
@@ -961,8 +2206,7 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     println!("  2) OFF");
     print!("Choose light status (1-2): ");
     io::stdout().flush()?;
-    let mut light_choice = String::new();
-    io::stdin().read_line(&mut light_choice)?;
+    let Some(light_choice) = prompt_input() else { return Ok(()); };
     let light_status = match light_choice.trim() {
         "1" => "ON",
         "2" => "OFF",
@@ -979,8 +2223,7 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     println!("  3) High");
     print!("Choose fan speed (1-3): ");
     io::stdout().flush()?;
-    let mut fan_choice = String::new();
-    io::stdin().read_line(&mut fan_choice)?;
+    let Some(fan_choice) = prompt_input() else { return Ok(()); };
     let fan_speed = match fan_choice.trim() {
         "1" => "Low",
         "2" => "Medium",
@@ -994,16 +2237,25 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
     // 8. Optional: greeting and description
     print!("\nEnter greeting (optional, press Enter to skip): ");
     io::stdout().flush()?;
-    let mut greeting = String::new();
-    io::stdin().read_line(&mut greeting)?;
+    let Some(greeting) = prompt_input() else { return Ok(()); };
     let greeting = greeting.trim();
 
     print!("Enter description (optional, press Enter to skip): ");
     io::stdout().flush()?;
-    let mut description = String::new();
-    io::stdin().read_line(&mut description)?;
+    let Some(description) = prompt_input() else { return Ok(()); };
     let description = description.trim();
 
+    print!("Enter icon (optional, up to {} characters, e.g. 🌿): ", db::MAX_PROFILE_ICON_CHARS);
+    io::stdout().flush()?;
+    let Some(icon) = prompt_input() else { return Ok(()); };
+    let icon = icon.trim();
+    if !icon.is_empty()
+        && let Err(e) = db::validate_profile_icon(icon)
+    {
+        println!("❌ {}", e);
+        return Ok(());
+    }
+
     // Create the profile
     db::create_profile(
         conn,
@@ -1017,17 +2269,21 @@ fn create_new_profile_flow(conn: &mut Connection, username: &str, user_role: &st
         light_status,
         fan_speed,
     )?;
+    if !icon.is_empty() {
+        db::set_profile_icon(conn, &name, Some(icon))?;
+    }
 
     // Log the creation
     let log_msg = format!("Profile '{}' created by {} ({})", name, username, user_role);
     logger::log_event(conn, username, None, "HVAC", Some(&log_msg))?;
+    let _ = db::log_profile_edited(conn, username, user_role, &name, "created", homeowner_ctx);
 
     println!("\n✅ Profile '{}' created successfully!", name);
     Ok(())
 }
 
 // Helper function to delete a profile
-fn delete_profile_flow(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
+fn delete_profile_flow(conn: &mut Connection, username: &str, user_role: &str, homeowner_ctx: Option<&str>) -> Result<()> {
     use std::io::{self, Write};
 
     println!("\n========== DELETE PROFILE ==========");
@@ -1046,7 +2302,8 @@ fn delete_profile_flow(conn: &mut Connection, username: &str, user_role: &str) -
         } else {
             "[Custom]"
         };
-        println!("  {}. {} {}", idx + 1, profile.name, profile_type);
+        let usage = crate::profile::profile_usage(conn, &profile.name).map(|u| u.tags()).unwrap_or_default();
+        println!("  {}. {} {} {}", idx + 1, profile.name, profile_type, usage);
     }
 
     println!("\n⚠️  Note: Default profiles cannot be deleted.");
@@ -1054,8 +2311,7 @@ fn delete_profile_flow(conn: &mut Connection, username: &str, user_role: &str) -
     // Get profile name to delete
     print!("\nEnter profile name to delete (or press Enter to cancel): ");
     io::stdout().flush()?;
-    let mut name = String::new();
-    io::stdin().read_line(&mut name)?;
+    let Some(name) = prompt_input() else { return Ok(()); };
     let name = name.trim();
 
     if name.is_empty() {
@@ -1076,17 +2332,13 @@ fn delete_profile_flow(conn: &mut Connection, username: &str, user_role: &str) -
     }
 
     // Confirm deletion
-    print!("⚠️  Are you sure you want to delete profile '{}'? (yes/no): ", name);
-    io::stdout().flush()?;
-    let mut confirm = String::new();
-    io::stdin().read_line(&mut confirm)?;
-    
-    if confirm.trim().eq_ignore_ascii_case("yes") {
+    if confirm(&format!("⚠️  Are you sure you want to delete profile '{}'?", name), false) {
         db::delete_profile(conn, name)?;
         
         // Log the deletion
         let log_msg = format!("Profile '{}' deleted by {} ({})", name, username, user_role);
         logger::log_event(conn, username, None, "HVAC", Some(&log_msg))?;
+        let _ = db::log_profile_edited(conn, username, user_role, name, "deleted", homeowner_ctx);
         
         println!("✅ Profile '{}' deleted successfully!", name);
     } else {
@@ -1097,7 +2349,7 @@ fn delete_profile_flow(conn: &mut Connection, username: &str, user_role: &str) -
 }
 
 // Helper function to edit a profile with full control
-fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str) -> Result<()> {
+fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str, homeowner_ctx: Option<&str>) -> Result<()> {
     use std::io::{self, Write};
 
     println!("\n========== EDIT PROFILE ==========");
@@ -1122,8 +2374,7 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
     // Get profile name to edit
     print!("\nEnter profile name to edit (or press Enter to cancel): ");
     io::stdout().flush()?;
-    let mut name = String::new();
-    io::stdin().read_line(&mut name)?;
+    let Some(name) = prompt_input() else { return Ok(()); };
     let name = name.trim();
 
     if name.is_empty() {
@@ -1149,13 +2400,14 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
     println!("Fan Speed: {}", current.fan_speed);
     println!("Greeting: {}", current.greeting.as_deref().unwrap_or("(none)"));
     println!("Description: {}", current.description.as_deref().unwrap_or("(none)"));
+    let current_icon_label = db::profile_icon(&current).to_string();
+    println!("Icon: {}", current_icon_label);
     println!("\n📝 Press Enter to keep current value, or enter new value:\n");
 
     // 1. Edit mode
     print!("Mode [Off/Heating/Cooling/FanOnly/Auto] (current: {}): ", current.mode);
     io::stdout().flush()?;
-    let mut mode_input = String::new();
-    io::stdin().read_line(&mut mode_input)?;
+    let Some(mode_input) = prompt_input() else { return Ok(()); };
     let new_mode = if mode_input.trim().is_empty() {
         current.mode
     } else {
@@ -1168,28 +2420,31 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
         }
     };
 
-    // 2. Edit target temperature
-    print!("Target Temperature [16-40] (current: {}): ", current.target_temp);
+    // 2. Edit target temperature; the range shown is the chosen mode's
+    let (min_t, max_t) = HVACMode::from_name(&new_mode).map(|m| m.temperature_range()).unwrap_or((16.0, 40.0));
+    print!("Target Temperature [{:.0}-{:.0}] (current: {}): ", min_t, max_t, current.target_temp);
     io::stdout().flush()?;
-    let mut temp_input = String::new();
-    io::stdin().read_line(&mut temp_input)?;
+    let Some(temp_input) = prompt_input() else { return Ok(()); };
     let new_target_temp = if temp_input.trim().is_empty() {
         current.target_temp
     } else {
-        match temp_input.trim().parse::<f32>() {
-            Ok(t) if t >= 16.0 && t <= 40.0 => t,
+        match crate::units::parse_decimal(&temp_input) {
+            Some(t) if (16.0..=40.0).contains(&t) => t,
             _ => {
                 println!("❌ Invalid temperature. Keeping current value.");
                 current.target_temp
             }
         }
     };
+    let Some(new_target_temp) = crate::profile::reconcile_profile_temperature(&new_mode, new_target_temp, |q| confirm(q, false)) else {
+        println!("❌ Edit cancelled.");
+        return Ok(());
+    };
 
     // 3. Edit heater status
     print!("Heater [On/Off/Auto] (current: {}): ", current.heater_status);
     io::stdout().flush()?;
-    let mut heater_input = String::new();
-    io::stdin().read_line(&mut heater_input)?;
+    let Some(heater_input) = prompt_input() else { return Ok(()); };
     let new_heater = if heater_input.trim().is_empty() {
         current.heater_status
     } else {
@@ -1205,8 +2460,7 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
     // 4. Edit AC status
     print!("AC [On/Off/Auto] (current: {}): ", current.ac_status);
     io::stdout().flush()?;
-    let mut ac_input = String::new();
-    io::stdin().read_line(&mut ac_input)?;
+    let Some(ac_input) = prompt_input() else { return Ok(()); };
     let new_ac = if ac_input.trim().is_empty() {
         current.ac_status
     } else {
@@ -1218,12 +2472,15 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
             }
         }
     };
+    if let Err(e) = db::validate_device_interlock(&new_heater, &new_ac) {
+        println!("❌ {} Profile not changed.", e);
+        return Ok(());
+    }
 
     // 5. Edit light status
     print!("Light [ON/OFF] (current: {}): ", current.light_status);
     io::stdout().flush()?;
-    let mut light_input = String::new();
-    io::stdin().read_line(&mut light_input)?;
+    let Some(light_input) = prompt_input() else { return Ok(()); };
     let new_light = if light_input.trim().is_empty() {
         current.light_status
     } else {
@@ -1239,8 +2496,7 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
     // 6. Edit fan speed
     print!("Fan Speed [Low/Medium/High] (current: {}): ", current.fan_speed);
     io::stdout().flush()?;
-    let mut fan_input = String::new();
-    io::stdin().read_line(&mut fan_input)?;
+    let Some(fan_input) = prompt_input() else { return Ok(()); };
     let new_fan_speed = if fan_input.trim().is_empty() {
         current.fan_speed
     } else {
@@ -1256,8 +2512,7 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
     // 7. Edit greeting (optional)
     print!("Greeting (current: {}): ", current.greeting.as_deref().unwrap_or("(none)"));
     io::stdout().flush()?;
-    let mut greeting_input = String::new();
-    io::stdin().read_line(&mut greeting_input)?;
+    let Some(greeting_input) = prompt_input() else { return Ok(()); };
     let new_greeting = if greeting_input.trim().is_empty() {
         current.greeting
     } else {
@@ -1267,14 +2522,29 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
     // 8. Edit description (optional)
     print!("Description (current: {}): ", current.description.as_deref().unwrap_or("(none)"));
     io::stdout().flush()?;
-    let mut desc_input = String::new();
-    io::stdin().read_line(&mut desc_input)?;
+    let Some(desc_input) = prompt_input() else { return Ok(()); };
     let new_description = if desc_input.trim().is_empty() {
         current.description
     } else {
         Some(desc_input.trim().to_string())
     };
 
+    // 9. Edit icon (optional, '-' clears it)
+    print!("Icon, up to {} characters, '-' to clear (current: {}): ", db::MAX_PROFILE_ICON_CHARS, current_icon_label);
+    io::stdout().flush()?;
+    let Some(icon_input) = prompt_input() else { return Ok(()); };
+    let new_icon = match icon_input.trim() {
+        "" => current.icon.clone(),
+        "-" => None,
+        icon => match db::validate_profile_icon(icon) {
+            Ok(()) => Some(icon.to_string()),
+            Err(e) => {
+                println!("❌ {} Keeping current value.", e);
+                current.icon.clone()
+            }
+        },
+    };
+
     // Update the profile
     db::update_profile_full(
         conn,
@@ -1288,10 +2558,12 @@ fn edit_profile_full_flow(conn: &mut Connection, username: &str, user_role: &str
         &new_light,
         &new_fan_speed,
     )?;
+    db::set_profile_icon(conn, name, new_icon.as_deref())?;
 
     // Log the edit
     let log_msg = format!("Profile '{}' edited by {} ({})", name, username, user_role);
     logger::log_event(conn, username, None, "HVAC", Some(&log_msg))?;
+    let _ = db::log_profile_edited(conn, username, user_role, name, "edited", homeowner_ctx);
 
     println!("\n✅ Profile '{}' updated successfully!", name);
     Ok(())
@@ -1304,10 +2576,7 @@ fn apply_custom_profile(
     user_role: &str,
     profile: &db::ProfileRow,
 ) -> Result<()> {
-    use crate::profile::celsius_to_fahrenheit;
-    use chrono::Local;
-    
-    let mut hvac = HVACSystem::new(conn);
+    let mut hvac = HVACSystem::for_user(conn, username);
     
     // Map mode string to HVACMode
     let mode = match profile.mode.as_str() {
@@ -1343,59 +2612,45 @@ fn apply_custom_profile(
     // Apply the settings
     hvac.set_mode(conn, mode);
     hvac.set_target_temperature(conn, adjusted_temp);
+    // What was actually applied, after any guest limit
+    let adjusted_temp = hvac.target_temperature;
     hvac.set_light_status(conn, &profile.light_status);
     
     // Set current profile name and save to database
     hvac.current_profile = Some(profile.name.clone());
     hvac.save_state(conn);
+    hvac.apply_profile_humidity(conn, profile.target_humidity);
     
-    // Display profile application
+    // Display profile application in the user's preferred layout
     let greet = profile.greeting.as_deref().unwrap_or("Custom profile activated");
+    let greet = crate::profile::take_greeting(conn, username, &profile.name).then_some(greet);
     let now = Local::now();
     let time_str = now.format("%b %d, %Y %I:%M %p %Z").to_string();
-    let temp_f = celsius_to_fahrenheit(adjusted_temp);
-    
+
     // Get current temperature to determine actual runtime behavior
     let current_temp = senser::get_indoor_temperature().unwrap_or(22.0);
-    
-    // Determine heater/AC display status based on actual runtime behavior
-    let (heater_display, ac_display) = match mode {
-        HVACMode::Heating => ("ON", "OFF"),
-        HVACMode::Cooling => ("OFF", "ON"),
-        HVACMode::Auto => {
-            // Auto mode: check temperature difference to determine what's actually running
-            if current_temp < adjusted_temp - 0.5 {
-                ("ON", "OFF")  // Need heating
-            } else if current_temp > adjusted_temp + 0.5 {
-                ("OFF", "ON")  // Need cooling
-            } else {
-                ("OFF", "OFF") // Temperature is at target
-            }
-        },
-        HVACMode::FanOnly => ("OFF", "OFF"),
-        HVACMode::Off => ("OFF", "OFF"),
+    let (heater_on, ac_on) = hvac.decide(current_temp, db::get_presence(conn).unwrap_or_default());
+
+    let view = crate::profile::ProfileAppliedView {
+        label: format!("{} (Custom)", profile.name),
+        greeting: greet,
+        mode,
+        target_c: adjusted_temp,
+        heater_on,
+        ac_on,
+        light: &profile.light_status,
+        fan: Some(&profile.fan_speed),
+        description: profile.description.as_deref(),
+        vacation: None,
+        schedule: None,
+        time: &time_str,
     };
-    
-    println!("🌈✨=============================================✨🌈");
-    println!("🏡  HVAC Profile Applied");
-    println!();
-    println!("📋  Profile: {} (Custom)", profile.name);
-    println!();
-    println!("{}", greet);
-    println!();
-    println!("⚙️  Mode: {:?}", mode);
-    println!();
-    println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", adjusted_temp, temp_f);
-    println!();
-    println!("📝  Description: Temperature: {:.1}°C / {:.1}°F", adjusted_temp, temp_f);
-    println!("    🔥 Heater: {} | ❄️ AC: {} | 💡 Light: {} | 🌀 Fan: {}", 
-             heater_display, ac_display, profile.light_status, profile.fan_speed);
-    if let Some(desc) = &profile.description {
-        println!("    {}", desc);
-    }
-    println!();
-    println!("🕒  Time: {}", time_str);
-    println!("🌈✨=============================================✨🌈");
+    let display = db::get_display_mode(conn, username).unwrap_or_default();
+    println!("{}", crate::profile::render_profile_applied(&view, display));
+
+    if let Some(note) = crate::profile::schedule_advisory(&profile.name, crate::profile::current_scheduled_profile()) {
+        println!("{}", note);
+    }
     
     // Log the profile application
     let log_msg = format!(
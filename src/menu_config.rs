@@ -0,0 +1,275 @@
+// ======================================================
+//                  ROLE MENU LAYOUT
+// ======================================================
+// Each role's main menu is a list of items (key, label, handler id). The lists
+// below are the shipped layout; rows in menu_config hide an item or move it, so
+// a deployment can trim or reorder a menu without code changes. [0] Log out and
+// the [X]-[Z] shortcut keys are fixed and never part of the table.
+
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection};
+
+use crate::logger;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MenuItem {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub action: &'static str,
+}
+
+const fn item(key: &'static str, label: &'static str, action: &'static str) -> MenuItem {
+    MenuItem { key, label, action }
+}
+
+// Handlers that stay visible so an admin can always undo a layout change
+const PINNED_ACTIONS: &[&str] = &["menu_layout"];
+
+// One-step actions printed on a row of their own above the two-column menu
+pub const QUICK_ACTIONS: &[&str] = &["apply_last_profile"];
+
+// Listed in display order: menus print two items per row, left then right
+const HOMEOWNER_MENU: &[MenuItem] = &[
+    item("R", "Apply my last profile", "apply_last_profile"),
+    item("1", "View profile", "view_profile"),
+    item("6", "View System Status", "system_status"),
+    item("2", "Manage Guests", "manage_guests"),
+    item("7", "Profile Settings", "profile_settings"),
+    item("3", "Indoor Sensing", "indoor_sensing"),
+    item("8", "Energy Usage", "energy_usage"),
+    item("4", "Outdoor Weather", "outdoor_weather"),
+    item("9", "Energy Comparison", "energy_comparison"),
+    item("5", "HVAC Control", "hvac_control"),
+    item("C", "View Guest Notes", "guest_notes"),
+    item("A", "Request a Technician", "request_technician"),
+    item("B", "View Active Grants", "active_grants"),
+    item("D", "Export Job Records (CSV)", "export_jobs"),
+    item("S", "Manage Shortcuts", "manage_shortcuts"),
+    item("V", "Compact/Verbose View", "display_mode"),
+    item("U", "°C/°F Units", "temp_unit"),
+    item("E", "Profile Effectiveness", "profile_effectiveness"),
+    item("L", "My Login History", "login_history"),
+    item("P", "Whole/Decimal Degrees", "temp_precision"),
+    item("W", "Weather Location", "weather_location"),
+    item("G", "Greet Once/Always", "greet_once"),
+    item("M", "My Permissions", "my_permissions"),
+    item("H", "While I Was Away", "away_report"),
+];
+
+const ADMIN_MENU: &[MenuItem] = &[
+    item("1", "Show my profile", "view_profile"),
+    item("5", "View security logs", "security_logs"),
+    item("2", "Register a user", "register_user"),
+    item("6", "Clear user lockouts", "clear_lockouts"),
+    item("3", "View user(s)", "view_users"),
+    item("7", "Reset user password", "reset_password"),
+    item("4", "Manage Users", "manage_users"),
+    item("8", "Diagnostics history", "diagnostics_history"),
+    item("9", "Recent activity feed", "activity_feed"),
+    item("G", "Strict guest login", "strict_guest_login"),
+    item("L", "My login history", "login_history"),
+    item("K", "Lockout policy", "lockout_policy"),
+    item("R", "Reset all settings", "reset_settings"),
+    item("F", "Freeze guard", "freeze_guard"),
+    item("W", "Weather location", "weather_location"),
+    item("A", "Anonymize a user", "anonymize_user"),
+    item("T", "Sensor thresholds", "sensor_thresholds"),
+    item("M", "Menu layout", "menu_layout"),
+    item("P", "My permissions", "my_permissions"),
+    item("O", "Log out a user", "logout_user"),
+    item("C", "Consistency check", "consistency_check"),
+    item("E", "Away on last logout", "away_on_last_logout"),
+];
+
+const TECHNICIAN_MENU: &[MenuItem] = &[
+    item("1", "Show my profile", "view_profile"),
+    item("6", "View System Status", "system_status"),
+    item("2", "View all jobs", "view_jobs"),
+    item("7", "Indoor sensing", "indoor_sensing"),
+    item("3", "Access job", "access_job"),
+    item("8", "Outdoor weather", "outdoor_weather"),
+    item("4", "Manage guest(s)", "manage_guests"),
+    item("9", "Profile settings", "profile_settings"),
+    item("5", "Run diagnostics", "run_diagnostics"),
+    item("A", "Diagnostics history", "diagnostics_history"),
+    item("B", "Export jobs (CSV)", "export_jobs"),
+    item("S", "Manage Shortcuts", "manage_shortcuts"),
+    item("V", "Compact/Verbose", "display_mode"),
+    item("U", "°C/°F Units", "temp_unit"),
+    item("L", "My login history", "login_history"),
+    item("P", "Whole/Decimal", "temp_precision"),
+    item("G", "Greet Once/Always", "greet_once"),
+    item("M", "My permissions", "my_permissions"),
+];
+
+const GUEST_MENU: &[MenuItem] = &[
+    item("R", "Apply my last profile", "apply_last_profile"),
+    item("1", "View Profile", "view_profile"),
+    item("2", "Indoor Sensor", "indoor_sensing"),
+    item("3", "Outdoor Weather", "outdoor_weather"),
+    item("4", "HVAC Control", "hvac_control"),
+    item("5", "Choose Profile", "choose_profile"),
+    item("6", "Leave a Note for Homeowner", "leave_note"),
+    item("S", "Manage Shortcuts", "manage_shortcuts"),
+    item("V", "Compact/Verbose View", "display_mode"),
+    item("U", "°C/°F Units", "temp_unit"),
+    item("L", "My Login History", "login_history"),
+    item("P", "Whole/Decimal Degrees", "temp_precision"),
+    item("G", "Greet Once/Always", "greet_once"),
+];
+
+pub fn default_menu(role: &str) -> &'static [MenuItem] {
+    match role {
+        "homeowner" => HOMEOWNER_MENU,
+        "admin" => ADMIN_MENU,
+        "technician" => TECHNICIAN_MENU,
+        "guest" => GUEST_MENU,
+        _ => &[],
+    }
+}
+
+// Admins get no shortcut slots
+pub fn has_shortcut_keys(role: &str) -> bool {
+    role != "admin"
+}
+
+/* Every item of the role's menu with its position and hidden flag, sorted by
+   position. An item without a config row keeps its default position (10, 20,
+   30, ...), so a single moved item slots in between its neighbours. */
+pub fn menu_layout(conn: &Connection, role: &str) -> Result<Vec<(MenuItem, i64, bool)>> {
+    let mut stmt = conn.prepare("SELECT action, position, hidden FROM menu_config WHERE role = ?1")?;
+    let rows: Vec<(String, Option<i64>, i64)> = stmt
+        .query_map(params![role], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut layout: Vec<(MenuItem, i64, bool)> = default_menu(role)
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let row = rows.iter().find(|(action, _, _)| action == item.action);
+            let hidden = row.is_some_and(|(_, _, h)| *h != 0) && !PINNED_ACTIONS.contains(&item.action);
+            let position = row.and_then(|(_, p, _)| *p).unwrap_or((i as i64 + 1) * 10);
+            (*item, position, hidden)
+        })
+        .collect();
+    layout.sort_by_key(|(_, position, _)| *position);
+    Ok(layout)
+}
+
+// The items actually offered to the role, in display order
+pub fn load_menu(conn: &Connection, role: &str) -> Result<Vec<MenuItem>> {
+    Ok(menu_layout(conn, role)?
+        .into_iter()
+        .filter(|(_, _, hidden)| !hidden)
+        .map(|(item, _, _)| item)
+        .collect())
+}
+
+// Menu items each shortcut action stands in for; hiding all of them hides the shortcut too
+const SHORTCUT_MENU_ITEMS: &[(&str, &[&str])] = &[
+    ("apply_day", &["choose_profile", "hvac_control"]),
+    ("apply_night", &["choose_profile", "hvac_control"]),
+    ("apply_sleep", &["choose_profile", "hvac_control"]),
+    ("view_weather", &["outdoor_weather"]),
+    ("indoor_sensing", &["indoor_sensing"]),
+    ("system_status", &["system_status"]),
+    ("nudge_up", &["hvac_control"]),
+    ("nudge_down", &["hvac_control"]),
+];
+
+// True when a pinned shortcut's menu item is still offered in `items`
+pub fn shortcut_visible(items: &[MenuItem], shortcut: &str) -> bool {
+    SHORTCUT_MENU_ITEMS
+        .iter()
+        .find(|(action, _)| *action == shortcut)
+        .is_some_and(|(_, menu)| items.iter().any(|i| menu.contains(&i.action)))
+}
+
+/* Handler id for a key typed at the menu, case-insensitive. "logout" and
+   "shortcut" are the fixed keys; None means the key is not offered. */
+pub fn menu_action(items: &[MenuItem], role: &str, input: &str) -> Option<&'static str> {
+    let key = input.trim().to_ascii_uppercase();
+    match key.as_str() {
+        "0" => Some("logout"),
+        "X" | "Y" | "Z" if has_shortcut_keys(role) => Some("shortcut"),
+        _ => items.iter().find(|i| i.key == key).map(|i| i.action),
+    }
+}
+
+/* Keys for the "Select an option [...]" prompt, digits then letters. Runs of
+   three or more collapse, e.g. "0-9, A-E, G, H, L, M, P, R, S, U-Z". */
+pub fn menu_prompt_keys(items: &[MenuItem], role: &str) -> String {
+    let mut keys: Vec<char> = items.iter().filter_map(|i| i.key.chars().next()).collect();
+    keys.push('0');
+    if has_shortcut_keys(role) {
+        keys.extend(['X', 'Y', 'Z']);
+    }
+    // Digits sort before letters in ASCII
+    keys.sort_unstable();
+    keys.dedup();
+
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < keys.len() {
+        let mut j = i;
+        while j + 1 < keys.len() && keys[j + 1] as u32 == keys[j] as u32 + 1 {
+            j += 1;
+        }
+        if j - i >= 2 {
+            parts.push(format!("{}-{}", keys[i], keys[j]));
+        } else {
+            parts.extend(keys[i..=j].iter().map(|c| c.to_string()));
+        }
+        i = j + 1;
+    }
+    parts.join(", ")
+}
+
+// Hide or show one item (by handler id or key); pinned items cannot be hidden
+pub fn set_menu_item_hidden(conn: &Connection, admin_username: &str, role: &str, action: &str, hidden: bool) -> Result<()> {
+    let item = find_item(role, action)?;
+    if hidden && PINNED_ACTIONS.contains(&item.action) {
+        return Err(anyhow!("'{}' cannot be hidden.", item.label));
+    }
+    conn.execute(
+        "INSERT INTO menu_config (role, action, hidden, updated_by, updated_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now'))
+         ON CONFLICT(role, action) DO UPDATE SET hidden = excluded.hidden,
+             updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+        params![role, item.action, hidden as i64, admin_username],
+    )?;
+    let desc = format!("{} menu: [{}] {} {}", role, item.key, item.label, if hidden { "hidden" } else { "shown" });
+    logger::log_event(conn, admin_username, None, "SETTINGS_CHANGED", Some(&desc))?;
+    Ok(())
+}
+
+// Move one item; lower positions print first (defaults are 10, 20, 30, ...)
+pub fn set_menu_item_position(conn: &Connection, admin_username: &str, role: &str, action: &str, position: i64) -> Result<()> {
+    let item = find_item(role, action)?;
+    conn.execute(
+        "INSERT INTO menu_config (role, action, position, updated_by, updated_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now'))
+         ON CONFLICT(role, action) DO UPDATE SET position = excluded.position,
+             updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+        params![role, item.action, position, admin_username],
+    )?;
+    let desc = format!("{} menu: [{}] {} moved to position {}", role, item.key, item.label, position);
+    logger::log_event(conn, admin_username, None, "SETTINGS_CHANGED", Some(&desc))?;
+    Ok(())
+}
+
+// Back to the shipped layout for one role
+pub fn reset_menu(conn: &Connection, admin_username: &str, role: &str) -> Result<usize> {
+    let cleared = conn.execute("DELETE FROM menu_config WHERE role = ?1", params![role])?;
+    let desc = format!("{} menu restored to the default layout", role);
+    logger::log_event(conn, admin_username, None, "SETTINGS_CHANGED", Some(&desc))?;
+    Ok(cleared)
+}
+
+// Accepts the handler id or the key shown in the menu
+fn find_item(role: &str, action_or_key: &str) -> Result<&'static MenuItem> {
+    default_menu(role)
+        .iter()
+        .find(|i| i.action == action_or_key || i.key.eq_ignore_ascii_case(action_or_key))
+        .ok_or_else(|| anyhow!("The {} menu has no '{}' option.", role, action_or_key))
+}
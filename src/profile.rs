@@ -1,13 +1,12 @@
 use crate::hvac::{HVACMode, HVACSystem};
+use anyhow::Result;
 use rusqlite::Connection;
 use crate::logger;
 use crate::db;
-use chrono::{Local, Timelike};
-
-// Convert Celsius to Fahrenheit
-pub fn celsius_to_fahrenheit(celsius: f32) -> f32 {
-    (celsius * 9.0 / 5.0) + 32.0
-}
+use crate::clock::{Clock, RealClock};
+use crate::units::{self, TempUnit};
+use chrono::{Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use std::sync::Mutex;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HVACProfile {
@@ -49,6 +48,7 @@ pub fn apply_profile(conn: &Connection, hvac: &mut HVACSystem, profile: HVACProf
 
     let name = format!("{:?}", profile);
     let mut greeting_opt: Option<String> = None;
+    let mut humidity: Option<f32> = None;
     if let Ok(Some(row)) = db::get_profile_row(conn, &name) {
         // Map mode string -> HVACMode
         mode = match row.mode.as_str() {
@@ -61,6 +61,7 @@ pub fn apply_profile(conn: &Connection, hvac: &mut HVACSystem, profile: HVACProf
         };
         temperature = row.target_temp;
         greeting_opt = row.greeting;
+        humidity = row.target_humidity;
     }
     
     // Enforce mode-specific temperature ranges (e.g., Heating 25–32, Cooling 16–22)
@@ -76,6 +77,8 @@ pub fn apply_profile(conn: &Connection, hvac: &mut HVACSystem, profile: HVACProf
 
     hvac.set_mode(conn, mode);
     hvac.set_target_temperature(conn, temperature);
+    // A guest's limits may have moved it; the banner and logs show what was applied
+    temperature = hvac.target_temperature;
     
     // Update light status from profile
     if let Ok(Some(row)) = db::get_profile_row(conn, &name) {
@@ -85,97 +88,59 @@ pub fn apply_profile(conn: &Connection, hvac: &mut HVACSystem, profile: HVACProf
     // Set current profile name and save to database
     hvac.current_profile = Some(name.clone());
     hvac.save_state(conn);
+    hvac.apply_profile_humidity(conn, humidity);
     
-    // Display profile application with decorative format
+    // Display profile application in the user's preferred layout
     let greet = greeting_opt.as_deref().unwrap_or(profile.greeting_message());
+    let greet = take_greeting(conn, username, &name).then_some(greet);
     let now = Local::now();
     let time_str = now.format("%b %d, %Y %I:%M %p %Z").to_string();
     let scheduled = current_scheduled_profile();
-    let temp_f = celsius_to_fahrenheit(temperature);
-    
+
     // Get current temperature to determine actual runtime behavior
     let current_temp = crate::senser::get_indoor_temperature().unwrap_or(22.0);
-    
-    // Determine actual heater/AC status based on CURRENT mode, temperature, and profile settings
-    let (heater_display, ac_display, light_display) = if let Ok(Some(row)) = db::get_profile_row(conn, &name) {
-        let profile_light = row.light_status.clone();
-        
-        // Determine actual runtime status based on mode and current temperature
-        let (heater_on, ac_on) = match mode {
-            HVACMode::Heating => (true, false),  // Heating mode: heater on, AC off
-            HVACMode::Cooling => (false, true),  // Cooling mode: heater off, AC on
-            HVACMode::Auto => {
-                // Auto mode: check temperature difference to determine what's actually running
-                if current_temp < temperature - 0.5 {
-                    (true, false)  // Need heating
-                } else if current_temp > temperature + 0.5 {
-                    (false, true)  // Need cooling
-                } else {
-                    (false, false) // Temperature is at target
-                }
-            },
-            HVACMode::FanOnly => (false, false), // Fan only: both off
-            HVACMode::Off => (false, false),     // Off mode: both off
-        };
-        
-        (if heater_on { "ON" } else { "OFF" }, if ac_on { "ON" } else { "OFF" }, profile_light)
-    } else {
-        // Fallback based on mode only
-        let (heater_on, ac_on) = match mode {
-            HVACMode::Heating => (true, false),
-            HVACMode::Cooling => (false, true),
-            HVACMode::Auto => {
-                if current_temp < temperature - 0.5 {
-                    (true, false)
-                } else if current_temp > temperature + 0.5 {
-                    (false, true)
-                } else {
-                    (false, false)
-                }
-            },
-            _ => (false, false),
-        };
-        (if heater_on { "ON" } else { "OFF" }, if ac_on { "ON" } else { "OFF" }, "OFF".to_string())
+    let (heater_on, ac_on) = hvac.decide(current_temp, db::get_presence(conn).unwrap_or_default());
+    let light = match db::get_profile_row(conn, &name) {
+        Ok(Some(row)) => row.light_status,
+        _ => "OFF".to_string(),
     };
-    
-    let desc = format!(
-        "Temperature: {:.1}°C / {:.1}°F\n🔥 Heater: {} | ❄️ AC: {} | 💡 Light: {}",
-        temperature, temp_f, heater_display, ac_display, light_display
-    );
-    
-    println!("🌈✨=============================================✨🌈");
-    println!("🏡  HVAC Profile Applied");
-    println!();
-    println!("📋  Profile: {:?}", profile);
-    println!();
-    println!("{}", greet);
-    println!();
-    
+
     // Special display for Vacation profile with dates
-    if matches!(profile, HVACProfile::Vacation) {
-        if let Ok(Some(vac_profile)) = db::get_profile_row(conn, "Vacation") {
-            if let (Some(start), Some(end)) = (vac_profile.vacation_start_date, vac_profile.vacation_end_date) {
-                println!("🏖️  Vacation mode ON from {} to {}", start, end);
-                println!();
-            }
+    let vacation = if matches!(profile, HVACProfile::Vacation) {
+        match db::get_profile_row(conn, "Vacation") {
+            Ok(Some(row)) => row.vacation_start_date.zip(row.vacation_end_date),
+            _ => None,
         }
-    }
-    
-    println!("⚙️  Mode: {:?}", mode);
-    println!();
-    println!("🎯  Target Temperature: {:.1}°C / {:.1}°F", temperature, temp_f);
-    println!();
-    
-    if scheduled == profile {
-        println!("⏰  Schedule: Within {:?} window ✅", scheduled);
     } else {
-        println!("⏰  Schedule: {:?} window (manual override)", scheduled);
+        None
+    };
+
+    let schedule = if scheduled == profile {
+        format!("Within {:?} window ✅", scheduled)
+    } else {
+        format!("{:?} window (manual override)", scheduled)
+    };
+
+    let view = ProfileAppliedView {
+        label: name.clone(),
+        greeting: greet,
+        mode,
+        target_c: temperature,
+        heater_on,
+        ac_on,
+        light: &light,
+        fan: None,
+        description: None,
+        vacation: vacation.as_ref().map(|(s, e)| (s.as_str(), e.as_str())),
+        schedule: Some(schedule),
+        time: &time_str,
+    };
+    let display = db::get_display_mode(conn, username).unwrap_or_default();
+    println!("{}", render_profile_applied(&view, display));
+
+    if let Some(note) = schedule_advisory(&name, scheduled) {
+        println!("{}", note);
     }
-    println!();
-    println!("📝  Description: {}", desc);
-    println!();
-    println!("🕒  Time: {}", time_str);
-    println!("🌈✨=============================================✨🌈");
     
     let profile_name = name.clone();
     
@@ -193,37 +158,416 @@ pub fn apply_profile(conn: &Connection, hvac: &mut HVACSystem, profile: HVACProf
     let _ = db::log_profile_applied(conn, username, user_role, &profile_name, &mode_str, temperature);
 }
 
-// Determine current scheduled profile based on local time windows.
+/* Check a profile's mode and target before it is saved. A temperature outside
+   the mode's range is never stored as-is: `confirm_adjust` is asked whether to
+   snap it to the nearest limit, and None means the user declined (cancel). */
+pub fn reconcile_profile_temperature(mode: &str, temp: f32, confirm_adjust: impl FnOnce(&str) -> bool) -> Option<f32> {
+    let Some(hvac_mode) = HVACMode::from_name(mode) else {
+        return Some(temp);
+    };
+    if hvac_mode.is_valid_temperature_for_mode(temp) {
+        return Some(temp);
+    }
+
+    let (min_t, max_t) = hvac_mode.temperature_range();
+    let adjusted = hvac_mode.clamp_temperature(temp);
+    println!(
+        "⚠️  {:.1}°C is outside the {} range ({:.0}–{:.0}°C).",
+        temp, mode, min_t, max_t
+    );
+    confirm_adjust(&format!("Use {:.1}°C instead?", adjusted)).then_some(adjusted)
+}
+
+// ======================================================
+//                 GREETING SUPPRESSION
+// ======================================================
+
+// Last profile whose apply banner ran this session. The CLI runs one session
+// per process (see auth::ACTIVE_SESSION), so a process-wide value is enough.
+static LAST_GREETED: Mutex<Option<String>> = Mutex::new(None);
+
+// With greet-once on, re-applying the profile that was last greeted stays quiet
+pub fn should_show_greeting(greet_once: bool, last_greeted: Option<&str>, profile_name: &str) -> bool {
+    !greet_once || last_greeted.is_none_or(|last| !last.eq_ignore_ascii_case(profile_name))
+}
+
+// Decide for this apply and remember the profile for the next one
+pub fn take_greeting(conn: &Connection, username: &str, profile_name: &str) -> bool {
+    let greet_once = db::get_greet_once(conn, username).unwrap_or(false);
+    let Ok(mut last) = LAST_GREETED.lock() else { return true };
+    let show = should_show_greeting(greet_once, last.as_deref(), profile_name);
+    *last = Some(profile_name.to_string());
+    show
+}
+
+// Called when a session starts or ends so the next user is greeted afresh
+pub fn reset_greeting_memory() {
+    if let Ok(mut last) = LAST_GREETED.lock() {
+        *last = None;
+    }
+}
+
+// ======================================================
+//               PROFILE APPLIED RENDERING
+// ======================================================
+
+// What the "profile applied" banner shows, for built-in and custom profiles alike
+pub struct ProfileAppliedView<'a> {
+    pub label: String,
+    pub greeting: Option<&'a str>,
+    pub mode: HVACMode,
+    pub target_c: f32,
+    pub heater_on: bool,
+    pub ac_on: bool,
+    pub light: &'a str,
+    pub fan: Option<&'a str>,
+    pub description: Option<&'a str>,
+    pub vacation: Option<(&'a str, &'a str)>,
+    pub schedule: Option<String>,
+    pub time: &'a str,
+}
+
+pub fn render_profile_applied(view: &ProfileAppliedView, display: db::DisplayMode) -> String {
+    let on_off = |b: bool| if b { "ON" } else { "OFF" };
+    let target = units::format_temp_dual(view.target_c, TempUnit::Celsius);
+    let mut devices = format!(
+        "🔥 Heater: {} | ❄️ AC: {} | 💡 Light: {}",
+        on_off(view.heater_on), on_off(view.ac_on), view.light
+    );
+    if let Some(fan) = view.fan {
+        devices.push_str(&format!(" | 🌀 Fan: {}", fan));
+    }
+
+    match display {
+        db::DisplayMode::Compact => {
+            let mut lines = vec![
+                format!(
+                    "📋 {} applied | ⚙️ {:?} | 🎯 {}",
+                    view.label, view.mode, target
+                ),
+                devices,
+            ];
+            let mut extra: Vec<String> = Vec::new();
+            if let Some((start, end)) = view.vacation {
+                extra.push(format!("🏖️ {} → {}", start, end));
+            }
+            if let Some(schedule) = &view.schedule {
+                extra.push(format!("⏰ {}", schedule));
+            }
+            if !extra.is_empty() {
+                lines.push(extra.join(" | "));
+            }
+            lines.join("\n")
+        }
+        db::DisplayMode::Verbose => {
+            let mut lines: Vec<String> = vec![
+                "🌈✨=============================================✨🌈".to_string(),
+                "🏡  HVAC Profile Applied".to_string(),
+                String::new(),
+                format!("📋  Profile: {}", view.label),
+                String::new(),
+            ];
+            if let Some(greeting) = view.greeting {
+                lines.push(greeting.to_string());
+                lines.push(String::new());
+            }
+            if let Some((start, end)) = view.vacation {
+                lines.push(format!("🏖️  Vacation mode ON from {} to {}", start, end));
+                lines.push(String::new());
+            }
+            lines.push(format!("⚙️  Mode: {:?}", view.mode));
+            lines.push(String::new());
+            lines.push(format!("🎯  Target Temperature: {}", target));
+            lines.push(String::new());
+            if let Some(schedule) = &view.schedule {
+                lines.push(format!("⏰  Schedule: {}", schedule));
+                lines.push(String::new());
+            }
+            lines.push(format!("📝  Description: Temperature: {}", target));
+            lines.push(format!("    {}", devices));
+            if let Some(desc) = view.description {
+                lines.push(format!("    {}", desc));
+            }
+            lines.push(String::new());
+            lines.push(format!("🕒  Time: {}", view.time));
+            lines.push("🌈✨=============================================✨🌈".to_string());
+            lines.join("\n")
+        }
+    }
+}
+
+// The schedule's profile for now, or None while a temporary override holds it back
+pub fn scheduled_profile_to_apply(conn: &Connection) -> Option<HVACProfile> {
+    match schedule_suppression(conn) {
+        Some(_) => None,
+        None => Some(current_scheduled_profile()),
+    }
+}
+
+pub const VACATION_SUPPRESSION: &str = "Schedule suppressed: vacation mode active";
+pub const HOLD_SUPPRESSION: &str = "Schedule paused: temporary hold active";
+
+// Why the schedule is not switching profiles right now, if something holds it back
+pub fn schedule_suppression(conn: &Connection) -> Option<&'static str> {
+    if db::vacation_mode_active(conn).unwrap_or(false) {
+        Some(VACATION_SUPPRESSION)
+    } else if let Ok(Some(_)) = db::active_override(conn) {
+        Some(HOLD_SUPPRESSION)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleCheck {
+    Applied(HVACProfile),
+    NotDue,
+    Suppressed(&'static str),
+}
+
+// Start of the schedule window that contains `now`
+pub fn current_window_start(now: NaiveDateTime) -> NaiveDateTime {
+    let today = now.date();
+    let yesterday = today.pred_opt().unwrap_or(today);
+    [yesterday, today]
+        .iter()
+        .flat_map(|day| SCHEDULE_WINDOWS.iter().filter_map(move |(h, m, _)| day.and_hms_opt(*h, *m, 0)))
+        .filter(|start| *start <= now)
+        .max()
+        .unwrap_or(now)
+}
+
+/* Switches to the scheduled profile once a window boundary has passed since the
+   last profile was applied. Nothing is switched on a fresh install (no profile
+   applied yet), and a due switch is skipped while vacation or a hold is active. */
+pub fn apply_scheduled_profile_if_due(conn: &Connection, hvac: &mut HVACSystem, username: &str, user_role: &str) -> ScheduleCheck {
+    apply_scheduled_profile_if_due_at(conn, hvac, username, user_role, RealClock.now_local())
+}
+
+pub fn apply_scheduled_profile_if_due_at(
+    conn: &Connection,
+    hvac: &mut HVACSystem,
+    username: &str,
+    user_role: &str,
+    now: NaiveDateTime,
+) -> ScheduleCheck {
+    let last_applied = db::last_profile_applied_at(conn)
+        .ok()
+        .flatten()
+        .map(|utc| utc.with_timezone(&Local).naive_local());
+    let due = last_applied.is_some_and(|at| at < current_window_start(now));
+    if !due {
+        return ScheduleCheck::NotDue;
+    }
+    if let Some(reason) = schedule_suppression(conn) {
+        return ScheduleCheck::Suppressed(reason);
+    }
+    let profile = scheduled_profile_at(now.time());
+    apply_profile(conn, hvac, profile, username, user_role);
+    ScheduleCheck::Applied(profile)
+}
+
+/* Called after a logout or a stale-session sweep. With the admin setting on, no
+   live session left and no vacation or hold in place, the system applies Away
+   and remembers it did so. Returns
+   whether Away was applied. */
+pub fn apply_away_if_house_empty(conn: &Connection) -> Result<bool> {
+    if !db::away_on_last_logout(conn)?
+        || db::active_session_count(conn)? > 0
+        || db::vacation_mode_active(conn)?
+        || db::active_override(conn)?.is_some()
+    {
+        return Ok(false);
+    }
+    let mut hvac = HVACSystem::new(conn);
+    if hvac.current_profile.as_deref() == Some("Away") {
+        return Ok(false);
+    }
+    apply_profile(conn, &mut hvac, HVACProfile::Away, "system", "system");
+    db::set_idle_away_applied(conn, true)?;
+    logger::log_event(conn, "system", None, "HVAC", Some("Away applied: last user logged out"))?;
+    Ok(true)
+}
+
+/* Away from an empty house is only the idle default: the first login after it
+   goes back to the scheduled profile (unless a hold or vacation holds the
+   schedule back). A manual profile change since then just clears the marker. */
+pub fn resume_from_idle_away(conn: &Connection, username: &str, user_role: &str) -> Option<HVACProfile> {
+    if !db::idle_away_applied(conn).unwrap_or(false) {
+        return None;
+    }
+    let _ = db::set_idle_away_applied(conn, false);
+    let mut hvac = HVACSystem::new(conn);
+    if hvac.current_profile.as_deref() != Some("Away") {
+        return None;
+    }
+    let profile = scheduled_profile_to_apply(conn)?;
+    println!("\n🏠 Welcome back. Leaving the idle Away profile for the {:?} schedule.", profile);
+    apply_profile(conn, &mut hvac, profile, username, user_role);
+    Some(profile)
+}
+
+// Once an override runs out, put the scheduled profile back
+pub fn revert_expired_override(conn: &Connection, username: &str, user_role: &str) {
+    let Ok(Some(expired)) = db::take_expired_override(conn) else {
+        return;
+    };
+    let Some(profile) = scheduled_profile_to_apply(conn) else {
+        return;
+    };
+    println!(
+        "\n⏰ Temporary hold at {:.1}°C ({}) has ended. Returning to the {:?} schedule.",
+        expired.target_temp, expired.mode, profile
+    );
+    let mut hvac = HVACSystem::new(conn);
+    apply_profile(conn, &mut hvac, profile, username, user_role);
+}
+
+// Advisory shown when a profile is applied outside its schedule window.
+// Never blocks the action; returns None when the applied profile matches the schedule.
+pub fn schedule_advisory(applied_name: &str, scheduled: HVACProfile) -> Option<String> {
+    let scheduled_name = format!("{:?}", scheduled);
+    if applied_name.eq_ignore_ascii_case(&scheduled_name) {
+        None
+    } else {
+        Some(format!("Note: the current schedule suggests the '{}' profile.", scheduled_name))
+    }
+}
+
+// Start (hour, minute) of each schedule window, in day order.
 // Assumptions (to avoid gaps):
 // - Day: 06:00–18:00
 // - Night: 18:01–23:00
 // - Sleep: 23:01–05:59
-pub fn current_scheduled_profile() -> HVACProfile {
-    let now = Local::now();
-    let hour = now.hour();
-    let minute = now.minute();
-
-    // Day window 06:00–18:00 inclusive
-    if (6..=18).contains(&hour) {
-        // If exactly 18:01 and beyond, handled below
-        if hour == 18 && minute > 0 {
-            // fall through
-        } else {
-            return HVACProfile::Day;
+const SCHEDULE_WINDOWS: [(u32, u32, HVACProfile); 3] = [
+    (6, 0, HVACProfile::Day),
+    (18, 1, HVACProfile::Night),
+    (23, 1, HVACProfile::Sleep),
+];
+
+// Where a profile is referenced, shown in the profile list before anything is deleted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileUsage {
+    pub active: bool,    // the profile the HVAC state currently runs
+    pub scheduled: bool, // a daily schedule window, or Vacation with dates booked
+}
+
+impl ProfileUsage {
+    // e.g. "[active][scheduled]", empty when unused
+    pub fn tags(&self) -> String {
+        let mut tags = String::new();
+        if self.active {
+            tags.push_str("[active]");
+        }
+        if self.scheduled {
+            tags.push_str("[scheduled]");
         }
+        tags
+    }
+}
+
+pub fn profile_usage(conn: &Connection, name: &str) -> Result<ProfileUsage> {
+    let (_, _, _, current) = db::get_hvac_state(conn)?;
+    let active = current.is_some_and(|c| c.eq_ignore_ascii_case(name));
+    let in_window = SCHEDULE_WINDOWS.iter().any(|(_, _, p)| format!("{:?}", p).eq_ignore_ascii_case(name));
+    let vacation_booked = name.eq_ignore_ascii_case("Vacation")
+        && db::get_profile_row(conn, "Vacation")?.is_some_and(|p| p.vacation_start_date.is_some());
+    Ok(ProfileUsage { active, scheduled: in_window || vacation_booked })
+}
+
+// Determine current scheduled profile based on local time windows.
+pub fn current_scheduled_profile() -> HVACProfile {
+    scheduled_profile_at(RealClock.now_local().time())
+}
+
+// The window a time of day falls in; before 06:00 is still last night's Sleep
+pub fn scheduled_profile_at(time: NaiveTime) -> HVACProfile {
+    let now = (time.hour(), time.minute());
+    SCHEDULE_WINDOWS
+        .iter()
+        .rev()
+        .find(|(h, m, _)| now >= (*h, *m))
+        .map(|(_, _, p)| *p)
+        .unwrap_or(HVACProfile::Sleep)
+}
+
+// What the thermostat will do over the next `hours`: (what, when) in local time.
+// An active hold comes first; windows it covers are skipped since the revert
+// applies whichever profile is scheduled when it ends.
+pub fn upcoming_schedule(conn: &Connection, hours: u32) -> Result<Vec<(String, String)>> {
+    upcoming_schedule_from(conn, RealClock.now_local(), hours)
+}
+
+pub fn upcoming_schedule_from(conn: &Connection, now: NaiveDateTime, hours: u32) -> Result<Vec<(String, String)>> {
+    let horizon = now + Duration::hours(hours as i64);
+    let fmt = "%Y-%m-%d %H:%M";
+    let mut upcoming = Vec::new();
+    let mut after = now;
+
+    if let Some(hold) = db::active_override(conn)? {
+        let ends = NaiveDateTime::parse_from_str(&hold.expires_at, "%Y-%m-%d %H:%M:%S")
+            .map(|utc| Utc.from_utc_datetime(&utc).with_timezone(&Local).naive_local())?;
+        upcoming.push((
+            format!("Hold {:.1}°C ({}) ends, back to {:?}", hold.target_temp, hold.mode, scheduled_profile_at(ends.time())),
+            ends.format(fmt).to_string(),
+        ));
+        after = ends;
     }
 
-    // Night window 18:01–23:00
-    if (18..=23).contains(&hour) {
-        if (hour > 18) || (hour == 18 && minute >= 1) {
-            if hour == 23 && minute > 0 {
-                // 23:01 enters Sleep
-            } else {
-                return HVACProfile::Night;
+    let mut day = now.date();
+    while day <= horizon.date() {
+        for (h, m, profile) in SCHEDULE_WINDOWS {
+            let Some(start) = day.and_hms_opt(h, m, 0) else { continue };
+            if start > after && start <= horizon {
+                upcoming.push((format!("{:?}", profile), start.format(fmt).to_string()));
             }
         }
+        let Some(next) = day.succ_opt() else { break };
+        day = next;
     }
+    Ok(upcoming)
+}
 
-    // Sleep 23:01–05:59
-    HVACProfile::Sleep
+// ======================================================
+//                 PROFILE EFFECTIVENESS
+// ======================================================
+
+// Average |indoor - target| per profile over the last `days`, best-held first.
+// Each reading is charged to the latest PROFILE_APPLIED at or before it, using
+// the target logged with that event; profiles with no readings are left out.
+pub fn profile_effectiveness(conn: &Connection, days: u32) -> Result<Vec<(String, f32)>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.profile_name,
+                AVG(ABS(r.temperature_c - CAST(substr(a.new_value, instr(a.new_value, '|') + 1) AS REAL))) AS deviation
+         FROM sensor_readings r
+         JOIN hvac_activity_log a ON a.id = (
+             SELECT id FROM hvac_activity_log
+             WHERE action_type = 'PROFILE_APPLIED' AND datetime(timestamp) <= datetime(r.recorded_at)
+             ORDER BY datetime(timestamp) DESC, id DESC
+             LIMIT 1
+         )
+         WHERE datetime(r.recorded_at) >= datetime('now', ?1)
+           AND a.profile_name IS NOT NULL AND instr(a.new_value, '|') > 0
+         GROUP BY a.profile_name
+         ORDER BY deviation ASC, a.profile_name",
+    )?;
+    let rows = stmt.query_map([format!("-{} days", days)], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)? as f32))
+    })?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+pub fn show_profile_effectiveness(conn: &Connection, days: u32) -> Result<()> {
+    let results = profile_effectiveness(conn, days)?;
+    println!("\n===== Profile Effectiveness (last {} days) =====", days);
+    if results.is_empty() {
+        println!("No readings recorded while a profile was active.");
+        return Ok(());
+    }
+    println!("{:<15} Avg deviation from target", "Profile");
+    println!("{}", "-".repeat(42));
+    for (name, deviation) in results {
+        println!("{:<15} ±{:.1}°C", name, deviation);
+    }
+    Ok(())
 }
@@ -2,7 +2,9 @@
 //! Purpose：Get all the indoor senser data(temperature、humidity、CO level)
 //! - input validation(boundary、NaN、infinite) and error handling(prevent panic)
 
+use anyhow::Result as AnyResult;
 use rand::Rng;
+use rusqlite::{params, Connection, OptionalExtension};
 use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -35,21 +37,96 @@ pub enum SensorError {
 }
 
 // Dashboard for indoor data
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Thresholds {
-    pub temp_warn_hi: f32, // °C
-    pub co_warn_hi: f32,   // ppm
+    pub temp_comfort_lo: f32, // °C, readings outside the band are flagged
+    pub temp_comfort_hi: f32, // °C
+    pub humidity_lo: f32,     // %
+    pub humidity_hi: f32,     // %
+    pub co_warn: f32,         // ppm
+    pub co_danger: f32,       // ppm
+    pub humidity_target: Option<f32>, // % from the active profile, if it sets one (not persisted)
 }
 
 impl Default for Thresholds {
     fn default() -> Self {
         Self {
-            temp_warn_hi: 32.0, // Warning for high temperature
-            co_warn_hi: 35.0,   // Warning for high CO level
+            temp_comfort_lo: 18.0,
+            temp_comfort_hi: 32.0, // Warning for high temperature
+            humidity_lo: 30.0,
+            humidity_hi: 60.0,
+            co_warn: 35.0,         // Warning for high CO level
+            co_danger: 100.0,
+            humidity_target: None,
+        }
+    }
+}
+
+impl Thresholds {
+    // Each band must be ordered and inside the sensor's own range
+    pub fn validate(&self) -> Result<(), SensorError> {
+        let bands = [
+            (SensorType::TemperatureC, self.temp_comfort_lo, self.temp_comfort_hi, "comfort band must be low < high within -15..45°C"),
+            (SensorType::HumidityPct, self.humidity_lo, self.humidity_hi, "humidity band must be low < high within 0..100%"),
+            (SensorType::COPpm, self.co_warn, self.co_danger, "CO warning must be below CO danger within 0..1000ppm"),
+        ];
+        for (kind, lower, upper, reason) in bands {
+            validate_finite(lower)?;
+            validate_finite(upper)?;
+            let (lo, hi) = default_bounds(kind);
+            if lower >= upper || lower < lo || upper > hi {
+                return Err(SensorError::InvalidBounds { lower, upper, reason });
+            }
         }
+        Ok(())
     }
 }
 
+// Saved thresholds, or the defaults when none were ever saved
+pub fn load_thresholds(conn: &Connection) -> AnyResult<Thresholds> {
+    let saved = conn
+        .query_row(
+            "SELECT temp_comfort_lo, temp_comfort_hi, humidity_lo, humidity_hi, co_warn, co_danger
+               FROM sensor_thresholds WHERE id = 1",
+            [],
+            |r| {
+                Ok(Thresholds {
+                    temp_comfort_lo: r.get(0)?,
+                    temp_comfort_hi: r.get(1)?,
+                    humidity_lo: r.get(2)?,
+                    humidity_hi: r.get(3)?,
+                    co_warn: r.get(4)?,
+                    co_danger: r.get(5)?,
+                    humidity_target: None,
+                })
+            },
+        )
+        .optional()?;
+    Ok(saved.unwrap_or_default())
+}
+
+// Validates, then replaces the single saved row and logs the change
+pub fn save_thresholds(conn: &Connection, thresholds: &Thresholds, updated_by: &str) -> AnyResult<()> {
+    thresholds.validate()?;
+    let t = thresholds;
+    conn.execute(
+        "INSERT INTO sensor_thresholds
+             (id, temp_comfort_lo, temp_comfort_hi, humidity_lo, humidity_hi, co_warn, co_danger, updated_by, updated_at)
+         VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'))
+         ON CONFLICT(id) DO UPDATE SET
+             temp_comfort_lo = excluded.temp_comfort_lo, temp_comfort_hi = excluded.temp_comfort_hi,
+             humidity_lo = excluded.humidity_lo, humidity_hi = excluded.humidity_hi,
+             co_warn = excluded.co_warn, co_danger = excluded.co_danger,
+             updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+        params![t.temp_comfort_lo, t.temp_comfort_hi, t.humidity_lo, t.humidity_hi, t.co_warn, t.co_danger, updated_by],
+    )?;
+    crate::logger::log_event(conn, updated_by, None, "SETTINGS_CHANGED", Some(&format!(
+        "Sensor thresholds set: comfort {:.1}–{:.1}°C, humidity {:.0}–{:.0}%, CO warn {:.0}ppm / danger {:.0}ppm",
+        t.temp_comfort_lo, t.temp_comfort_hi, t.humidity_lo, t.humidity_hi, t.co_warn, t.co_danger
+    )))?;
+    Ok(())
+}
+
 impl fmt::Display for SensorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -171,14 +248,24 @@ pub fn run_dashboard_inline(thresholds: Thresholds) -> Result<(), SensorError> {
             let red = |s: String| format!("\x1b[31m{}\x1b[0m", s);
             let green = |s: String| format!("\x1b[32m{}\x1b[0m", s);
 
-            let temp_str = if r.temperature_c >= thresholds.temp_warn_hi {
+            let yellow = |s: String| format!("\x1b[33m{}\x1b[0m", s);
+
+            let temp_str = if r.temperature_c >= thresholds.temp_comfort_hi || r.temperature_c < thresholds.temp_comfort_lo {
                 red(format!("{:.1}°C", r.temperature_c))
             } else {
                 green(format!("{:.1}°C", r.temperature_c))
             };
 
-            let co_str = if r.co_ppm >= thresholds.co_warn_hi {
-                red(format!("{:.1}ppm", r.co_ppm))
+            let humidity_str = if r.humidity_pct > thresholds.humidity_hi || r.humidity_pct < thresholds.humidity_lo {
+                red(format!("{:.1}%", r.humidity_pct))
+            } else {
+                green(format!("{:.1}%", r.humidity_pct))
+            };
+
+            let co_str = if r.co_ppm >= thresholds.co_danger {
+                red(format!("{:.1}ppm (DANGER)", r.co_ppm))
+            } else if r.co_ppm >= thresholds.co_warn {
+                yellow(format!("{:.1}ppm (warning)", r.co_ppm))
             } else {
                 green(format!("{:.1}ppm", r.co_ppm))
             };
@@ -189,7 +276,15 @@ pub fn run_dashboard_inline(thresholds: Thresholds) -> Result<(), SensorError> {
             println!("🌈✨=============================================✨🌈");
             println!("🏠  Indoor Air Status");
             println!("🌡️  Temperature: {}", temp_str);
-            println!("💦  Humidity: {:.1}%", r.humidity_pct);
+            match thresholds.humidity_target {
+                Some(target) => {
+                    println!("💦  Humidity: {} (target {:.0}%)", humidity_str, target);
+                    if let Some(tip) = crate::hvac::humidity_suggestion(r.humidity_pct, target) {
+                        println!("💡  {}", tip);
+                    }
+                }
+                None => println!("💦  Humidity: {}", humidity_str),
+            }
             println!("🫧  CO: {}", co_str);
             println!("🕒  Time: {}", formatted);
             println!("🌈✨=============================================✨🌈");
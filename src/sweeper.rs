@@ -0,0 +1,125 @@
+// ======================================================
+//                  BACKGROUND SWEEPER
+// ======================================================
+// Expired grants, stale sessions and finished vacations are otherwise only
+// cleaned up when some menu happens to look at them. The sweeper runs the
+// same cleanup on a timer so the database never lags far behind the clock.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::clock::{Clock, RealClock};
+use crate::{db, logger, profile, senser};
+
+pub const DEFAULT_SWEEP_INTERVAL_SECS: u64 = 60;
+// Seconds between sweeps; 0 turns the background sweeper off
+pub const SWEEP_INTERVAL_ENV: &str = "SMART_THERMOSTAT_SWEEP_SECS";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SweepReport {
+    pub grants_expired: usize,
+    pub sessions_removed: usize,
+    pub vacation_cleared: bool,
+}
+
+pub fn run_sweep(conn: &Connection) -> Result<SweepReport> {
+    run_sweep_with_clock(conn, &RealClock)
+}
+
+// Grants expire and vacations end by the clock's time
+pub fn run_sweep_with_clock(conn: &Connection, clock: &dyn Clock) -> Result<SweepReport> {
+    let report = SweepReport {
+        grants_expired: db::sweep_expire_grants_with_clock(conn, clock)?,
+        sessions_removed: remove_stale_sessions(conn)?,
+        vacation_cleared: clear_finished_vacation(conn, clock.now_local().date())?,
+    };
+    record_cooldown_snapshot(conn, clock)?;
+    Ok(report)
+}
+
+// After an emergency all-off, every sweep adds an indoor reading for the cool-down report
+fn record_cooldown_snapshot(conn: &Connection, clock: &dyn Clock) -> Result<()> {
+    if db::cooldown_in_progress(conn, clock)?
+        && let Ok(temp) = senser::get_indoor_temperature()
+    {
+        db::record_sensor_reading(conn, temp, senser::get_indoor_humidity().ok())?;
+    }
+    Ok(())
+}
+
+// A session that timed out is a logout too, so the last one going can leave the house in Away
+fn remove_stale_sessions(conn: &Connection) -> Result<usize> {
+    let removed = db::cleanup_stale_sessions(conn)?;
+    if removed > 0 {
+        profile::apply_away_if_house_empty(conn)?;
+    }
+    Ok(removed)
+}
+
+// Vacation dates are stored mm-dd-yyyy; once the end date has passed the profile is released
+fn clear_finished_vacation(conn: &Connection, today: NaiveDate) -> Result<bool> {
+    let Some(profile) = db::get_profile_row(conn, "Vacation")? else { return Ok(false) };
+    let Some(end) = profile.vacation_end_date.as_deref() else { return Ok(false) };
+    let Ok(end_date) = NaiveDate::parse_from_str(end.trim(), "%m-%d-%Y") else { return Ok(false) };
+    if end_date >= today {
+        return Ok(false);
+    }
+    db::clear_vacation_dates(conn)?;
+    let _ = logger::log_event(conn, "system", None, "HVAC", Some(&format!("Vacation ended on {}; dates cleared", end)));
+    Ok(true)
+}
+
+// Interval from SWEEP_INTERVAL_ENV, None when the sweeper is switched off
+pub fn configured_interval() -> Option<Duration> {
+    let secs = std::env::var(SWEEP_INTERVAL_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SWEEP_INTERVAL_SECS);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+// Handle to the sweeper thread; dropping it stops the thread and waits for it
+pub struct Sweeper {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Sweeper {
+    pub fn start(conn: Arc<Mutex<Connection>>, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            // Anything but a timeout (a stop message or a dropped handle) ends the loop
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                if let Ok(conn) = conn.lock()
+                    && let Err(e) = run_sweep(&conn)
+                {
+                    eprintln!("⚠️  Background sweep failed: {e}");
+                }
+            }
+        });
+        Self { stop: Some(stop), handle: Some(handle) }
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Sweeper {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
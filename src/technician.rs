@@ -1,12 +1,10 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use rusqlite::{params, Connection, OptionalExtension};
-use rpassword::read_password;
-use std::io::{self, Write};
-use zeroize::Zeroizing;
+use std::{fs, io::{self, Write}, path::Path};
 
 use crate::auth;
 use crate::db;
-use crate::function::wait_for_enter;
+use crate::function::{confirm, parse_menu_index, prompt_input, wait_for_enter};
 
 pub fn homeowner_request_tech(conn: &mut Connection) -> Result<()> {
 
@@ -91,11 +89,9 @@ pub fn homeowner_request_tech(conn: &mut Connection) -> Result<()> {
         let mut acc = String::new();
         let mut saw_content = false;
 
-        loop {
-            let mut line = String::new();
-            let n = io::stdin().read_line(&mut line)?;
-            if n == 0 { break; } // EOF
-            let trimmed = line.trim_end_matches(&['\r','\n'][..]);
+        // stops on EOF
+        while let Some(line) = prompt_input() {
+            let trimmed = line.as_str();
 
             // ignore leading empty line
             if trimmed.is_empty() && !saw_content {
@@ -133,15 +129,13 @@ pub fn homeowner_request_tech(conn: &mut Connection) -> Result<()> {
     };
 
     // Minutes prompt/validation
-    let minutes: i64 = loop {
+    let duration: db::AccessDuration = loop {
         print!("Please specify minutes for the technician to access [30|60|90|120]: ");
         io::stdout().flush().ok();
-        let mut s = String::new();
-        io::stdin().read_line(&mut s)?;
-        let s = s.trim();
-        match s.parse::<i64>() {
-            Ok(m) if [30, 60, 90, 120].contains(&m) => break m,
-            _ => println!("Enter one of: 30, 60, 90, 120."),
+        let Some(s) = prompt_input() else { return Ok(()); };
+        match s.parse::<db::AccessDuration>() {
+            Ok(d) => break d,
+            Err(_) => println!("Enter one of: 30, 60, 90, 120."),
         }
     };
 
@@ -168,21 +162,33 @@ pub fn homeowner_request_tech(conn: &mut Connection) -> Result<()> {
     let idx: usize = loop {
         print!("\nSelect a technician by number: ");
         io::stdout().flush().ok();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        match input.trim().parse::<usize>() {
-            Ok(n) if n >= 1 && n <= techs.len() => break n - 1,
-            _ => println!("Invalid selection. Enter 1..{}", techs.len()),
+        let Some(input) = prompt_input() else { return Ok(()); };
+        match parse_menu_index(&input, techs.len()) {
+            Some(i) => break i,
+            None => println!("Invalid selection. Enter 1..{}", techs.len()),
         }
     };
     let technician_username = &techs[idx];
 
-    // Create job/grant (ASSIGNED)
-    let job_id = db::grant_technician_access(conn, &actor, technician_username, minutes, &desc)?;
+    // Review the grant before anything is written
+    let outcome = db::grant_technician_access_confirmed(conn, &actor, technician_username, duration, &desc, |summary| {
+        println!("\nPlease review this access grant:");
+        println!("  Technician: {}", summary.technician_username);
+        println!("  Duration: {} minutes", summary.duration.to_minutes());
+        println!("  Description: {}", summary.job_desc);
+        confirm("Grant this access?", false)
+    })?;
+    let job_id = match outcome {
+        db::GrantOutcome::Granted(job_id) => job_id,
+        db::GrantOutcome::Cancelled => {
+            println!("Request cancelled. No access was granted.");
+            return Ok(());
+        }
+    };
     println!("\nRequest recorded:");
     println!("  Homeowner: {}", actor);
     println!("  Technician: {}", technician_username);
-    println!("  Minutes: {}", minutes);
+    println!("  Minutes: {}", duration.to_minutes());
     println!("  Job ID: {}", job_id);
     println!("  Status: ASSIGNED");
 
@@ -349,16 +355,14 @@ let jobs: Vec<(i64, String, String, i64, String, String, String)> = {
     // Select by number
     print!("\nEnter number to start (or blank to abort): ");
     io::stdout().flush().ok();
-    let mut sel = String::new();
-    io::stdin().read_line(&mut sel).ok();
+    let Some(sel) = prompt_input() else { return Ok(()); };
     let s = sel.trim();
     if s.is_empty() {
         println!("Aborted.");
         wait_for_enter();
         return Ok(());
     }
-    let idx = s.parse::<usize>().unwrap_or(0);
-    let Some(job) = jobs.get(idx.saturating_sub(1)) else {
+    let Some(job) = parse_menu_index(s, jobs.len()).and_then(|i| jobs.get(i)) else {
         println!("Invalid selection.");
         wait_for_enter();
         return Ok(());
@@ -378,7 +382,7 @@ let jobs: Vec<(i64, String, String, i64, String, String, String)> = {
     println!("\nSecurity check for technician '{}':", tech_username);
     print!("Enter your technician password: ");
     io::stdout().flush().ok();
-    let pw_in = Zeroizing::new(read_password()?);
+    let pw_in = auth::read_secret()?;
     let pw_trimmed = pw_in.trim_end_matches(['\r', '\n']);
 
     let stored_hash_opt: Option<String> = conn
@@ -411,4 +415,87 @@ let jobs: Vec<(i64, String, String, i64, String, String, String)> = {
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+
+
+// ======================================================
+//                 JOB EXPORT (CSV)
+// ======================================================
+
+const JOB_CSV_HEADER: &str = "job_id,homeowner,technician,status,access_minutes,grant_start,grant_expires,job_desc,notes";
+
+// RFC 4180 quoting: wrap fields holding separators or quotes, doubling inner quotes
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/* Writes the jobs `username` may see to `path`: a technician gets their own jobs,
+   a homeowner every job under them. Returns the number of job rows written. */
+pub fn export_technician_jobs_csv(conn: &Connection, username: &str, path: &Path) -> Result<usize> {
+    let role: Option<String> = conn
+        .query_row(
+            "SELECT user_status FROM users WHERE username = ?1 COLLATE NOCASE",
+            params![username],
+            |r| r.get(0),
+        )
+        .optional()?;
+
+    let filter_column = match role.as_deref() {
+        Some("technician") => "technician_username",
+        Some("homeowner") => "homeowner_username",
+        _ => return Err(anyhow!("Only technicians and homeowners can export job records.")),
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT job_id, homeowner_username, technician_username, status, access_minutes,
+                grant_start, grant_expires, job_desc, COALESCE(notes, '')
+           FROM technician_jobs
+          WHERE {} = ?1 COLLATE NOCASE
+          ORDER BY job_id",
+        filter_column
+    ))?;
+
+    let mut out = String::from(JOB_CSV_HEADER);
+    out.push('\n');
+    let mut count = 0;
+    let mut rows = stmt.query(params![username])?;
+    while let Some(r) = rows.next()? {
+        let fields = [
+            r.get::<_, i64>(0)?.to_string(),
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+            r.get::<_, String>(3)?,
+            r.get::<_, i64>(4)?.to_string(),
+            r.get::<_, String>(5)?,
+            r.get::<_, String>(6)?,
+            r.get::<_, String>(7)?,
+            r.get::<_, String>(8)?,
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+        count += 1;
+    }
+
+    fs::write(path, out).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(count)
+}
+
+// Menu wrapper: asks for a file name and reports how many jobs were written
+pub fn export_jobs_menu(conn: &Connection, username: &str) -> Result<()> {
+    let default_path = format!("technician_jobs_{}.csv", username);
+    print!("Export file [{}]: ", default_path);
+    io::stdout().flush().ok();
+    let Some(input) = prompt_input() else { return Ok(()); };
+    let path = if input.trim().is_empty() { default_path } else { input.trim().to_string() };
+
+    match export_technician_jobs_csv(conn, username, Path::new(&path)) {
+        Ok(n) => println!("✅ Exported {} job record(s) to {}.", n, path),
+        Err(e) => println!("❌ Export failed: {}", e),
+    }
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use colored::*;
+use rusqlite::Connection;
 
+use crate::menu_config::{self, MenuItem};
 
-pub fn front_page_ui() {
+
+pub fn front_page_ui(conn: &Connection) {
     // colors definition
     let roof_color = Color::Magenta;
     let wall_color = Color::BrightGreen;
@@ -62,17 +65,23 @@ pub fn front_page_ui() {
     // Base
     println!("{}{}", pad, "|_____________________|".color(wall_color));
 
+    // Quick status from the snapshot row; a read failure just leaves it out
+    if let Ok(summary) = crate::db::front_page_summary(conn) {
+        println!();
+        println!("    {}", summary.color(label_color));
+    }
+
     // Menu section
     println!();
     println!(
         "{}{}",
         "    ",
-        "[1] User Login   [2] Guest Login   [3] About Application   [4] Exit"
+        "[1] User Login  [2] Guest Login  [3] About  [4] Exit  [5] I'm Locked Out"
             .color(menu_color)
             .bold()
     );
     println!(); // add an extra blank line for readability
-    print!("{}", "Select an option [1-4]: ".bold().color(Color::Cyan));
+    print!("{}", "Select an option [1-5]: ".bold().color(Color::Cyan));
 }
 
 pub fn user_login_ui() {
@@ -84,7 +93,39 @@ pub fn user_login_ui() {
     println!("{}{}", port_spc, port_bar.color(Color::BrightGreen));
 }
 
-pub fn homeowner_ui() {
+// Pinned quick actions shown above the role menu, e.g. [X] Apply Night profile
+pub fn shortcuts_ui(shortcuts: &[(&str, &str)]) {
+    if shortcuts.is_empty() {
+        return;
+    }
+    let spacing2 = " ".repeat(8);
+    let line = shortcuts
+        .iter()
+        .map(|(key, label)| format!("[{}] {}", key, label))
+        .collect::<Vec<_>>()
+        .join("  |  ");
+    println!("{}{}", spacing2, format!("⭐ {}", line).color(Color::BrightGreen));
+}
+
+// Two items per row, "[k] label" with the left column padded to `width`;
+// quick actions come first, one per row
+fn menu_rows_ui(spacing: &str, items: &[MenuItem], width: usize) {
+    let (quick, items): (Vec<MenuItem>, Vec<MenuItem>) =
+        items.iter().copied().partition(|i| menu_config::QUICK_ACTIONS.contains(&i.action));
+    for item in &quick {
+        println!("{}{}", spacing, format!("[{}] {}", item.key, item.label).color(Color::BrightGreen));
+    }
+    for pair in items.chunks(2) {
+        let left = format!("[{}] {}", pair[0].key, pair[0].label);
+        let line = match pair.get(1) {
+            Some(right) => format!("{:<width$}|  [{}] {}", left, right.key, right.label),
+            None => left,
+        };
+        println!("{}{}", spacing, line.color(Color::White));
+    }
+}
+
+pub fn homeowner_ui(items: &[MenuItem]) {
     let bar_color = Color::Magenta;
     let menu_bar = "=".repeat(46);
     let menu_spc = " ".repeat(11);
@@ -95,20 +136,15 @@ pub fn homeowner_ui() {
     println!("{}{}{}", spacing1, menu_spc, "HOMEOWNER MAIN MENU".bold().color(Color::BrightYellow));
     println!("{}{}", spacing1, menu_bar.color(bar_color));
 
-    println!("{}{}", spacing2, "[1] View profile              |  [6] View System Status".color(Color::White));
-    println!("{}{}", spacing2, "[2] Manage Guests             |  [7] Profile Settings".color(Color::White));
-    println!("{}{}", spacing2, "[3] Indoor Sensing            |  [8] Energy Usage".color(Color::White));
-    println!("{}{}", spacing2, "[4] Outdoor Weather           |  [9] Energy Comparison".color(Color::White));
-    println!("{}{}", spacing2, "[5] HVAC Control              |  ".color(Color::White));
-    println!("{}{}", spacing2, "[A] Request a Technician      |  [B] View Active Grants".color(Color::White));
+    menu_rows_ui(&spacing2, items, 30);
     println!("{}{}", spacing2, "              [0] Log out".color(Color::Red));
     
      println!(); 
-    print!("{}", "Select an option [0-9, A-B]: ".bold().color(Color::Cyan));
+    print!("{}", format!("Select an option [{}]: ", menu_config::menu_prompt_keys(items, "homeowner")).bold().color(Color::Cyan));
 
 }
 
-pub fn admin_ui() {
+pub fn admin_ui(items: &[MenuItem]) {
 let bar_color = Color::Red;
     let menu_bar = "=".repeat(48);
     let menu_spc = " ".repeat(14);
@@ -119,17 +155,14 @@ let bar_color = Color::Red;
     println!("{}{}{}", spacing1, menu_spc, "ADMIN MAIN MENU".bold().color(Color::BrightYellow));
     println!("{}{}", spacing1, menu_bar.color(bar_color));
 
-    println!("{}{}", spacing2, "[1] Show my profile           |  [5] View security logs".color(Color::White));
-    println!("{}{}", spacing2, "[2] Register a user           |  [6] Clear user lockouts".color(Color::White));
-    println!("{}{}", spacing2, "[3] View user(s)              |".color(Color::White));
-    println!("{}{}", spacing2, "[4] Manage Users              |".color(Color::White));
+    menu_rows_ui(&spacing2, items, 30);
     println!("{}{}", spacing2, "              [0] Log out".color(Color::Red));
 
     println!(); // add an extra blank line for readability
-    print!("{}", "Select an option [0-6]: ".bold().color(Color::Cyan));
+    print!("{}", format!("Select an option [{}]: ", menu_config::menu_prompt_keys(items, "admin")).bold().color(Color::Cyan));
 }
 
-pub fn technician_ui(){
+pub fn technician_ui(items: &[MenuItem]) {
     let bar_color = Color::Red;
     let menu_bar = "=".repeat(46);
     let spacing1 = " ".repeat(7);
@@ -140,18 +173,14 @@ pub fn technician_ui(){
     println!("{}{}{}", spacing1, menu_spc, "TECHNICIAN MAIN MENU".bold().color(Color::BrightYellow));
     println!("{}{}", spacing1, menu_bar.color(bar_color));
 
-    println!("{}{}", spacing2, "[1] Show my profile   |  [6] View System Status".color(Color::White));
-    println!("{}{}", spacing2, "[2] View all jobs     |  [7] Indoor sensing".color(Color::White));
-    println!("{}{}", spacing2, "[3] Access job        |  [8] Outdoor weather".color(Color::White));
-    println!("{}{}", spacing2, "[4] Manage guest(s)   |  [9] Profile settings".color(Color::White));
-    println!("{}{}", spacing2, "[5] Run diagnostics   |".color(Color::White));
+    menu_rows_ui(&spacing2, items, 22);
     println!("{}{}", spacing2, "              [0] Log out".color(Color::Red));
     
     println!(); // add an extra blank line for readability
-    print!("{}", "Select an option [0-9]: ".bold().color(Color::Cyan));
+    print!("{}", format!("Select an option [{}]: ", menu_config::menu_prompt_keys(items, "technician")).bold().color(Color::Cyan));
 }
 
-pub fn guest_ui() {
+pub fn guest_ui(items: &[MenuItem]) {
     let bar_color = Color::BrightBlue;
     let menu_bar = "=".repeat(46);
     let menu_spc = " ".repeat(14);
@@ -162,15 +191,13 @@ pub fn guest_ui() {
     println!("{}{}{}", spacing1, menu_spc, "GUEST MAIN MENU".bold().color(Color::BrightYellow));
     println!("{}{}", spacing1, menu_bar.color(bar_color));
 
-    println!("{}{}", spacing2, "[1] View Profile".color(Color::White));
-    println!("{}{}", spacing2, "[2] Indoor Sensor".color(Color::White));
-    println!("{}{}", spacing2, "[3] Outdoor Weather".color(Color::White));
-    println!("{}{}", spacing2, "[4] HVAC Control".color(Color::White));
-    println!("{}{}", spacing2, "[5] Choose Profile".color(Color::White));
+    for item in items {
+        println!("{}{}", spacing2, format!("[{}] {}", item.key, item.label).color(Color::White));
+    }
     println!("{}{}", spacing2, "[0] Log out".color(Color::Red));
 
     println!(); // add an extra blank line for readability
-    print!("{}", "Select an option [0-5]: ".bold().color(Color::Cyan));
+    print!("{}", format!("Select an option [{}]: ", menu_config::menu_prompt_keys(items, "guest")).bold().color(Color::Cyan));
     
 }
 
@@ -196,10 +223,13 @@ pub fn manage_guest_menu() {
     println!("{}{}", spacing2, "[3] Reset Guest Pin".color(text_color));
     println!("{}{}", spacing2, "[4] Enable/Disable Guest Account".color(text_color));
     println!("{}{}", spacing2, "[5] Delete Guest Account".color(text_color));
+    println!("{}{}", spacing2, "[T] Guest Temperature Limits".color(text_color));
+    println!("{}{}", spacing2, "[L] Lock/Unlock Guest HVAC Changes".color(text_color));
+    println!("{}{}", spacing2, "[N] Guest Login Notices On/Off".color(text_color));
     println!("{}{}", spacing2, "[6] Return to User Menu".color(text_color));
 
     println!();
-    print!("{}","Select an option [1-4]: ".bold().color(Color::Cyan));
+    print!("{}","Select an option [1-6, L, N, T]: ".bold().color(Color::Cyan));
     std::io::stdout().flush().expect("Failed to flush stdout");
 
 }
@@ -252,25 +282,35 @@ pub fn hvac_control_ui(user_role: &str) {
     
     println!("{}{}", spacing2, "[1] Change Mode (Heat/Cool/Auto/Fan/Off)".color(text_color));
     println!("{}{}", spacing2, "[2] View Current Status".color(text_color));
+    println!("{}{}", spacing2, "[H] Hold Temperature For a While".color(text_color));
+    println!("{}{}", spacing2, "[F] Fan Boost For a While".color(text_color));
+    println!("{}{}", spacing2, "[U] Upcoming Schedule".color(text_color));
+    println!("{}{}", spacing2, "[P] Toggle Home/Away".color(text_color));
     
     // Different menu options based on user role
     if user_role == "homeowner" {
         // Homeowners: Choose Profile option
+        println!("{}{}", spacing2, "[B] Comfort Band".color(text_color));
+        println!("{}{}", spacing2, "[E] Emergency All-Off".color(Color::Red));
+        println!("{}{}", spacing2, "[C] Cool-down Report".color(text_color));
         println!("{}{}", spacing2, "[3] Choose Profile".color(text_color));
         println!("{}{}", spacing2, "[4] Return to Main Menu".color(text_color));
         println!();
-        print!("{}","Select an option [1-4]: ".bold().color(Color::Cyan));
+        print!("{}","Select an option [1-4, B, C, E, F, H, P, U]: ".bold().color(Color::Cyan));
     } else if user_role == "guest" {
         // Guests: No option 3 (already have Choose Profile in main menu)
         println!("{}{}", spacing2, "[3] Return to Main Menu".color(text_color));
         println!();
-        print!("{}","Select an option [1-3]: ".bold().color(Color::Cyan));
+        print!("{}","Select an option [1-3, F, H, P, U]: ".bold().color(Color::Cyan));
     } else {
         // Technicians only: Include diagnostics
+        println!("{}{}", spacing2, "[B] Comfort Band".color(text_color));
+        println!("{}{}", spacing2, "[E] Emergency All-Off".color(Color::Red));
+        println!("{}{}", spacing2, "[C] Cool-down Report".color(text_color));
         println!("{}{}", spacing2, "[3] Run Diagnostics".color(text_color));
         println!("{}{}", spacing2, "[4] Return to Main Menu".color(text_color));
         println!();
-        print!("{}","Select an option [1-4]: ".bold().color(Color::Cyan));
+        print!("{}","Select an option [1-4, B, C, E, F, H, P, U]: ".bold().color(Color::Cyan));
     }
 }
 
@@ -291,13 +331,14 @@ pub fn profile_selection_ui(profiles: &[crate::db::ProfileRow]) {
     // Display all profiles dynamically
     for (idx, profile) in profiles.iter().enumerate() {
         let description = profile.description.as_deref().unwrap_or("Custom profile");
-        println!("{}{}", spacing2, format!("[{}] {} - {}", idx + 1, profile.name, description).color(text_color));
+        println!("{}{}", spacing2, format!("[{}] {} {} - {}", idx + 1, crate::db::profile_icon(profile), profile.name, description).color(text_color));
     }
     
+    println!("{}{}", spacing2, "[M] Filter by mode".color(text_color));
     println!("{}{}", spacing2, "[0] Cancel".color(Color::Red));
 
     println!();
-    print!("{}", format!("Select a profile [0-{}]: ", profiles.len()).bold().color(Color::Cyan));
+    print!("{}", format!("Select a profile [0-{}, M]: ", profiles.len()).bold().color(Color::Cyan));
 }
 
 
@@ -0,0 +1,134 @@
+// ======================================================
+//                  TEMPERATURE UNITS
+// ======================================================
+// Everything stored (DB, HVAC state, profiles) is Celsius.
+// Fahrenheit only exists at display time, via the helpers below.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TempUnit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TempUnit::Celsius => "°C",
+            TempUnit::Fahrenheit => "°F",
+        }
+    }
+
+    // Stored form in user_preferences.temp_unit
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TempUnit::Celsius => "celsius",
+            TempUnit::Fahrenheit => "fahrenheit",
+        }
+    }
+}
+
+pub fn celsius_to_fahrenheit(celsius: f32) -> f32 {
+    (celsius * 9.0 / 5.0) + 32.0
+}
+
+pub fn fahrenheit_to_celsius(fahrenheit: f32) -> f32 {
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+// A stored Celsius value in `unit`, unrounded
+pub fn from_celsius(celsius: f32, unit: TempUnit) -> f32 {
+    match unit {
+        TempUnit::Celsius => celsius,
+        TempUnit::Fahrenheit => celsius_to_fahrenheit(celsius),
+    }
+}
+
+// A value typed in `unit` back to Celsius, the only unit anything is validated in
+pub fn to_celsius(value: f32, unit: TempUnit) -> f32 {
+    match unit {
+        TempUnit::Celsius => value,
+        TempUnit::Fahrenheit => fahrenheit_to_celsius(value),
+    }
+}
+
+/* A typed number with either "." or "," as the decimal separator, so "22,5"
+   and "22.5" are both 22.5. Input with both separators is ambiguous and
+   rejected. Output is unaffected: Rust's {:.1} always writes ".". */
+pub fn parse_decimal(input: &str) -> Option<f32> {
+    let input = input.trim();
+    if input.contains(',') && input.contains('.') {
+        return None;
+    }
+    input.replacen(',', ".", 1).parse::<f32>().ok().filter(|v| v.is_finite())
+}
+
+// User input in `unit`, as Celsius; None when it is not a number
+pub fn parse_temp_input(input: &str, unit: TempUnit) -> Option<f32> {
+    parse_decimal(input).map(|v| to_celsius(v, unit))
+}
+
+// A Celsius range as whole degrees in `unit`, e.g. "18-28°C" or "65-82°F".
+// The ends are rounded inward so every number shown is accepted.
+pub fn format_temp_range(min_c: f32, max_c: f32, unit: TempUnit) -> String {
+    let min = (from_celsius(min_c, unit) - 1e-3).ceil();
+    let max = (from_celsius(max_c, unit) + 1e-3).floor();
+    format!("{:.0}-{:.0}{}", min, max, unit.symbol())
+}
+
+pub fn mph_to_kmh(mph: f64) -> f64 {
+    mph * 1.609344
+}
+
+// Round to one decimal place, the precision every screen shows
+pub fn round1(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+pub const DEFAULT_TEMP_PRECISION: u8 = 1;
+
+// Decimals shown to the signed-in user. The CLI runs one session per process
+// (see auth::ACTIVE_SESSION), so a process-wide value is enough.
+static TEMP_PRECISION: AtomicU8 = AtomicU8::new(DEFAULT_TEMP_PRECISION);
+
+// 0 = whole degrees, 1 = one decimal; anything else is treated as 1
+pub fn set_temp_precision(precision: u8) {
+    TEMP_PRECISION.store(precision.min(1), Ordering::Relaxed);
+}
+
+pub fn temp_precision() -> u8 {
+    TEMP_PRECISION.load(Ordering::Relaxed)
+}
+
+// A stored Celsius value shown in the chosen unit at the session's precision, e.g. "21.5°C" or "22°C"
+pub fn format_temp(celsius: f32, unit: TempUnit) -> String {
+    format_temp_with(celsius, unit, temp_precision())
+}
+
+// Same as format_temp with an explicit precision; display only, nothing stored is rounded
+pub fn format_temp_with(celsius: f32, unit: TempUnit, precision: u8) -> String {
+    let value = from_celsius(celsius, unit);
+    match precision {
+        0 => format!("{:.0}{}", value.round() + 0.0, unit.symbol()), // + 0.0 turns -0 into 0
+        _ => format!("{:.1}{}", round1(value), unit.symbol()),
+    }
+}
+
+// Both units with the preferred one first, e.g. "21.5°C / 70.7°F"
+pub fn format_temp_dual(celsius: f32, preferred: TempUnit) -> String {
+    let other = match preferred {
+        TempUnit::Celsius => TempUnit::Fahrenheit,
+        TempUnit::Fahrenheit => TempUnit::Celsius,
+    };
+    format!("{} / {}", format_temp(celsius, preferred), format_temp(celsius, other))
+}
+
+// Wind follows the temperature preference: km/h alongside °C, mph alongside °F
+pub fn format_wind(mph: f64, unit: TempUnit) -> String {
+    match unit {
+        TempUnit::Celsius => format!("{:.1} km/h", mph_to_kmh(mph)),
+        TempUnit::Fahrenheit => format!("{:.1} mph", mph),
+    }
+}
@@ -1,10 +1,13 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
 use chrono_tz::America::New_York;
 use reqwest::blocking::Client;
 use reqwest::redirect::Policy;
+use crate::units::{self, celsius_to_fahrenheit, TempUnit};
+use crate::hvac::HVACMode;
 use serde::Deserialize;
 use rusqlite::Connection;
+use std::path::PathBuf;
 use crate::db;
 
 #[derive(Debug, Deserialize)]
@@ -28,6 +31,7 @@ pub struct Measurement {
     pub value: Option<f64>,
 }
 
+#[derive(Debug, Deserialize)]
 pub struct WeatherRecord {
     pub time: String,
     pub temperature_f: Option<f64>,
@@ -40,24 +44,164 @@ pub struct WeatherRecord {
     pub condition: String,
 }
 
-pub fn fetch_weather() -> Result<WeatherRecord> {
+// ======================================================
+//                    WEATHER SOURCES
+// ======================================================
 
-    let station = "KNYC"; // Central Park station (near CCNY)
-    let url = format!("https://api.weather.gov/stations/{}/observations/latest", station);
+// Point this at a JSON WeatherRecord to run without internet access
+pub const WEATHER_FILE_ENV: &str = "SMART_THERMOSTAT_WEATHER_FILE";
+
+pub trait WeatherSource {
+    fn fetch(&self) -> Result<WeatherRecord>;
+}
+
+// Live observations from api.weather.gov for the configured location
+pub struct HttpSource {
+    pub location: WeatherLocation,
+}
+
+impl WeatherSource for HttpSource {
+    fn fetch(&self) -> Result<WeatherRecord> {
+        fetch_weather_at(&self.location)
+    }
+}
+
+// A saved observation on disk, for offline use and tests
+pub struct FileSource {
+    pub path: PathBuf,
+}
+
+impl WeatherSource for FileSource {
+    fn fetch(&self) -> Result<WeatherRecord> {
+        let raw = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("reading weather file {}", self.path.display()))?;
+        serde_json::from_str(&raw)
+            .with_context(|| format!("parsing weather file {}", self.path.display()))
+    }
+}
+
+// File source when SMART_THERMOSTAT_WEATHER_FILE is set, otherwise HTTP at the saved location
+pub fn configured_source(conn: &Connection) -> Box<dyn WeatherSource> {
+    match std::env::var(WEATHER_FILE_ENV) {
+        Ok(path) if !path.trim().is_empty() => Box::new(FileSource { path: PathBuf::from(path.trim()) }),
+        _ => Box::new(HttpSource { location: db::weather_location(conn).unwrap_or_default() }),
+    }
+}
 
-    let client = Client::builder()
+// Fetch from the given source and keep a copy in the weather table
+pub fn fetch_and_store(conn: &mut Connection, source: &dyn WeatherSource) -> Result<WeatherRecord> {
+    let data = source.fetch()?;
+    db::insert_weather(conn, &data)?;
+    Ok(data)
+}
+
+// ======================================================
+//                   WEATHER LOCATION
+// ======================================================
+
+pub const DEFAULT_STATION: &str = "KNYC"; // Central Park station (near CCNY)
+pub const DEFAULT_LATITUDE: f64 = 40.7789;
+pub const DEFAULT_LONGITUDE: f64 = -73.9692;
+
+// Where observations come from. A station ID wins; without one the
+// nearest station to the coordinates is looked up on each fetch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub station: Option<String>,
+}
+
+impl Default for WeatherLocation {
+    fn default() -> Self {
+        Self {
+            latitude: DEFAULT_LATITUDE,
+            longitude: DEFAULT_LONGITUDE,
+            station: Some(DEFAULT_STATION.to_string()),
+        }
+    }
+}
+
+pub fn validate_coordinates(latitude: f64, longitude: f64) -> Result<()> {
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(anyhow!("Latitude must be between -90 and 90"));
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        return Err(anyhow!("Longitude must be between -180 and 180"));
+    }
+    Ok(())
+}
+
+// Station IDs are short uppercase codes such as KNYC or KLGA
+pub fn validate_station(station: &str) -> Result<()> {
+    if !(3..=10).contains(&station.len()) || !station.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+        return Err(anyhow!("Station ID must be 3-10 uppercase letters or digits (e.g. KNYC)"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct StationList {
+    features: Vec<StationFeature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StationFeature {
+    properties: StationProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct StationProperties {
+    stationIdentifier: String,
+}
+
+fn http_client() -> Result<Client> {
+    Client::builder()
         .timeout(std::time::Duration::from_secs(10))
         .redirect(Policy::none())
         .build()
-        .context("building http client")?;
+        .context("building http client")
+}
+
+fn user_agent() -> String {
+    std::env::var("WEATHER_USER_AGENT")
+        .unwrap_or_else(|_| "ccny-weather-bot (your_email@example.com)".into())
+}
+
+// Nearest observation station to a point (api.weather.gov lists them closest first)
+fn nearest_station(client: &Client, latitude: f64, longitude: f64) -> Result<String> {
+    let url = format!("https://api.weather.gov/points/{:.4},{:.4}/stations", latitude, longitude);
+    let list = client
+        .get(&url)
+        .header("User-Agent", user_agent())
+        .send()
+        .context("station lookup failed")?
+        .json::<StationList>()
+        .context("station list parse failed")?;
+    list.features
+        .into_iter()
+        .next()
+        .map(|f| f.properties.stationIdentifier)
+        .ok_or_else(|| anyhow!("No weather station found near {:.4}, {:.4}", latitude, longitude))
+}
+
+// Observations from the default location
+pub fn fetch_weather() -> Result<WeatherRecord> {
+    fetch_weather_at(&WeatherLocation::default())
+}
+
+pub fn fetch_weather_at(location: &WeatherLocation) -> Result<WeatherRecord> {
+    let client = http_client()?;
+    let station = match &location.station {
+        Some(station) => station.clone(),
+        None => nearest_station(&client, location.latitude, location.longitude)?,
+    };
+    let url = format!("https://api.weather.gov/stations/{}/observations/latest", station);
 
     let resp = client
         .get(&url)
-        .header(
-            "User-Agent",
-            std::env::var("WEATHER_USER_AGENT")
-                .unwrap_or_else(|_| "ccny-weather-bot (your_email@example.com)".into()),
-        )
+        .header("User-Agent", user_agent())
         .send()
         .context("HTTP request failed")?
         .json::<ObservationResponse>()
@@ -76,8 +220,8 @@ pub fn fetch_weather() -> Result<WeatherRecord> {
         .take(200)
         .collect::<String>();
 
-    let temp_f = temp_c.map(|c| c * 9.0 / 5.0 + 32.0);
-    let dew_f = dew_c.map(|c| c * 9.0 / 5.0 + 32.0);
+    let temp_f = temp_c.map(|c| celsius_to_fahrenheit(c as f32) as f64);
+    let dew_f = dew_c.map(|c| celsius_to_fahrenheit(c as f32) as f64);
     let wind_mph = wind_ms.map(|m| m * 2.23694);
 
     let now_local: DateTime<chrono_tz::Tz> = Utc::now().with_timezone(&New_York);
@@ -96,27 +240,64 @@ pub fn fetch_weather() -> Result<WeatherRecord> {
     })
 }
 
-pub fn get_current_weather(conn: &mut Connection) -> Result<()> {
-    let data = fetch_weather()?;
+pub fn get_current_weather(conn: &mut Connection, username: &str) -> Result<()> {
+    let source = configured_source(conn);
+    let data = fetch_and_store(conn, source.as_ref())?;
+    let unit = db::get_temp_unit(conn, username).unwrap_or_default();
 
     println!("🌈✨=============================================✨🌈");
-    println!("🌤️  Condition: {}", data.condition);
-    println!(
-        "🌡️  Temperature: {:.1}°F / {:.1}°C",
-        data.temperature_f.unwrap_or(0.0),
-        data.temperature_c.unwrap_or(0.0)
-    );
-    println!(
-        "💧  Dewpoint: {:.1}°F / {:.1}°C",
-        data.dewpoint_f.unwrap_or(0.0),
-        data.dewpoint_c.unwrap_or(0.0)
-    );
-    println!("💦  Humidity: {:.1}%", data.humidity.unwrap_or(0.0));
-    println!("💨  Wind Speed: {:.1} mph", data.wind_speed_mph.unwrap_or(0.0));
-    println!("🧭  Wind Direction: {:.1}°", data.wind_direction_deg.unwrap_or(0.0));
-    println!("🕒  Time: {}", data.time);
+    println!("{}", render_weather(&data, unit));
+    if let (Some(outdoor_c), Ok((mode, target, _, _))) = (data.temperature_c, db::get_hvac_state(conn))
+        && let Some(advice) = HVACMode::from_name(&mode).and_then(|m| outdoor_advice(outdoor_c as f32, m, target))
+    {
+        println!();
+        println!("💡  {}", advice);
+    }
     println!("🌈✨=============================================✨🌈");
-
-    db::insert_weather(conn, &data)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+// Advisory only: flags when the outdoor air alone would get the house to target
+pub fn outdoor_advice(outdoor_c: f32, mode: HVACMode, target_c: f32) -> Option<String> {
+    let outside = units::format_temp(outdoor_c, TempUnit::Celsius);
+    let target = units::format_temp(target_c, TempUnit::Celsius);
+    match mode {
+        HVACMode::Cooling if outdoor_c < target_c => Some(format!(
+            "It's {} outside and you're cooling to {} — consider Fan Only or opening a window.",
+            outside, target
+        )),
+        HVACMode::Heating if outdoor_c > target_c => Some(format!(
+            "It's {} outside and you're heating to {} — consider turning the heat off and opening a window.",
+            outside, target
+        )),
+        _ => None,
+    }
+}
+
+/* One outdoor temperature to a decimal place, keeping the minus sign below zero
+   (-20.0°C, -4.0°F). A missing reading shows as N/A rather than a made-up 0.0,
+   and a value that rounds to zero prints as 0.0, never -0.0. */
+pub fn format_outdoor_temp(value: Option<f64>, unit: TempUnit) -> String {
+    match value {
+        Some(v) if v.is_finite() => format!("{:.1}{}", (v * 10.0).round() / 10.0 + 0.0, unit.symbol()),
+        _ => "N/A".to_string(),
+    }
+}
+
+// Weather body in the user's units; temperatures come straight from the stored _c/_f fields
+pub fn render_weather(data: &WeatherRecord, unit: TempUnit) -> String {
+    let (temp, dew) = match unit {
+        TempUnit::Celsius => (data.temperature_c, data.dewpoint_c),
+        TempUnit::Fahrenheit => (data.temperature_f, data.dewpoint_f),
+    };
+    [
+        format!("🌤️  Condition: {}", data.condition),
+        format!("🌡️  Temperature: {}", format_outdoor_temp(temp, unit)),
+        format!("💧  Dewpoint: {}", format_outdoor_temp(dew, unit)),
+        format!("💦  Humidity: {:.1}%", data.humidity.unwrap_or(0.0)),
+        format!("💨  Wind Speed: {}", units::format_wind(data.wind_speed_mph.unwrap_or(0.0), unit)),
+        format!("🧭  Wind Direction: {:.1}°", data.wind_direction_deg.unwrap_or(0.0)),
+        format!("🕒  Time: {}", data.time),
+    ]
+    .join("\n")
+}